@@ -2,9 +2,13 @@ use core::fmt;
 use std::{fmt::Display, sync::Arc};
 
 use log::error;
-use tokio::{signal, sync::Mutex, task};
+use tokio::{signal, task};
 
-use crate::service::{OverallStatus, Service, ServiceManager, ServiceManagerBuilder};
+use crate::{
+    config::FileConfig,
+    event::{EventBus, EventInfo},
+    service::{BuildError, EscalationPolicy, ServiceHandle, ServiceManager, ServiceManagerBuilder},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum ExitReason {
@@ -24,41 +28,76 @@ impl Display for ExitReason {
 pub struct BotBuilder {
     name: String,
     service_manager: ServiceManagerBuilder,
+    event_bus: Option<Arc<EventBus>>,
 }
 
 impl BotBuilder {
     pub fn new(name: &str) -> Self {
         Self {
             name: name.to_string(),
-            service_manager: ServiceManager::builder(),
+            service_manager: ServiceManager::builder().with_bot_name(name),
+            event_bus: None,
         }
     }
 
-    pub async fn with_service(mut self, service: Arc<Mutex<dyn Service>>) -> Self {
-        self.service_manager = self.service_manager.with_service(service).await; // The ServiceManagerBuilder itself will warn when adding a service multiple times
+    pub async fn with_service(mut self, service: impl Into<ServiceHandle>) -> Self {
+        self.service_manager = self.service_manager.with_service(service).await; // The ServiceManagerBuilder itself will reject adding a service multiple times
 
         self
     }
 
-    pub async fn with_services(mut self, services: Vec<Arc<Mutex<dyn Service>>>) -> Self {
-        for service in services {
-            self.service_manager = self.service_manager.with_service(service).await;
-        }
+    pub async fn with_services<I>(mut self, services: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<ServiceHandle>,
+    {
+        self.service_manager = self.service_manager.with_services(services).await;
+
+        self
+    }
+
+    pub fn with_escalation_policy(mut self, escalation_policy: EscalationPolicy) -> Self {
+        self.service_manager = self
+            .service_manager
+            .with_escalation_policy(escalation_policy);
 
         self
     }
 
-    pub async fn build(self) -> Bot {
-        Bot {
+    pub fn with_config(mut self, config: Arc<FileConfig>) -> Self {
+        self.service_manager = self.service_manager.with_config(config);
+
+        self
+    }
+
+    pub fn with_timeouts_from_config(mut self, config: &FileConfig) -> Self {
+        self.service_manager = self.service_manager.with_timeouts_from_config(config);
+
+        self
+    }
+
+    // For sharing a bus that was created before the bot, e.g. to hand it to services
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+
+        self
+    }
+
+    pub async fn build(self) -> Result<Bot, BuildError> {
+        Ok(Bot {
             name: self.name,
-            service_manager: self.service_manager.build().await,
-        }
+            service_manager: self.service_manager.build().await?,
+            event_bus: self.event_bus.unwrap_or_default(),
+        })
     }
 }
 
 pub struct Bot {
     pub name: String,
     pub service_manager: Arc<ServiceManager>,
+
+    // The bot-scoped registry of shared events
+    pub event_bus: Arc<EventBus>,
 }
 
 impl Bot {
@@ -76,6 +115,15 @@ impl Bot {
         //TODO: Potential for further deinitialization here, like modules
     }
 
+    // What events exist on the bot's bus and who listens to them
+    pub async fn introspect(&self) -> Vec<EventInfo> {
+        self.event_bus.introspect().await
+    }
+
+    /*
+        Returns on SIGINT, or when the ServiceManager requests a shutdown because an essential service failed.
+        An Unhealthy overall status alone doesn't end the bot, so EscalationPolicy::Ignore and StopDependents keep it running.
+    */
     pub async fn join(&self) -> ExitReason {
         let name_clone = self.name.clone();
         let signal_task = tokio::spawn(async move {
@@ -97,26 +145,20 @@ impl Bot {
         };
         let subscriber_name = format!("Bot join on task {}", task_id);
 
-        let service_manager_clone = self.service_manager.clone();
-        let (_, mut receiver) = self
+        // The ServiceManager's escalation policy decides whether an essential service failure ends the bot
+        let (_subscription, mut receiver) = self
             .service_manager
-            .on_status_change
-            .event
+            .events()
+            .on_shutdown_requested
             .subscribe_channel(subscriber_name, 2, true, true)
             .await;
-        let status_task = tokio::spawn(async move {
-            let service_manager = service_manager_clone;
-            while (receiver.recv().await).is_some() {
-                let overall_status = service_manager.overall_status().await;
-                if overall_status == OverallStatus::Unhealthy {
-                    return;
-                }
-            }
+        let escalation_task = tokio::spawn(async move {
+            receiver.recv().await;
         });
 
         tokio::select! {
             _ = signal_task => ExitReason::SIGINT,
-            _ = status_task => ExitReason::EssentialServiceFailed,
+            _ = escalation_task => ExitReason::EssentialServiceFailed,
         }
     }
 }
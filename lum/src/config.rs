@@ -1,11 +1,25 @@
+pub mod config_format;
 pub mod config_handler;
+pub mod config_schema;
+pub mod config_watcher;
+pub mod encrypted_secrets;
 pub mod environment_config;
 pub mod file_config;
+pub mod layered_config;
+pub mod secret_reference;
 
+pub use config_format::ConfigFormat;
 pub use config_handler::{
     ConfigHandler, ConfigInitError, ConfigParseError, ConfigPathError, ConfigSaveError,
     EnvironmentConfigParseError, FileConfigParseError, Merge,
 };
 
+pub use config_schema::{ConfigSchema, ConfigViolation, SchemaField, ValueKind, ViolationKind};
+pub use config_watcher::{ConfigChanged, ConfigWatcher};
+#[cfg(feature = "encrypted-secrets")]
+pub use encrypted_secrets::SecretsKey;
+pub use encrypted_secrets::{ENCRYPTED_SECRETS_SECTION, EncryptedSecretsError};
 pub use environment_config::EnvironmentConfig;
-pub use file_config::FileConfig;
+pub use file_config::{ConfigSectionError, FileConfig, TimeoutConfig};
+pub use layered_config::{ConfigSource, LayeredConfig};
+pub use secret_reference::{SecretResolveError, resolve_secrets, resolve_secrets_with};
@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{ConfigSaveError, FileConfigParseError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ConfigFormat {
+    #[default]
+    Json,
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl ConfigFormat {
+    // In the order they are looked for when a config directory has several config files
+    #[cfg(not(feature = "yaml"))]
+    pub const ALL: &[ConfigFormat] = &[ConfigFormat::Json, ConfigFormat::Toml];
+    #[cfg(feature = "yaml")]
+    pub const ALL: &[ConfigFormat] = &[ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml];
+
+    // Files with an unknown extension are read as JSON, the original format
+    pub fn from_path(path: &Path) -> Self {
+        let extension = match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => extension,
+            None => return ConfigFormat::Json,
+        };
+
+        ConfigFormat::ALL
+            .iter()
+            .copied()
+            .find(|format| {
+                format
+                    .extensions()
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(extension))
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => "yaml",
+        }
+    }
+
+    // Every extension that is detected as this format
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ConfigFormat::Json => &["json"],
+            ConfigFormat::Toml => &["toml"],
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    // Written to new config files, so serde fills in the defaults
+    pub fn empty_document(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "{}",
+            ConfigFormat::Toml => "",
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => "{}",
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<Value, FileConfigParseError> {
+        match self {
+            ConfigFormat::Json => Ok(serde_json::from_str(content)?),
+            ConfigFormat::Toml => Ok(toml::from_str(content)?),
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => Ok(serde_yaml::from_str(content)?),
+        }
+    }
+
+    pub fn to_string_pretty<T>(&self, config: &T) -> Result<String, ConfigSaveError>
+    where
+        T: Serialize,
+    {
+        match self {
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(config)?),
+            ConfigFormat::Toml => Ok(toml::to_string_pretty(config)?),
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => Ok(serde_yaml::to_string(config)?),
+        }
+    }
+}
@@ -1,8 +1,25 @@
-use std::{fs, io, marker::PhantomData, path::PathBuf};
-
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use thiserror::Error;
 
+#[cfg(feature = "encrypted-secrets")]
+use super::SecretsKey;
+use super::{
+    ConfigFormat, ConfigSchema, ConfigSource, ConfigViolation, EncryptedSecretsError,
+    LayeredConfig, SecretResolveError,
+    encrypted_secrets::ENCRYPTED_SECRETS_SECTION,
+    layered_config::{join_key, merge_layer, normalize_key, record_changes, record_leaves},
+    secret_reference::{resolve_string, resolve_value},
+};
+
 pub trait Merge<T> {
     fn merge(&self, other: &T) -> Self;
 }
@@ -33,6 +50,13 @@ pub enum ConfigSaveError {
     #[error("Unable to serialize config: {0}")]
     Serde(#[from] serde_json::Error),
 
+    #[error("Unable to serialize config as TOML: {0}")]
+    Toml(#[from] toml::ser::Error),
+
+    #[cfg(feature = "yaml")]
+    #[error("Unable to serialize config as YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
     #[error("I/O error: {0}")]
     IO(#[from] io::Error),
 }
@@ -53,12 +77,22 @@ pub enum FileConfigParseError {
 
     #[error("Unable to serialize or deserialize config: {0}")]
     Serde(#[from] serde_json::Error),
+
+    #[error("Unable to parse TOML config: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[cfg(feature = "yaml")]
+    #[error("Unable to parse YAML config: {0}")]
+    Yaml(#[from] serde_yaml::Error),
 }
 
 #[derive(Debug, Error)]
 pub enum EnvironmentConfigParseError {
     #[error("Unable to parse environment variables: {0}")]
     Envy(#[from] serde_env::Error),
+
+    #[error("Unable to apply environment variable overrides: {0}")]
+    Override(#[from] serde_json::Error),
 }
 
 #[derive(Debug, Error)]
@@ -68,6 +102,25 @@ pub enum ConfigParseError {
 
     #[error("Unable to parse config from environment: {0}")]
     Env(#[from] EnvironmentConfigParseError),
+
+    #[error("Unable to build config from its sources: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Unable to resolve a secret reference: {0}")]
+    Secret(#[from] SecretResolveError),
+
+    #[error("Unable to decrypt the encrypted secrets: {0}")]
+    EncryptedSecret(#[from] EncryptedSecretsError),
+
+    #[error("Invalid config:{}", violation_list(.0))]
+    Invalid(Vec<ConfigViolation>),
+}
+
+fn violation_list(violations: &[ConfigViolation]) -> String {
+    violations
+        .iter()
+        .map(|violation| format!("\n  - {}", violation))
+        .collect()
 }
 
 #[derive(Debug)]
@@ -77,6 +130,11 @@ where
     ENV: Serialize + for<'de> Deserialize<'de>,
 {
     pub app_name: String,
+    config_file_path: Option<PathBuf>,
+    system_config_file_path: Option<PathBuf>,
+    schema: Option<ConfigSchema>,
+    #[cfg(feature = "encrypted-secrets")]
+    secrets_key: Option<SecretsKey>,
     _phantom_file: PhantomData<FILE>,
     _phantom_env: PhantomData<ENV>,
 }
@@ -89,11 +147,68 @@ where
     pub fn new(app_name: &str) -> Self {
         ConfigHandler {
             app_name: app_name.to_string(),
+            config_file_path: None,
+            system_config_file_path: None,
+            schema: None,
+            #[cfg(feature = "encrypted-secrets")]
+            secrets_key: None,
             _phantom_file: PhantomData,
             _phantom_env: PhantomData,
         }
     }
 
+    // Replaces the user config file in the OS-specific config directory
+    pub fn with_config_file_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file_path = Some(path.into());
+        self
+    }
+
+    pub fn with_system_config_file_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.system_config_file_path = Some(path.into());
+        self
+    }
+
+    // Checked when loading the layered config, see load_layered_config
+    pub fn with_schema(mut self, schema: ConfigSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    // Replaces the key from <APP_NAME>_SECRETS_KEY, e.g. with one read from the OS keyring
+    #[cfg(feature = "encrypted-secrets")]
+    pub fn with_secrets_key(mut self, key: SecretsKey) -> Self {
+        self.secrets_key = Some(key);
+        self
+    }
+
+    // The variable holding the base64 encoded key for the encryptedSecrets section
+    pub fn secrets_key_variable(&self) -> String {
+        format!("{}_SECRETS_KEY", self.app_name.to_uppercase())
+    }
+
+    #[cfg(feature = "encrypted-secrets")]
+    pub fn secrets_key(&self) -> Result<SecretsKey, EncryptedSecretsError> {
+        if let Some(key) = &self.secrets_key {
+            return Ok(key.clone());
+        }
+
+        let variable = self.secrets_key_variable();
+        match env::var(&variable) {
+            Ok(encoded) => SecretsKey::from_base64(&encoded),
+            Err(_) => Err(EncryptedSecretsError::MissingKey(variable)),
+        }
+    }
+
+    /*
+        Produces a ciphertext for the encryptedSecrets section, e.g.
+        { "encryptedSecrets": { "discordToken": "<encrypt_value("discordToken", token)>" } }
+        Non-string values are written as JSON, like environment overrides.
+    */
+    #[cfg(feature = "encrypted-secrets")]
+    pub fn encrypt_value(&self, key: &str, value: &str) -> Result<String, EncryptedSecretsError> {
+        self.secrets_key()?.encrypt(key, value)
+    }
+
     pub fn get_config_dir_path(&self) -> Result<PathBuf, ConfigPathError> {
         let mut path = match dirs::config_dir() {
             Some(path) => path,
@@ -105,27 +220,43 @@ where
     }
 
     pub fn create_config_dir_path(&self) -> Result<(), ConfigInitError> {
-        let path = self.get_config_dir_path()?;
-        fs::create_dir_all(path)?;
+        let path = self.get_config_file_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
 
         Ok(())
     }
 
     pub fn get_config_file_path(&self) -> Result<PathBuf, ConfigPathError> {
-        let mut path = self.get_config_dir_path()?;
-        path.push("config.json");
+        if let Some(path) = &self.config_file_path {
+            return Ok(path.clone());
+        }
 
-        Ok(path)
+        Ok(find_config_file(&self.get_config_dir_path()?))
+    }
+
+    // Shared by all users of the machine. Defaults to /etc/<app_name>/config.json (or .toml) on Unix and to none elsewhere.
+    pub fn get_system_config_file_path(&self) -> Option<PathBuf> {
+        if let Some(path) = &self.system_config_file_path {
+            return Some(path.clone());
+        }
+
+        match cfg!(unix) {
+            true => Some(find_config_file(&Path::new("/etc").join(&self.app_name))),
+            false => None,
+        }
     }
 
+    // Written in the format matching the config file's extension
     pub fn save_config(&self, config: &FILE) -> Result<(), ConfigSaveError> {
         let path = self.get_config_file_path()?;
         if !path.exists() {
             self.create_config_dir_path()?;
         }
 
-        let config_json = serde_json::to_string_pretty(config)?;
-        fs::write(path, config_json)?;
+        let content = ConfigFormat::from_path(&path).to_string_pretty(config)?;
+        fs::write(path, content)?;
 
         Ok(())
     }
@@ -134,11 +265,10 @@ where
         let path = self.get_config_file_path()?;
         if !path.exists() {
             self.create_config_dir_path()?;
-            fs::write(&path, "{}")?;
+            fs::write(&path, ConfigFormat::from_path(&path).empty_document())?;
         }
 
-        let config_json = fs::read_to_string(path)?;
-        let config = serde_json::from_str(&config_json)?;
+        let config = serde_json::from_value(read_config_file(&path)?)?;
         self.save_config(&config)?; // In case the config file was missing some fields which serde used the defaults for
 
         Ok(config)
@@ -155,11 +285,362 @@ where
         secondary_config.merge(prioritized_config)
     }
 
-    pub fn load_config(&self) -> Result<FILE, ConfigParseError> {
+    /*
+        Variables named <APP_NAME>_<KEY> override any key of the file config, e.g. LUM_DISCORD__TOKEN for discordToken.
+        Nested keys are separated by a double underscore, like LUM_DEFAULT_TIMEOUTS__STARTUP_SECONDS.
+        Keys are matched ignoring case and underscores, so both DISCORD_TOKEN and DISCORD__TOKEN name discordToken.
+        Variables naming keys that can't be added are logged and skipped, see may_add_key.
+    */
+    pub fn apply_env_overrides(&self, config: FILE) -> Result<FILE, EnvironmentConfigParseError> {
+        self.apply_overrides(config, env::vars())
+    }
+
+    // Like apply_env_overrides, with the variables given instead of read from the environment
+    pub fn apply_overrides<I>(
+        &self,
+        config: FILE,
+        vars: I,
+    ) -> Result<FILE, EnvironmentConfigParseError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut value = serde_json::to_value(config)?;
+        let may_add = |path: &str| self.may_add_key(path);
+        for (segments, raw) in self.env_overrides(vars) {
+            let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+            apply_override(&mut value, &segments, "", &raw, &may_add);
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /*
+        Precedence, from highest to lowest:
+        1. Command line flags (see load_layered_config)
+        2. Environment variable overrides (see apply_env_overrides) and the environment config (ENV)
+        3. The user config file
+        4. The system config file
+        5. Built-in defaults
+    */
+    pub fn load_config(&self) -> Result<FILE, ConfigParseError>
+    where
+        FILE: Default,
+    {
+        Ok(self.load_layered_config(env::args().skip(1))?.into_config())
+    }
+
+    /*
+        Builds the config layer by layer and remembers where each value came from.
+        Flags look like --config.<key>=<value>, with nested keys separated by dots, e.g. --config.defaultTimeouts.startupSeconds=30.
+        Other arguments are ignored. Unlike load_config_from_file, the user config file isn't rewritten.
+        Secret references (see config::resolve_secrets) are resolved per layer, so they never end up on disk.
+        Encrypted secrets are decrypted after the file layers, so environment and CLI overrides still win.
+    */
+    pub fn load_layered_config<I>(
+        &self,
+        cli_args: I,
+    ) -> Result<LayeredConfig<FILE>, ConfigParseError>
+    where
+        FILE: Default,
+        I: IntoIterator<Item = String>,
+    {
+        let mut origins = BTreeMap::new();
+
+        let mut value = serde_json::to_value(FILE::default())?;
+        record_leaves(&value, "", &ConfigSource::Default, &mut origins);
+
+        if let Some(path) = self.get_system_config_file_path()
+            && path.exists()
+        {
+            let mut layer = read_config_file(&path)?;
+            resolve_value(&mut layer, "", &lookup_env)?;
+            merge_layer(
+                &mut value,
+                layer,
+                "",
+                &ConfigSource::SystemFile(path),
+                &mut origins,
+            );
+        }
+
+        let path = self
+            .get_config_file_path()
+            .map_err(FileConfigParseError::from)?;
+        if !path.exists() {
+            self.create_config_dir_path()
+                .map_err(FileConfigParseError::from)?;
+            fs::write(&path, ConfigFormat::from_path(&path).empty_document())
+                .map_err(FileConfigParseError::from)?;
+        }
+        let mut layer = read_config_file(&path)?;
+        resolve_value(&mut layer, "", &lookup_env)?;
+        merge_layer(
+            &mut value,
+            layer,
+            "",
+            &ConfigSource::UserFile(path),
+            &mut origins,
+        );
+
+        self.decrypt_secrets(&mut value, &mut origins)?;
+
+        let mut overrides = Vec::new();
+        for (segments, raw) in self.env_overrides(env::vars()) {
+            let raw = resolve_string(&raw, &segments.join("__"), &lookup_env)?;
+            overrides.push((segments, raw, ConfigSource::Environment));
+        }
+        for (segments, raw) in cli_overrides(cli_args) {
+            let raw = resolve_string(&raw, &segments.join("."), &lookup_env)?;
+            overrides.push((segments, raw, ConfigSource::Cli));
+        }
+
+        /*
+            Merging the environment config needs a typed FILE, so the overrides are applied first as well.
+            That way they can fix file values that wouldn't deserialize, e.g. in a broken deployment file.
+            They are applied again after the merge, so they still win over the environment config.
+        */
+        let may_add = |path: &str| self.may_add_key(path);
+        apply_override_layers(&mut value, &overrides, &may_add, &mut origins);
         let env_config = self.load_config_from_env()?;
-        let file_config = self.load_config_from_file()?;
-        let merged_config = ConfigHandler::merge_configs(&env_config, file_config);
+        let file_config: FILE = match serde_json::from_value(value.clone()) {
+            Ok(file_config) => file_config,
+            Err(err) => {
+                self.check_schema(&value)?;
+                return Err(err.into());
+            }
+        };
+        let merged = ConfigHandler::merge_configs(&env_config, file_config);
+        let merged = serde_json::to_value(merged)?;
+        record_changes(
+            &value,
+            &merged,
+            "",
+            &ConfigSource::Environment,
+            &mut origins,
+        );
+        value = merged;
+        apply_override_layers(&mut value, &overrides, &may_add, &mut origins);
+
+        self.check_schema(&value)?;
+        Ok(LayeredConfig {
+            config: serde_json::from_value(value)?,
+            origins,
+        })
+    }
+
+    // Checked before deserializing, so every problem is reported instead of only the first one serde finds
+    fn check_schema(&self, value: &Value) -> Result<(), ConfigParseError> {
+        let violations = match &self.schema {
+            Some(schema) => schema.validate(value),
+            None => return Ok(()),
+        };
+
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigParseError::Invalid(violations)),
+        }
+    }
+
+    #[cfg(feature = "encrypted-secrets")]
+    fn decrypt_secrets(
+        &self,
+        value: &mut Value,
+        origins: &mut BTreeMap<String, ConfigSource>,
+    ) -> Result<(), EncryptedSecretsError> {
+        let section = match value.get(ENCRYPTED_SECRETS_SECTION) {
+            Some(Value::Object(section)) if !section.is_empty() => section.clone(),
+            _ => return Ok(()),
+        };
+        let key = self.secrets_key()?;
+
+        for (path, ciphertext) in section {
+            let Value::String(ciphertext) = ciphertext else {
+                return Err(EncryptedSecretsError::Malformed(path));
+            };
+            let plaintext = key.decrypt(&path, &ciphertext)?;
+
+            let segments: Vec<&str> = path.split('.').collect();
+            if let Some(key) = apply_override(value, &segments, "", &plaintext, &|_| true) {
+                let source = origins
+                    .get(&join_key(ENCRYPTED_SECRETS_SECTION, &path))
+                    .cloned()
+                    .unwrap_or(ConfigSource::Default);
+                record_leaves(&Value::Null, &key, &source, origins);
+            }
+        }
 
-        Ok(merged_config)
+        Ok(())
+    }
+
+    // Refuses to start with secrets it can't read, rather than silently using the plain values
+    #[cfg(not(feature = "encrypted-secrets"))]
+    fn decrypt_secrets(
+        &self,
+        value: &mut Value,
+        _origins: &mut BTreeMap<String, ConfigSource>,
+    ) -> Result<(), EncryptedSecretsError> {
+        match value.get(ENCRYPTED_SECRETS_SECTION) {
+            Some(Value::Object(section)) if !section.is_empty() => {
+                Err(EncryptedSecretsError::Unsupported)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /*
+        Overrides may only add keys the schema declares, e.g. a new service section. Without a schema,
+        anything but new top-level keys, so unrelated variables like LUM_LOG_LEVEL don't end up in the config.
+    */
+    fn may_add_key(&self, path: &str) -> bool {
+        match &self.schema {
+            Some(schema) => schema.declares(path),
+            None => path.contains('.'),
+        }
+    }
+
+    fn env_overrides<I>(&self, vars: I) -> Vec<(Vec<String>, String)>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let prefix = format!("{}_", self.app_name.to_uppercase());
+        let secrets_key_variable = self.secrets_key_variable();
+
+        vars.into_iter()
+            .filter(|(name, _)| *name != secrets_key_variable)
+            .filter_map(|(name, raw)| {
+                let key = name.strip_prefix(&prefix)?;
+                if key.is_empty() {
+                    return None;
+                }
+
+                Some((key.split("__").map(String::from).collect(), raw))
+            })
+            .collect()
+    }
+}
+
+fn lookup_env(variable: &str) -> Option<String> {
+    env::var(variable).ok()
+}
+
+fn cli_overrides<I>(args: I) -> Vec<(Vec<String>, String)>
+where
+    I: IntoIterator<Item = String>,
+{
+    args.into_iter()
+        .filter_map(|arg| {
+            let (key, raw) = arg.strip_prefix("--config.")?.split_once('=')?;
+            if key.is_empty() {
+                return None;
+            }
+
+            Some((key.split('.').map(String::from).collect(), raw.to_string()))
+        })
+        .collect()
+}
+
+fn apply_override_layers(
+    value: &mut Value,
+    overrides: &[(Vec<String>, String, ConfigSource)],
+    may_add: &dyn Fn(&str) -> bool,
+    origins: &mut BTreeMap<String, ConfigSource>,
+) {
+    for (segments, raw, source) in overrides {
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        if let Some(key) = apply_override(value, &segments, "", raw, may_add) {
+            record_leaves(&Value::Null, &key, source, origins);
+        }
+    }
+}
+
+// The first existing config.<extension> in the directory, or config.json if there is none
+fn find_config_file(dir: &Path) -> PathBuf {
+    ConfigFormat::ALL
+        .iter()
+        .flat_map(|format| format.extensions())
+        .map(|extension| dir.join(format!("config.{}", extension)))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join("config.json"))
+}
+
+fn read_config_file(path: &Path) -> Result<Value, FileConfigParseError> {
+    let content = fs::read_to_string(path)?;
+    ConfigFormat::from_path(path).parse(&content)
+}
+
+// Strings stay strings. Anything else is parsed as JSON, falling back to a string if that fails.
+fn parse_override(raw: &str, current: Option<&Value>) -> Value {
+    match current {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
+
+/*
+    A key may span several segments (DISCORD__TOKEN names discordToken), so the shortest run of segments
+    matching an existing key wins. Unknown keys are added in snake_case if may_add allows their path, e.g. a new
+    service section. Values that aren't sections are never replaced by one.
+    Returns the dotted path of the key that was set, or None if the override was skipped.
+*/
+fn apply_override(
+    value: &mut Value,
+    segments: &[&str],
+    path: &str,
+    raw: &str,
+    may_add: &dyn Fn(&str) -> bool,
+) -> Option<String> {
+    if value.is_null() {
+        *value = Value::Object(Map::new());
+    }
+    let Some(object) = value.as_object_mut() else {
+        warn!(
+            "Ignoring config override of \"{}\", since \"{}\" is not a section.",
+            join_key(path, &segments.join(".")),
+            path
+        );
+        return None;
+    };
+
+    for length in 1..=segments.len() {
+        let wanted = normalize_key(&segments[..length].concat());
+        let key = match object.keys().find(|key| normalize_key(key) == wanted) {
+            Some(key) => key.clone(),
+            None => continue,
+        };
+
+        let key_path = join_key(path, &key);
+        let rest = &segments[length..];
+        return match rest.is_empty() {
+            true => {
+                let parsed = parse_override(raw, object.get(&key));
+                object.insert(key, parsed);
+                Some(key_path)
+            }
+            false => apply_override(object.get_mut(&key)?, rest, &key_path, raw, may_add),
+        };
+    }
+
+    let key = segments.first()?.to_lowercase().replace('-', "_");
+    let key_path = join_key(path, &key);
+    if !may_add(&key_path) {
+        warn!(
+            "Ignoring config override of unknown key \"{}\".",
+            join_key(path, &segments.join("."))
+        );
+        return None;
+    }
+
+    match &segments[1..] {
+        [] => {
+            object.insert(key, parse_override(raw, None));
+            Some(key_path)
+        }
+        rest => apply_override(
+            object.entry(key).or_insert(Value::Null),
+            rest,
+            &key_path,
+            raw,
+            may_add,
+        ),
     }
 }
@@ -0,0 +1,352 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::{Map, Value};
+
+use super::layered_config::{join_key, normalize_key};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Array,
+    Object,
+    Any,
+}
+
+impl ValueKind {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => ValueKind::String,
+            Value::Number(number) if number.is_i64() || number.is_u64() => ValueKind::Integer,
+            Value::Number(_) => ValueKind::Number,
+            Value::Bool(_) => ValueKind::Boolean,
+            Value::Array(_) => ValueKind::Array,
+            Value::Object(_) | Value::Null => ValueKind::Object,
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        match (self, ValueKind::of(value)) {
+            (ValueKind::Any, _) => true,
+            (ValueKind::Number, ValueKind::Integer) => true,
+            (expected, found) => *expected == found,
+        }
+    }
+}
+
+impl Display for ValueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueKind::String => "a string",
+            ValueKind::Integer => "an integer",
+            ValueKind::Number => "a number",
+            ValueKind::Boolean => "a boolean",
+            ValueKind::Array => "an array",
+            ValueKind::Object => "an object",
+            ValueKind::Any => "any value",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    // Dotted key path. A * segment matches every key of a map, e.g. "serviceTimeouts.*.startupSeconds".
+    pub path: String,
+    pub kind: ValueKind,
+    pub required: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl SchemaField {
+    pub fn new(path: impl Into<String>, kind: ValueKind) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+            required: false,
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    // Inclusive, for numbers
+    pub fn with_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    Missing,
+    WrongType {
+        expected: ValueKind,
+        found: ValueKind,
+    },
+    OutOfRange {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    UnknownKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigViolation {
+    pub path: String,
+    pub kind: ViolationKind,
+
+    // The closest known key, for unknown keys that look like a typo
+    pub suggestion: Option<String>,
+}
+
+impl Display for ConfigViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ViolationKind::Missing => write!(f, "{}: required key is missing", self.path)?,
+            ViolationKind::WrongType { expected, found } => {
+                write!(f, "{}: expected {}, found {}", self.path, expected, found)?
+            }
+            ViolationKind::OutOfRange { min, max } => {
+                write!(f, "{}: must be", self.path)?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, " between {} and {}", min, max)?,
+                    (Some(min), None) => write!(f, " at least {}", min)?,
+                    (None, Some(max)) => write!(f, " at most {}", max)?,
+                    (None, None) => write!(f, " in range")?,
+                }
+            }
+            ViolationKind::UnknownKey => write!(f, "{}: unknown key", self.path)?,
+        }
+
+        match &self.suggestion {
+            Some(suggestion) => write!(f, " (did you mean \"{}\"?)", suggestion),
+            None => Ok(()),
+        }
+    }
+}
+
+/*
+    Describes the keys a config may have. Objects with declared children reject unknown keys,
+    objects without declared children (like the services map) accept anything.
+    Keys are matched like environment overrides do, ignoring case, underscores and dashes.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigSchema {
+    pub fields: Vec<SchemaField>,
+}
+
+impl ConfigSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_field(mut self, field: SchemaField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    // Every problem found, not just the first one
+    pub fn validate(&self, config: &Value) -> Vec<ConfigViolation> {
+        let mut violations = Vec::new();
+
+        for field in &self.fields {
+            let segments: Vec<&str> = field.path.split('.').collect();
+            self.validate_field(field, config, &segments, "", &mut violations);
+        }
+        self.find_unknown_keys(config, &[], "", &mut violations);
+
+        violations
+    }
+
+    fn validate_field(
+        &self,
+        field: &SchemaField,
+        value: &Value,
+        segments: &[&str],
+        path: &str,
+        violations: &mut Vec<ConfigViolation>,
+    ) {
+        let (segment, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => return check_value(field, value, path, violations),
+        };
+
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+
+        if *segment == "*" {
+            for (key, child) in object {
+                self.validate_field(field, child, rest, &join_key(path, key), violations);
+            }
+            return;
+        }
+
+        match find_key(object, segment) {
+            Some((key, child)) => {
+                self.validate_field(field, child, rest, &join_key(path, key), violations)
+            }
+            // Only the last key can be missing, a missing parent section is reported for itself if required
+            None if field.required && rest.is_empty() => violations.push(ConfigViolation {
+                path: join_key(path, segment),
+                kind: ViolationKind::Missing,
+                suggestion: None,
+            }),
+            None => {}
+        }
+    }
+
+    fn find_unknown_keys(
+        &self,
+        value: &Value,
+        schema_path: &[&str],
+        path: &str,
+        violations: &mut Vec<ConfigViolation>,
+    ) {
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+
+        let known = self.children(schema_path);
+        if known.is_empty() {
+            return;
+        }
+
+        for (key, child) in object {
+            let child_path = join_key(path, key);
+            let matched = known
+                .iter()
+                .find(|known| **known == "*" || normalize_key(known) == normalize_key(key));
+
+            match matched {
+                Some(known) => {
+                    let mut child_schema_path = schema_path.to_vec();
+                    child_schema_path.push(known);
+                    self.find_unknown_keys(child, &child_schema_path, &child_path, violations);
+                }
+                None => violations.push(ConfigViolation {
+                    path: child_path,
+                    kind: ViolationKind::UnknownKey,
+                    suggestion: suggest(key, &known),
+                }),
+            }
+        }
+    }
+
+    // True if validate wouldn't report the dotted key path as unknown
+    pub(crate) fn declares(&self, path: &str) -> bool {
+        let mut schema_path = Vec::new();
+        for segment in path.split('.') {
+            let known = self.children(&schema_path);
+            if known.is_empty() {
+                return true;
+            }
+
+            let matched = known
+                .iter()
+                .find(|known| **known == "*" || normalize_key(known) == normalize_key(segment));
+            match matched {
+                Some(known) => schema_path.push(*known),
+                None => return false,
+            }
+        }
+
+        true
+    }
+
+    // The declared keys directly below a schema path
+    fn children<'a>(&'a self, schema_path: &[&str]) -> Vec<&'a str> {
+        let mut children = Vec::new();
+        for field in &self.fields {
+            let segments: Vec<&str> = field.path.split('.').collect();
+            if segments.len() > schema_path.len() && segments[..schema_path.len()] == *schema_path {
+                let child = segments[schema_path.len()];
+                if !children.contains(&child) {
+                    children.push(child);
+                }
+            }
+        }
+
+        children
+    }
+}
+
+fn find_key<'a>(object: &'a Map<String, Value>, key: &str) -> Option<(&'a String, &'a Value)> {
+    let wanted = normalize_key(key);
+    object.iter().find(|(key, _)| normalize_key(key) == wanted)
+}
+
+fn check_value(
+    field: &SchemaField,
+    value: &Value,
+    path: &str,
+    violations: &mut Vec<ConfigViolation>,
+) {
+    if !field.kind.accepts(value) {
+        violations.push(ConfigViolation {
+            path: path.to_string(),
+            kind: ViolationKind::WrongType {
+                expected: field.kind,
+                found: ValueKind::of(value),
+            },
+            suggestion: None,
+        });
+        return;
+    }
+
+    if let Some(number) = value.as_f64() {
+        let too_low = field.min.is_some_and(|min| number < min);
+        let too_high = field.max.is_some_and(|max| number > max);
+        if too_low || too_high {
+            violations.push(ConfigViolation {
+                path: path.to_string(),
+                kind: ViolationKind::OutOfRange {
+                    min: field.min,
+                    max: field.max,
+                },
+                suggestion: None,
+            });
+        }
+    }
+}
+
+// Suggests a known key if the unknown one is at most a third of its length away from it
+fn suggest(key: &str, known: &[&str]) -> Option<String> {
+    let key = normalize_key(key);
+
+    known
+        .iter()
+        .filter(|known| **known != "*")
+        .map(|known| (edit_distance(&key, &normalize_key(known)), *known))
+        .filter(|(distance, known)| *distance <= (known.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known.to_string())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
@@ -0,0 +1,254 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+use log::{info, warn};
+use notify::{PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    task::JoinHandle,
+};
+
+use crate::event::Event;
+
+use super::{ConfigHandler, ConfigParseError, Merge, layered_config::changed_keys};
+
+#[derive(Debug)]
+pub struct ConfigChanged<FILE> {
+    // Dotted key paths as written in the config file, e.g. "defaultTimeouts.startupSeconds"
+    pub changed_keys: Vec<String>,
+    pub previous: Arc<FILE>,
+    pub config: Arc<FILE>,
+}
+
+impl<FILE> ConfigChanged<FILE> {
+    // True if the key or anything below it changed, e.g. "defaultTimeouts" for any timeout
+    pub fn changed(&self, key: &str) -> bool {
+        let prefix = format!("{}.", key);
+        self.changed_keys
+            .iter()
+            .any(|changed| changed == key || changed.starts_with(&prefix))
+    }
+}
+
+/*
+    Reloads the config when one of its files changes and dispatches on_change with the keys that changed.
+    The directories holding the files are watched, so files replaced by an editor or created later are noticed.
+    Events only trigger a re-read, the contents are compared to skip unrelated ones. A file that fails to parse keeps the current config.
+*/
+pub struct ConfigWatcher<FILE, ENV>
+where
+    FILE: Serialize + for<'de> Deserialize<'de> + Merge<ENV> + Send + Sync + 'static,
+    ENV: Serialize + for<'de> Deserialize<'de>,
+{
+    handler: ConfigHandler<FILE, ENV>,
+    current: RwLock<Arc<FILE>>,
+    file_contents: RwLock<Vec<Option<String>>>,
+    file_watcher: Mutex<Option<Box<dyn Watcher + Send>>>,
+    pub on_change: Event<ConfigChanged<FILE>>,
+}
+
+impl<FILE, ENV> ConfigWatcher<FILE, ENV>
+where
+    FILE: Serialize + for<'de> Deserialize<'de> + Merge<ENV> + Default + Send + Sync + 'static,
+    ENV: Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+{
+    pub fn new(handler: ConfigHandler<FILE, ENV>) -> Result<Self, ConfigParseError> {
+        let config = handler.load_config()?;
+        let file_contents = read_files(&watched_paths(&handler));
+
+        Ok(Self {
+            handler,
+            current: RwLock::new(Arc::new(config)),
+            file_contents: RwLock::new(file_contents),
+            file_watcher: Mutex::new(None),
+            on_change: Event::new("config_on_change"),
+        })
+    }
+
+    pub fn config(&self) -> Arc<FILE> {
+        Arc::clone(
+            &self
+                .current
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+
+    pub fn handler(&self) -> &ConfigHandler<FILE, ENV> {
+        &self.handler
+    }
+
+    /*
+        Watches the config files through the OS (inotify, FSEvents, ...) until the returned handle is aborted,
+        the watcher is dropped or watch is called again. Falls back to polling every poll_interval
+        where the OS can't watch them, e.g. when the inotify watch limit is reached.
+    */
+    pub fn watch(self: &Arc<Self>, poll_interval: Duration) -> JoinHandle<()> {
+        let watcher = Arc::downgrade(self);
+        let (sender, mut events) = mpsc::unbounded_channel();
+
+        // Owns the sender, so dropping it ends the task below
+        *self
+            .file_watcher
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            create_file_watcher(&watched_paths(&self.handler), sender, poll_interval);
+
+        tokio::spawn(async move {
+            while events.recv().await.is_some() {
+                // Saving a file usually causes several events
+                while events.try_recv().is_ok() {}
+
+                let watcher = match watcher.upgrade() {
+                    Some(watcher) => watcher,
+                    None => return,
+                };
+
+                if let Err(err) = watcher.reload_if_changed().await {
+                    warn!("Unable to reload config, keeping the current one: {}", err);
+                }
+            }
+        })
+    }
+
+    // Returns true if a config file changed since the last check
+    pub async fn reload_if_changed(&self) -> Result<bool, ConfigParseError> {
+        let contents = read_files(&watched_paths(&self.handler));
+        if *self
+            .file_contents
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            == contents
+        {
+            return Ok(false);
+        }
+
+        // Remembered before parsing, so a broken file is reported once instead of on every check
+        *self
+            .file_contents
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = contents;
+
+        self.reload().await?;
+        Ok(true)
+    }
+
+    // Dispatches on_change if any value differs from the current config
+    pub async fn reload(&self) -> Result<(), ConfigParseError> {
+        let config = Arc::new(self.handler.load_config()?);
+        let previous = self.config();
+
+        let changed_keys = changed_keys(
+            &serde_json::to_value(&*previous)?,
+            &serde_json::to_value(&*config)?,
+        );
+        if changed_keys.is_empty() {
+            return Ok(());
+        }
+
+        *self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::clone(&config);
+
+        info!("Config reloaded, changed: {}", changed_keys.join(", "));
+
+        // Failed deliveries are logged by the subscribers
+        let _ = self
+            .on_change
+            .dispatch(Arc::new(ConfigChanged {
+                changed_keys,
+                previous,
+                config,
+            }))
+            .await;
+
+        Ok(())
+    }
+}
+
+fn watched_paths<FILE, ENV>(handler: &ConfigHandler<FILE, ENV>) -> Vec<PathBuf>
+where
+    FILE: Serialize + for<'de> Deserialize<'de> + Merge<ENV>,
+    ENV: Serialize + for<'de> Deserialize<'de>,
+{
+    handler
+        .get_system_config_file_path()
+        .into_iter()
+        .chain(handler.get_config_file_path().ok())
+        .collect()
+}
+
+fn create_file_watcher(
+    paths: &[PathBuf],
+    sender: UnboundedSender<()>,
+    poll_interval: Duration,
+) -> Option<Box<dyn Watcher + Send>> {
+    // Directories that don't exist yet can't be watched, files created in them later are missed
+    let mut directories: Vec<&Path> = paths
+        .iter()
+        .filter_map(|path| path.parent())
+        .filter(|directory| directory.is_dir())
+        .collect();
+    directories.dedup();
+
+    let on_event = move |event: notify::Result<notify::Event>| match event {
+        Ok(_) => {
+            let _ = sender.send(());
+        }
+        Err(err) => warn!("Error while watching config files: {}", err),
+    };
+
+    let recommended = RecommendedWatcher::new(on_event.clone(), notify::Config::default())
+        .and_then(|mut watcher| {
+            watch_directories(&mut watcher, &directories)?;
+            Ok(watcher)
+        });
+    let err = match recommended {
+        Ok(watcher) => return Some(Box::new(watcher)),
+        Err(err) => err,
+    };
+    warn!(
+        "Unable to watch config files, polling them every {:?} instead: {}",
+        poll_interval, err
+    );
+
+    let polling = PollWatcher::new(
+        on_event,
+        notify::Config::default().with_poll_interval(poll_interval),
+    )
+    .and_then(|mut watcher| {
+        watch_directories(&mut watcher, &directories)?;
+        Ok(watcher)
+    });
+    match polling {
+        Ok(watcher) => Some(Box::new(watcher)),
+        Err(err) => {
+            warn!(
+                "Unable to poll config files, they won't be reloaded: {}",
+                err
+            );
+            None
+        }
+    }
+}
+
+fn watch_directories(watcher: &mut dyn Watcher, directories: &[&Path]) -> notify::Result<()> {
+    for directory in directories {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+    }
+
+    Ok(())
+}
+
+fn read_files(paths: &[PathBuf]) -> Vec<Option<String>> {
+    paths
+        .iter()
+        .map(|path| fs::read_to_string(path).ok())
+        .collect()
+}
@@ -0,0 +1,147 @@
+use thiserror::Error;
+
+#[cfg(feature = "encrypted-secrets")]
+use {
+    super::layered_config::normalize_key,
+    base64::{Engine, engine::general_purpose::STANDARD},
+    ring::{
+        aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+        rand::{SecureRandom, SystemRandom},
+    },
+    std::fmt::{self, Debug, Formatter},
+};
+
+// Top level config section mapping dotted key paths to ciphertexts, e.g. { "discordToken": "..." }
+pub const ENCRYPTED_SECRETS_SECTION: &str = "encryptedSecrets";
+
+#[derive(Debug, Error)]
+pub enum EncryptedSecretsError {
+    #[error(
+        "The config contains encrypted secrets, but lum was built without the encrypted-secrets feature"
+    )]
+    Unsupported,
+
+    #[error("The config contains encrypted secrets, but no key was given. Set {0}.")]
+    MissingKey(String),
+
+    #[error("A secrets key must be 32 bytes, encoded as base64")]
+    InvalidKey,
+
+    #[error("{0}: encrypted secret is malformed")]
+    Malformed(String),
+
+    #[error(
+        "{0}: unable to decrypt secret, it was encrypted with another key or for another key path"
+    )]
+    Decrypt(String),
+
+    #[error("Unable to encrypt secret")]
+    Encrypt,
+}
+
+// An AES-256-GCM key. Ciphertexts are bound to their key path, so they can't be moved to another key.
+#[cfg(feature = "encrypted-secrets")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretsKey([u8; 32]);
+
+#[cfg(feature = "encrypted-secrets")]
+impl SecretsKey {
+    pub fn generate() -> Result<Self, EncryptedSecretsError> {
+        let mut bytes = [0; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| EncryptedSecretsError::Encrypt)?;
+
+        Ok(Self(bytes))
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self, EncryptedSecretsError> {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| EncryptedSecretsError::InvalidKey)?;
+
+        bytes
+            .try_into()
+            .map(Self)
+            .map_err(|_| EncryptedSecretsError::InvalidKey)
+    }
+
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(self.0)
+    }
+
+    // Returns base64 of the nonce followed by the ciphertext and tag
+    pub fn encrypt(
+        &self,
+        key_path: &str,
+        plaintext: &str,
+    ) -> Result<String, EncryptedSecretsError> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| EncryptedSecretsError::Encrypt)?;
+
+        let mut data = plaintext.as_bytes().to_vec();
+        self.aead_key()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(associated_data(key_path).as_bytes()),
+                &mut data,
+            )
+            .map_err(|_| EncryptedSecretsError::Encrypt)?;
+
+        Ok(STANDARD.encode([nonce.as_slice(), &data].concat()))
+    }
+
+    pub fn decrypt(
+        &self,
+        key_path: &str,
+        ciphertext: &str,
+    ) -> Result<String, EncryptedSecretsError> {
+        let malformed = || EncryptedSecretsError::Malformed(key_path.to_string());
+
+        let mut data = STANDARD
+            .decode(ciphertext.trim())
+            .map_err(|_| malformed())?;
+        if data.len() < NONCE_LEN {
+            return Err(malformed());
+        }
+        let (nonce, sealed) = data.split_at_mut(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| malformed())?;
+
+        let plaintext = self
+            .aead_key()
+            .open_in_place(
+                nonce,
+                Aad::from(associated_data(key_path).as_bytes()),
+                sealed,
+            )
+            .map_err(|_| EncryptedSecretsError::Decrypt(key_path.to_string()))?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| malformed())
+    }
+
+    fn aead_key(&self) -> LessSafeKey {
+        LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, &self.0).expect("Secrets key has an invalid length"),
+        )
+    }
+}
+
+// Never print the key itself
+#[cfg(feature = "encrypted-secrets")]
+impl Debug for SecretsKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretsKey(..)")
+    }
+}
+
+// Normalized like config keys, so discord_token and discordToken decrypt the same ciphertext
+#[cfg(feature = "encrypted-secrets")]
+fn associated_data(key_path: &str) -> String {
+    key_path
+        .split('.')
+        .map(normalize_key)
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -1,13 +1,121 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use thiserror::Error;
 
-use super::{EnvironmentConfig, Merge};
+use crate::service::TimeoutOverride;
 
-#[derive(Debug, PartialEq, PartialOrd, Serialize, Deserialize, Clone)]
+use super::{ConfigSchema, EnvironmentConfig, Merge, SchemaField, ValueKind};
+
+#[derive(Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize, Clone)]
+pub struct TimeoutConfig {
+    #[serde(
+        rename = "startupSeconds",
+        alias = "startup_seconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub startup_seconds: Option<u64>,
+
+    #[serde(
+        rename = "shutdownSeconds",
+        alias = "shutdown_seconds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shutdown_seconds: Option<u64>,
+}
+
+impl From<&TimeoutConfig> for TimeoutOverride {
+    fn from(config: &TimeoutConfig) -> Self {
+        TimeoutOverride {
+            startup: config.startup_seconds.map(Duration::from_secs),
+            shutdown: config.shutdown_seconds.map(Duration::from_secs),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct FileConfig {
-    #[serde(rename = "discordToken")]
+    // The snake_case aliases accept keys added by environment overrides, see ConfigHandler::apply_env_overrides
+    #[serde(rename = "discordToken", alias = "discord_token")]
     pub discord_token: String,
+
+    #[serde(rename = "defaultTimeouts", alias = "default_timeouts", default)]
+    pub default_timeouts: TimeoutConfig,
+
+    // Keyed by service ID
+    #[serde(rename = "serviceTimeouts", alias = "service_timeouts", default)]
+    pub service_timeouts: BTreeMap<String, TimeoutConfig>,
+
+    // Keyed by service ID, each service deserializes its own section
+    #[serde(default)]
+    pub services: BTreeMap<String, Value>,
+
+    // Keyed by dotted key path, decrypted into those keys when loading, see ConfigHandler::encrypt_value
+    #[serde(
+        rename = "encryptedSecrets",
+        alias = "encrypted_secrets",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub encrypted_secrets: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid config section for service \"{service_id}\": {source}")]
+pub struct ConfigSectionError {
+    pub service_id: String,
+
+    #[source]
+    pub source: serde_json::Error,
+}
+
+impl FileConfig {
+    pub fn service_section(&self, service_id: &str) -> Option<&Value> {
+        self.services.get(service_id)
+    }
+
+    // Describes the keys above. Services validate their own sections, see Service::validate_config.
+    pub fn schema() -> ConfigSchema {
+        let timeout =
+            |path: &str| SchemaField::new(path, ValueKind::Integer).with_range(Some(1.0), None);
+
+        ConfigSchema::new()
+            .with_field(SchemaField::new("discordToken", ValueKind::String).required())
+            .with_field(SchemaField::new("defaultTimeouts", ValueKind::Object))
+            .with_field(timeout("defaultTimeouts.startupSeconds"))
+            .with_field(timeout("defaultTimeouts.shutdownSeconds"))
+            .with_field(SchemaField::new("serviceTimeouts", ValueKind::Object))
+            .with_field(SchemaField::new("serviceTimeouts.*", ValueKind::Object))
+            .with_field(timeout("serviceTimeouts.*.startupSeconds"))
+            .with_field(timeout("serviceTimeouts.*.shutdownSeconds"))
+            .with_field(SchemaField::new("services", ValueKind::Object))
+            .with_field(SchemaField::new("services.*", ValueKind::Object))
+            .with_field(SchemaField::new("encryptedSecrets", ValueKind::Object))
+            .with_field(SchemaField::new("encryptedSecrets.*", ValueKind::String))
+    }
+
+    // A missing section is treated as empty, so sections with only defaulted fields are optional
+    pub fn section<T>(&self, service_id: &str) -> Result<T, ConfigSectionError>
+    where
+        T: DeserializeOwned,
+    {
+        let section = self
+            .service_section(service_id)
+            .cloned()
+            .unwrap_or_else(|| Value::Object(Map::new()));
+
+        serde_json::from_value(section).map_err(|source| ConfigSectionError {
+            service_id: service_id.to_string(),
+            source,
+        })
+    }
 }
 
 impl Merge<EnvironmentConfig> for FileConfig {
@@ -17,7 +125,10 @@ impl Merge<EnvironmentConfig> for FileConfig {
             .clone()
             .unwrap_or(self.discord_token.clone());
 
-        FileConfig { discord_token }
+        FileConfig {
+            discord_token,
+            ..self.clone()
+        }
     }
 }
 
@@ -25,6 +136,10 @@ impl Default for FileConfig {
     fn default() -> Self {
         FileConfig {
             discord_token: String::from("Please provide a token"),
+            default_timeouts: TimeoutConfig::default(),
+            service_timeouts: BTreeMap::new(),
+            services: BTreeMap::new(),
+            encrypted_secrets: BTreeMap::new(),
         }
     }
 }
@@ -0,0 +1,191 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+    path::PathBuf,
+};
+
+use serde_json::{Map, Value};
+
+// The layers of ConfigHandler::load_layered_config, from lowest to highest precedence
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigSource {
+    Default,
+    SystemFile(PathBuf),
+    UserFile(PathBuf),
+    Environment,
+    Cli,
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "built-in default"),
+            ConfigSource::SystemFile(path) => write!(f, "system config file {}", path.display()),
+            ConfigSource::UserFile(path) => write!(f, "user config file {}", path.display()),
+            ConfigSource::Environment => write!(f, "environment"),
+            ConfigSource::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+// A loaded config that remembers which source each value came from, for debugging
+#[derive(Debug, Clone)]
+pub struct LayeredConfig<FILE> {
+    pub config: FILE,
+
+    // Keyed by dotted key path as written in the config file, e.g. "defaultTimeouts.startupSeconds"
+    pub origins: BTreeMap<String, ConfigSource>,
+}
+
+impl<FILE> LayeredConfig<FILE> {
+    // Keys match like environment overrides do, so "default_timeouts.startup_seconds" works too
+    pub fn origin(&self, key: &str) -> Option<&ConfigSource> {
+        if let Some(source) = self.origins.get(key) {
+            return Some(source);
+        }
+
+        let wanted = normalize_path(key);
+        self.origins
+            .iter()
+            .find(|(key, _)| normalize_path(key) == wanted)
+            .map(|(_, source)| source)
+    }
+
+    pub fn into_config(self) -> FILE {
+        self.config
+    }
+}
+
+// Ignores case, underscores and dashes, so discord_token, discord-token and discordToken are the same key
+pub(crate) fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn normalize_path(path: &str) -> Vec<String> {
+    path.split('.').map(normalize_key).collect()
+}
+
+pub(crate) fn join_key(parent: &str, key: &str) -> String {
+    match parent.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", parent, key),
+    }
+}
+
+// Leaves are non-object values. Empty objects count as leaves, so an empty section still has an origin.
+pub(crate) fn record_leaves(
+    value: &Value,
+    path: &str,
+    source: &ConfigSource,
+    origins: &mut BTreeMap<String, ConfigSource>,
+) {
+    match value.as_object() {
+        Some(object) if !object.is_empty() => {
+            for (key, child) in object {
+                record_leaves(child, &join_key(path, key), source, origins);
+            }
+        }
+        _ => {
+            // A value replacing a whole section also replaces the origins recorded inside it
+            origins.retain(|key, _| !key.starts_with(&format!("{}.", path)));
+            origins.insert(path.to_string(), source.clone());
+        }
+    }
+}
+
+// Objects are merged key by key, everything else in the layer replaces what was there
+pub(crate) fn merge_layer(
+    base: &mut Value,
+    layer: Value,
+    path: &str,
+    source: &ConfigSource,
+    origins: &mut BTreeMap<String, ConfigSource>,
+) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) if !layer.is_empty() => {
+            for (key, child) in layer {
+                let child_path = join_key(path, &key);
+                let entry = base.entry(key).or_insert(Value::Null);
+                merge_layer(entry, child, &child_path, source, origins);
+            }
+        }
+        (Value::Object(_), Value::Object(_)) => {}
+        (base, layer) => {
+            record_leaves(&layer, path, source, origins);
+            *base = layer;
+        }
+    }
+}
+
+fn as_object<'a>(
+    value: Option<&'a Value>,
+    empty: &'a Map<String, Value>,
+) -> Option<&'a Map<String, Value>> {
+    match value {
+        Some(Value::Object(object)) => Some(object),
+        Some(_) => None,
+        None => Some(empty),
+    }
+}
+
+// Dotted paths of the leaves that were added, removed or changed
+pub(crate) fn changed_keys(before: &Value, after: &Value) -> Vec<String> {
+    let mut changed = BTreeSet::new();
+    collect_changes(Some(before), Some(after), "", &mut changed);
+
+    changed.into_iter().collect()
+}
+
+fn collect_changes(
+    before: Option<&Value>,
+    after: Option<&Value>,
+    path: &str,
+    changed: &mut BTreeSet<String>,
+) {
+    // A missing key compares like an empty section, so only the leaves below it are reported
+    let empty = Map::new();
+    match (as_object(before, &empty), as_object(after, &empty)) {
+        (Some(before_object), Some(after_object))
+            if !before_object.is_empty() || !after_object.is_empty() =>
+        {
+            for key in before_object.keys().chain(after_object.keys()) {
+                collect_changes(
+                    before_object.get(key),
+                    after_object.get(key),
+                    &join_key(path, key),
+                    changed,
+                );
+            }
+        }
+        _ if before != after => {
+            changed.insert(path.to_string());
+        }
+        _ => {}
+    }
+}
+
+// Records every leaf that differs between before and after, for layers that can't report what they set
+pub(crate) fn record_changes(
+    before: &Value,
+    after: &Value,
+    path: &str,
+    source: &ConfigSource,
+    origins: &mut BTreeMap<String, ConfigSource>,
+) {
+    match (before.as_object(), after.as_object()) {
+        (Some(before), Some(after)) if !after.is_empty() => {
+            for (key, child) in after {
+                let child_path = join_key(path, key);
+                match before.get(key) {
+                    Some(previous) => record_changes(previous, child, &child_path, source, origins),
+                    None => record_leaves(child, &child_path, source, origins),
+                }
+            }
+        }
+        _ if before != after => record_leaves(after, path, source, origins),
+        _ => {}
+    }
+}
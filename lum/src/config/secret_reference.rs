@@ -0,0 +1,131 @@
+use std::{env, fs, io, path::PathBuf};
+
+use serde_json::Value;
+use thiserror::Error;
+
+use super::layered_config::join_key;
+
+#[derive(Debug, Error)]
+pub enum SecretResolveError {
+    #[error("{key}: environment variable {variable} is not set")]
+    MissingVariable { key: String, variable: String },
+
+    #[error("{key}: unable to read secret file {}: {source}", .file.display())]
+    File {
+        key: String,
+        file: PathBuf,
+        source: io::Error,
+    },
+
+    #[error("{key}: secret reference is missing its closing brace")]
+    Unterminated { key: String },
+}
+
+/*
+    String values may reference secrets instead of containing them:
+    ${env:VAR} is replaced by the environment variable VAR, ${file:/path} by the contents of the file,
+    without trailing newlines (as Docker and Kubernetes secrets are usually written).
+    References can be embedded, like "postgres://lum:${env:DB_PASSWORD}@localhost/lum".
+    $${env:VAR} is kept verbatim as ${env:VAR}. Other ${...} sequences are left alone.
+*/
+pub fn resolve_secrets(value: &mut Value) -> Result<(), SecretResolveError> {
+    resolve_secrets_with(value, |variable| env::var(variable).ok())
+}
+
+// Like resolve_secrets, with environment variables looked up through the given function
+pub fn resolve_secrets_with<F>(value: &mut Value, lookup: F) -> Result<(), SecretResolveError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    resolve_value(value, "", &lookup)
+}
+
+pub(crate) fn resolve_value<F>(
+    value: &mut Value,
+    key: &str,
+    lookup: &F,
+) -> Result<(), SecretResolveError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    match value {
+        Value::String(raw) => *raw = resolve_string(raw, key, lookup)?,
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                resolve_value(item, &join_key(key, &index.to_string()), lookup)?;
+            }
+        }
+        Value::Object(object) => {
+            for (child_key, child) in object.iter_mut() {
+                resolve_value(child, &join_key(key, child_key), lookup)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+pub(crate) fn resolve_string<F>(
+    raw: &str,
+    key: &str,
+    lookup: &F,
+) -> Result<String, SecretResolveError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut resolved = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(start) = rest.find("${") {
+        let (before, reference) = rest.split_at(start);
+
+        if let Some(before) = before.strip_suffix('$') {
+            resolved.push_str(before);
+            resolved.push_str("${");
+            rest = &reference[2..];
+            continue;
+        }
+        resolved.push_str(before);
+
+        let (scheme, argument) = match reference[2..].split_once(':') {
+            Some((scheme @ ("env" | "file"), argument)) => (scheme, argument),
+            _ => {
+                resolved.push_str("${");
+                rest = &reference[2..];
+                continue;
+            }
+        };
+        let Some((argument, after)) = argument.split_once('}') else {
+            return Err(SecretResolveError::Unterminated {
+                key: key.to_string(),
+            });
+        };
+
+        match scheme {
+            "env" => match lookup(argument) {
+                Some(secret) => resolved.push_str(&secret),
+                None => {
+                    return Err(SecretResolveError::MissingVariable {
+                        key: key.to_string(),
+                        variable: argument.to_string(),
+                    });
+                }
+            },
+            _ => match fs::read_to_string(argument) {
+                Ok(secret) => resolved.push_str(secret.trim_end_matches(['\r', '\n'])),
+                Err(source) => {
+                    return Err(SecretResolveError::File {
+                        key: key.to_string(),
+                        file: PathBuf::from(argument),
+                        source,
+                    });
+                }
+            },
+        }
+        rest = after;
+    }
+
+    resolved.push_str(rest);
+    Ok(resolved)
+}
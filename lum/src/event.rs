@@ -1,11 +1,41 @@
 pub mod arc_observable;
+#[cfg(feature = "event-bridge")]
+pub mod bridge;
+pub mod cancellable_event;
+pub mod dead_letter;
+mod drop_oldest;
+#[allow(clippy::module_inception)]
 pub mod event;
+pub mod event_bus;
+pub mod event_metrics;
 pub mod event_repeater;
+pub mod event_stream;
+pub mod keyed_event;
 pub mod observable;
+pub mod priority_lane;
+pub mod query;
+pub mod rate_limit;
 pub mod subscriber;
+pub mod subscription_group;
+pub mod subscription_handle;
+pub mod topic_event;
 
 pub use arc_observable::ArcObservable;
-pub use event::Event;
+#[cfg(feature = "event-bridge")]
+pub use bridge::{BridgeAddress, BridgeError, BridgeFrame, EventBridgeClient, EventBridgeServer};
+pub use cancellable_event::{CancellableCallback, CancellableEvent, Propagation};
+pub use dead_letter::{DeadLetter, DeadLetterQueue};
+pub use event::{DispatchMode, Event, EventError, Middleware, TryDispatchError};
+pub use event_bus::{EventBus, EventInfo};
+pub use event_metrics::EventMetrics;
 pub use event_repeater::EventRepeater;
+pub use event_stream::EventStream;
+pub use keyed_event::KeyedEvent;
 pub use observable::{Observable, ObservableResult};
-pub use subscriber::{Callback, DispatchError, Subscriber};
+pub use priority_lane::{Lane, LaneReceiver, LaneSender, lane_channel};
+pub use query::{Query, QueryError};
+pub use rate_limit::RateLimit;
+pub use subscriber::{Backpressure, Callback, DispatchError, Filter, Sequenced, Subscriber};
+pub use subscription_group::SubscriptionGroup;
+pub use subscription_handle::SubscriptionHandle;
+pub use topic_event::{TopicEvent, TopicMessage, TopicPattern};
@@ -0,0 +1,261 @@
+use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::{Mutex, broadcast},
+    task::JoinHandle,
+};
+
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+use super::{Event, SubscriptionHandle};
+
+// Sent as one JSON object per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BridgeFrame {
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Error)]
+pub enum BridgeError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to (de)serialize bridged event data: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BridgeAddress {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+type InboundHandler =
+    Box<dyn Fn(serde_json::Value) -> BoxFuture<'static, Result<(), BridgeError>> + Send + Sync>;
+
+// Publishes bridged events to every connected client. Clients that fall behind skip frames instead of slowing down dispatch.
+pub struct EventBridgeServer {
+    frames: broadcast::Sender<Arc<String>>,
+    local_addr: Option<SocketAddr>,
+    accept_task: JoinHandle<()>,
+
+    // Type-erased SubscriptionHandles, kept so the bridged events stay subscribed
+    subscriptions: Mutex<Vec<Box<dyn Send + Sync>>>,
+}
+
+impl EventBridgeServer {
+    pub async fn bind(address: BridgeAddress, buffer: usize) -> Result<Self, BridgeError> {
+        let (frames, _) = broadcast::channel(buffer);
+
+        let (accept_task, local_addr) = match address {
+            BridgeAddress::Tcp(address) => {
+                let listener = TcpListener::bind(address).await?;
+                let local_addr = listener.local_addr()?;
+                let frames = frames.clone();
+
+                let task = tokio::spawn(async move {
+                    loop {
+                        match listener.accept().await {
+                            Ok((stream, _)) => {
+                                tokio::spawn(send_frames(stream, frames.subscribe()));
+                            }
+                            Err(err) => {
+                                log::warn!("Event bridge failed to accept a connection: {}", err)
+                            }
+                        }
+                    }
+                });
+
+                (task, Some(local_addr))
+            }
+            #[cfg(unix)]
+            BridgeAddress::Unix(path) => {
+                let listener = UnixListener::bind(path)?;
+                let frames = frames.clone();
+
+                let task = tokio::spawn(async move {
+                    loop {
+                        match listener.accept().await {
+                            Ok((stream, _)) => {
+                                tokio::spawn(send_frames(stream, frames.subscribe()));
+                            }
+                            Err(err) => {
+                                log::warn!("Event bridge failed to accept a connection: {}", err)
+                            }
+                        }
+                    }
+                });
+
+                (task, None)
+            }
+        };
+
+        Ok(Self {
+            frames,
+            local_addr,
+            accept_task,
+            subscriptions: Mutex::new(Vec::new()),
+        })
+    }
+
+    // Only set for TCP, e.g. to find out which port was picked for port 0
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.frames.receiver_count()
+    }
+
+    pub async fn bridge<T>(&self, event: &Event<T>)
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        let frames = self.frames.clone();
+        let event_name = event.name.clone();
+
+        let subscription: SubscriptionHandle<T> = event
+            .subscribe_closure(
+                format!("{}_bridge", event.name),
+                move |data| {
+                    let frame = BridgeFrame {
+                        event: event_name.clone(),
+                        payload: serde_json::to_value(&*data)?,
+                    };
+
+                    // Fails only if no client is connected, which is fine
+                    let _ = frames.send(Arc::new(serde_json::to_string(&frame)?));
+                    Ok(())
+                },
+                true,
+                false,
+            )
+            .await;
+
+        self.subscriptions.lock().await.push(Box::new(subscription));
+    }
+}
+
+impl Drop for EventBridgeServer {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+async fn send_frames<W>(mut writer: W, mut frames: broadcast::Receiver<Arc<String>>)
+where
+    W: AsyncWrite + Unpin,
+{
+    loop {
+        let frame = match frames.recv().await {
+            Ok(frame) => frame,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::warn!(
+                    "Event bridge client fell behind and missed {} frame(s).",
+                    skipped
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        let written = async {
+            writer.write_all(frame.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            writer.flush().await
+        };
+
+        if written.await.is_err() {
+            return;
+        }
+    }
+}
+
+// Dispatches the frames received from a bridge server to local events of the same name
+#[derive(Default)]
+pub struct EventBridgeClient {
+    handlers: HashMap<String, InboundHandler>,
+}
+
+impl EventBridgeClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_event<T>(mut self, event: Arc<Event<T>>) -> Self
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let event_name = event.name.clone();
+        let handler: InboundHandler = Box::new(move |payload| {
+            let event = Arc::clone(&event);
+            Box::pin(async move {
+                let data: T = serde_json::from_value(payload)?;
+
+                // Failed deliveries are logged by the local event's subscribers
+                let _ = event.dispatch(Arc::new(data)).await;
+                Ok(())
+            })
+        });
+
+        self.handlers.insert(event_name, handler);
+        self
+    }
+
+    pub fn is_handled(&self, event_name: &str) -> bool {
+        self.handlers.contains_key(event_name)
+    }
+
+    // Frames for events that weren't registered are ignored
+    pub async fn handle_frame(&self, frame: BridgeFrame) -> Result<(), BridgeError> {
+        match self.handlers.get(&frame.event) {
+            Some(handler) => handler(frame.payload).await,
+            None => Ok(()),
+        }
+    }
+
+    // Runs until the server closes the connection
+    pub async fn run(&self, address: BridgeAddress) -> Result<(), BridgeError> {
+        match address {
+            BridgeAddress::Tcp(address) => {
+                self.receive_frames(TcpStream::connect(address).await?)
+                    .await
+            }
+            #[cfg(unix)]
+            BridgeAddress::Unix(path) => {
+                self.receive_frames(UnixStream::connect(path).await?).await
+            }
+        }
+    }
+
+    async fn receive_frames<R>(&self, reader: R) -> Result<(), BridgeError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let result = match serde_json::from_str::<BridgeFrame>(&line) {
+                Ok(frame) => self.handle_frame(frame).await,
+                Err(err) => Err(err.into()),
+            };
+
+            // One bad frame shouldn't end the stream
+            if let Err(err) = result {
+                log::warn!("Event bridge client dropped a frame: {}", err);
+            }
+        }
+
+        Ok(())
+    }
+}
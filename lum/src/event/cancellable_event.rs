@@ -0,0 +1,194 @@
+use std::{
+    any::type_name,
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::service::{BoxedError, PinnedBoxedFutureResult};
+
+use super::{DispatchError, EventError};
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Propagation {
+    #[default]
+    Continue,
+
+    // The event is consumed, handlers after this one don't see it
+    Stop,
+}
+
+pub enum CancellableCallback<T>
+where
+    T: Send + Sync + 'static,
+{
+    Closure(Box<dyn Fn(Arc<T>) -> Result<Propagation, BoxedError> + Send + Sync>),
+    AsyncClosure(Box<dyn Fn(Arc<T>) -> PinnedBoxedFutureResult<Propagation> + Send + Sync>),
+}
+
+struct Handler<T>
+where
+    T: Send + Sync + 'static,
+{
+    name: String,
+    uuid: Uuid,
+    priority: i32,
+    callback: CancellableCallback<T>,
+}
+
+// Handlers run one after the other by priority, until one of them consumes the event. E.g. for commands, where the first match wins.
+pub struct CancellableEvent<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub name: String,
+    pub uuid: Uuid,
+    handlers: Mutex<Vec<Handler<T>>>,
+}
+
+impl<T> CancellableEvent<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            uuid: Uuid::new_v4(),
+            handlers: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub async fn handler_count(&self) -> usize {
+        self.handlers.lock().await.len()
+    }
+
+    pub async fn subscribe_closure<S>(
+        &self,
+        name: S,
+        priority: i32,
+        closure: impl Fn(Arc<T>) -> Result<Propagation, BoxedError> + Send + Sync + 'static,
+    ) -> Uuid
+    where
+        S: Into<String>,
+    {
+        self.subscribe(
+            name,
+            priority,
+            CancellableCallback::Closure(Box::new(closure)),
+        )
+        .await
+    }
+
+    pub async fn subscribe_async_closure<S>(
+        &self,
+        name: S,
+        priority: i32,
+        closure: impl Fn(Arc<T>) -> PinnedBoxedFutureResult<Propagation> + Send + Sync + 'static,
+    ) -> Uuid
+    where
+        S: Into<String>,
+    {
+        self.subscribe(
+            name,
+            priority,
+            CancellableCallback::AsyncClosure(Box::new(closure)),
+        )
+        .await
+    }
+
+    // Lower priorities run first. Handlers with the same priority keep their subscription order.
+    pub async fn subscribe<S>(
+        &self,
+        name: S,
+        priority: i32,
+        callback: CancellableCallback<T>,
+    ) -> Uuid
+    where
+        S: Into<String>,
+    {
+        let uuid = Uuid::new_v4();
+        let handler = Handler {
+            name: name.into(),
+            uuid,
+            priority,
+            callback,
+        };
+
+        let mut handlers = self.handlers.lock().await;
+        let index = handlers
+            .iter()
+            .position(|handler| handler.priority > priority)
+            .unwrap_or(handlers.len());
+        handlers.insert(index, handler);
+
+        uuid
+    }
+
+    pub async fn unsubscribe<UUID>(&self, uuid: &UUID) -> bool
+    where
+        UUID: AsRef<Uuid>,
+    {
+        let uuid = uuid.as_ref();
+
+        let mut handlers = self.handlers.lock().await;
+        let index = handlers.iter().position(|handler| handler.uuid == *uuid);
+
+        match index {
+            Some(index) => {
+                handlers.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /*
+        Returns the handler that consumed the event, if any.
+        A failing handler stops the propagation as well, and its error is returned.
+    */
+    pub async fn dispatch(&self, data: Arc<T>) -> Result<Option<Uuid>, EventError<T>> {
+        let handlers = self.handlers.lock().await;
+        for handler in handlers.iter() {
+            let result = match &handler.callback {
+                CancellableCallback::Closure(closure) => {
+                    closure(Arc::clone(&data)).map_err(DispatchError::Closure)
+                }
+                CancellableCallback::AsyncClosure(closure) => closure(Arc::clone(&data))
+                    .await
+                    .map_err(DispatchError::AsyncClosure),
+            };
+
+            match result {
+                Ok(Propagation::Continue) => continue,
+                Ok(Propagation::Stop) => return Ok(Some(handler.uuid)),
+                Err(source) => {
+                    return Err(EventError::Subscriber {
+                        event_name: self.name.clone(),
+                        subscriber_name: handler.name.clone(),
+                        subscriber_uuid: handler.uuid,
+                        source,
+                    });
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<T> Debug for CancellableEvent<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(type_name::<Self>())
+            .field("uuid", &self.uuid)
+            .field("name", &self.name)
+            .finish()
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use uuid::Uuid;
+
+// A delivery that failed, kept so it can be inspected and replayed later
+#[derive(Debug)]
+pub struct DeadLetter<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub event_name: String,
+    pub subscriber_name: String,
+    pub subscriber_uuid: Uuid,
+    pub sequence: u64,
+    pub payload: Arc<T>,
+    pub error: String,
+    pub timestamp: SystemTime,
+}
+
+impl<T> Clone for DeadLetter<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            event_name: self.event_name.clone(),
+            subscriber_name: self.subscriber_name.clone(),
+            subscriber_uuid: self.subscriber_uuid,
+            sequence: self.sequence,
+            payload: Arc::clone(&self.payload),
+            error: self.error.clone(),
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+// Once full, the oldest letters are dropped to make room for new ones
+#[derive(Debug)]
+pub struct DeadLetterQueue<T>
+where
+    T: Send + Sync + 'static,
+{
+    capacity: usize,
+    letters: Mutex<VecDeque<DeadLetter<T>>>,
+}
+
+impl<T> DeadLetterQueue<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            letters: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn push(&self, letter: DeadLetter<T>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Ok(mut letters) = self.letters.lock() {
+            if letters.len() >= self.capacity {
+                letters.pop_front();
+            }
+            letters.push_back(letter);
+        }
+    }
+
+    // Oldest letter first
+    pub fn letters(&self) -> Vec<DeadLetter<T>> {
+        match self.letters.lock() {
+            Ok(letters) => letters.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn take(&self) -> Vec<DeadLetter<T>> {
+        match self.letters.lock() {
+            Ok(mut letters) => letters.drain(..).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters
+            .lock()
+            .map(|letters| letters.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use tokio::sync::{
+    Notify,
+    mpsc::{Receiver, Sender, channel},
+};
+
+use crate::service::BoxedError;
+
+use super::Callback;
+
+/*
+    Dropping the oldest value needs access to the queue, which an mpsc Sender doesn't give.
+    So values are kept in a ring buffer and forwarded to the channel by a task.
+*/
+struct Relay<T> {
+    capacity: usize,
+    queue: Mutex<VecDeque<Arc<T>>>,
+    notify: Notify,
+    subscriber_dropped: AtomicBool,
+    receiver_dropped: AtomicBool,
+}
+
+// Owned by the subscriber's callback, so the forwarding task stops once the subscriber is gone
+struct RelayInput<T> {
+    relay: Arc<Relay<T>>,
+}
+
+impl<T> RelayInput<T> {
+    fn push(&self, data: Arc<T>) -> Result<(), BoxedError> {
+        if self.relay.receiver_dropped.load(Ordering::Acquire) {
+            return Err("Receiver was dropped".into());
+        }
+
+        let mut queue = self
+            .relay
+            .queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if queue.len() >= self.relay.capacity {
+            queue.pop_front();
+        }
+        queue.push_back(data);
+        drop(queue);
+
+        self.relay.notify.notify_one();
+        Ok(())
+    }
+}
+
+impl<T> Drop for RelayInput<T> {
+    fn drop(&mut self) {
+        self.relay.subscriber_dropped.store(true, Ordering::Release);
+        self.relay.notify.notify_one();
+    }
+}
+
+async fn forward<T>(relay: Arc<Relay<T>>, sender: Sender<Arc<T>>) {
+    loop {
+        loop {
+            let data = relay
+                .queue
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .pop_front();
+
+            let data = match data {
+                Some(data) => data,
+                None => break,
+            };
+
+            if sender.send(data).await.is_err() {
+                relay.receiver_dropped.store(true, Ordering::Release);
+                return;
+            }
+        }
+
+        if relay.subscriber_dropped.load(Ordering::Acquire) {
+            return;
+        }
+
+        relay.notify.notified().await;
+    }
+}
+
+// Besides the buffered values, up to two more can be in flight between the ring buffer and the receiver
+pub(crate) fn channel_dropping_oldest<T>(buffer: usize) -> (Callback<T>, Receiver<Arc<T>>)
+where
+    T: Send + Sync + 'static,
+{
+    let (sender, receiver) = channel(1);
+    let relay = Arc::new(Relay {
+        capacity: buffer.max(1),
+        queue: Mutex::new(VecDeque::with_capacity(buffer)),
+        notify: Notify::new(),
+        subscriber_dropped: AtomicBool::new(false),
+        receiver_dropped: AtomicBool::new(false),
+    });
+
+    let closed = sender.clone();
+    tokio::spawn(forward(Arc::clone(&relay), sender));
+
+    let input = RelayInput { relay };
+    let callback = Callback::Relay(
+        Box::new(move |data| input.push(data)),
+        Box::new(move || closed.is_closed()),
+    );
+
+    (callback, receiver)
+}
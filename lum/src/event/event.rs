@@ -1,16 +1,31 @@
 use crate::service::{BoxedError, PinnedBoxedFutureResult};
+use futures::future::join_all;
 use std::{
     any::type_name,
+    collections::VecDeque,
     fmt::{self, Debug, Formatter},
-    sync::Arc,
+    future::Future,
+    sync::{
+        Arc, Mutex as StdMutex, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Instant, SystemTime},
 };
+use thiserror::Error;
 use tokio::sync::{
-    Mutex,
+    Mutex, OwnedRwLockWriteGuard, broadcast,
     mpsc::{Receiver, channel},
+    oneshot, watch,
 };
+use tracing::{Span, field::Empty, instrument};
 use uuid::Uuid;
 
-use super::{Callback, DispatchError, Subscriber};
+use super::{
+    Backpressure, Callback, DeadLetter, DeadLetterQueue, DispatchError, EventMetrics, EventStream,
+    Lane, LaneReceiver, RateLimit, Sequenced, Subscriber, SubscriptionHandle,
+    drop_oldest::channel_dropping_oldest, event_metrics::EventCounters, lane_channel,
+    rate_limit::channel_rate_limited,
+};
 
 pub struct Event<T>
 where
@@ -19,7 +34,144 @@ where
     pub name: String,
 
     pub uuid: Uuid,
-    subscribers: Mutex<Vec<Subscriber<T>>>,
+
+    // Shared with SubscriptionHandles, so they can unsubscribe on drop
+    subscribers: Arc<Mutex<Vec<Arc<Subscriber<T>>>>>,
+    pub dispatch_mode: DispatchMode,
+
+    // Failed deliveries end up here if enabled, so they aren't only visible as a log line
+    // Shared with background replays, see subscribe
+    dead_letters: Option<Arc<DeadLetterQueue<T>>>,
+
+    // The last dispatched values, delivered to new subscribers. Only touched while the subscribers are locked.
+    replay_capacity: usize,
+    replay_buffer: StdMutex<VecDeque<(u64, Arc<T>)>>,
+
+    // The sequence number of the last dispatch
+    sequence: AtomicU64,
+
+    // For "current state" events. Only the latest value is kept and watchers see it right away.
+    latest: Option<watch::Sender<Option<Arc<T>>>>,
+
+    counters: EventCounters,
+    middleware: StdMutex<Vec<Middleware<T>>>,
+}
+
+// Runs before every dispatch, in registration order. It can replace the data, or return None to stop the dispatch.
+pub type Middleware<T> = Arc<dyn Fn(&str, Arc<T>) -> Option<Arc<T>> + Send + Sync>;
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DispatchMode {
+    // One subscriber after the other by priority, while the subscriber list stays locked
+    #[default]
+    Sequential,
+
+    // All subscribers at once, without holding the lock. Priorities don't apply.
+    Concurrent,
+}
+
+#[derive(Debug, Error)]
+pub enum TryDispatchError<T>
+where
+    T: Send + Sync + 'static,
+{
+    #[error("The subscribers are locked by another dispatch or subscription")]
+    Busy,
+
+    #[error("{} subscriber(s) would have blocked and {} failed", would_block.len(), errors.len())]
+    Incomplete {
+        // Subscribers with a full channel or an async closure. They didn't receive the data.
+        would_block: Vec<Uuid>,
+        errors: Vec<EventError<T>>,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum EventError<T>
+where
+    T: Send + Sync + 'static,
+{
+    #[error(
+        "Event \"{event_name}\" failed to dispatch data to subscriber {subscriber_name} ({subscriber_uuid}): {source}"
+    )]
+    Subscriber {
+        event_name: String,
+        subscriber_name: String,
+        subscriber_uuid: Uuid,
+        #[source]
+        source: DispatchError<T>,
+    },
+
+    #[error(
+        "Event \"{event_name}\" failed to dispatch data to subscribers {}",
+        subscriber_names(.errors)
+    )]
+    Subscribers {
+        event_name: String,
+        // Only Subscriber errors
+        errors: Vec<EventError<T>>,
+    },
+}
+
+impl<T> EventError<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn subscriber(event_name: &str, subscriber: &Subscriber<T>, source: DispatchError<T>) -> Self {
+        Self::Subscriber {
+            event_name: event_name.to_string(),
+            subscriber_name: subscriber.name.clone(),
+            subscriber_uuid: subscriber.uuid,
+            source,
+        }
+    }
+
+    fn from_errors(event_name: &str, mut errors: Vec<Self>) -> Result<(), Self> {
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(Self::Subscribers {
+                event_name: event_name.to_string(),
+                errors,
+            }),
+        }
+    }
+
+    pub fn dispatch_errors(&self) -> Vec<&DispatchError<T>> {
+        match self {
+            Self::Subscriber { source, .. } => vec![source],
+            Self::Subscribers { errors, .. } => errors
+                .iter()
+                .flat_map(EventError::dispatch_errors)
+                .collect(),
+        }
+    }
+
+    pub fn into_dispatch_errors(self) -> Vec<DispatchError<T>> {
+        match self {
+            Self::Subscriber { source, .. } => vec![source],
+            Self::Subscribers { errors, .. } => errors
+                .into_iter()
+                .flat_map(EventError::into_dispatch_errors)
+                .collect(),
+        }
+    }
+}
+
+fn subscriber_names<T>(errors: &[EventError<T>]) -> String
+where
+    T: Send + Sync + 'static,
+{
+    errors
+        .iter()
+        .filter_map(|error| match error {
+            EventError::Subscriber {
+                subscriber_name, ..
+            } => Some(subscriber_name.as_str()),
+            EventError::Subscribers { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 impl<T> Event<T>
@@ -33,8 +185,155 @@ where
         Self {
             name: name.into(),
             uuid: Uuid::new_v4(),
-            subscribers: Mutex::new(Vec::new()),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            dispatch_mode: DispatchMode::default(),
+            dead_letters: None,
+            replay_capacity: 0,
+            replay_buffer: StdMutex::new(VecDeque::new()),
+            sequence: AtomicU64::new(0),
+            latest: None,
+            counters: EventCounters::default(),
+            middleware: StdMutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_dispatch_mode(mut self, dispatch_mode: DispatchMode) -> Self {
+        self.dispatch_mode = dispatch_mode;
+        self
+    }
+
+    pub fn with_middleware(
+        self,
+        middleware: impl Fn(&str, Arc<T>) -> Option<Arc<T>> + Send + Sync + 'static,
+    ) -> Self {
+        self.add_middleware(middleware);
+        self
+    }
+
+    pub fn add_middleware(
+        &self,
+        middleware: impl Fn(&str, Arc<T>) -> Option<Arc<T>> + Send + Sync + 'static,
+    ) {
+        self.middleware
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(Arc::new(middleware));
+    }
+
+    pub fn with_replay(mut self, capacity: usize) -> Self {
+        self.replay_capacity = capacity;
+        self.replay_buffer = StdMutex::new(VecDeque::with_capacity(capacity));
+        self
+    }
+
+    // MQTT-style retain: new subscribers immediately receive the last dispatched value, if it fits into their buffer
+    pub fn with_retained(self) -> Self {
+        let capacity = self.replay_capacity.max(1);
+        self.with_replay(capacity)
+    }
+
+    // The value new subscribers would receive last, if the event retains or replays values
+    pub fn retained(&self) -> Option<Arc<T>> {
+        match self.replay_buffer.lock() {
+            Ok(buffer) => buffer.back().map(|(_, data)| Arc::clone(data)),
+            Err(_) => None,
+        }
+    }
+
+    // Forgets retained and replayable values, e.g. once the state they describe is gone
+    pub fn clear_retained(&self) {
+        if let Ok(mut buffer) = self.replay_buffer.lock() {
+            buffer.clear();
+        }
+    }
+
+    pub fn replay_values(&self) -> Vec<Arc<T>> {
+        self.replay_entries()
+            .into_iter()
+            .map(|(_, data)| data)
+            .collect()
+    }
+
+    fn replay_entries(&self) -> Vec<(u64, Arc<T>)> {
+        match self.replay_buffer.lock() {
+            Ok(buffer) => buffer.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Every dispatch gets the next sequence number, even if no subscriber receives it
+    pub fn sequence(&self) -> u64 {
+        self.sequence.load(Ordering::Relaxed)
+    }
+
+    pub fn with_latest_value(mut self) -> Self {
+        self.latest = Some(watch::Sender::new(None));
+        self
+    }
+
+    // None if the event doesn't keep its latest value or nothing was dispatched yet
+    pub fn latest(&self) -> Option<Arc<T>> {
+        self.latest
+            .as_ref()
+            .and_then(|latest| latest.borrow().clone())
+    }
+
+    // None if the event doesn't keep its latest value
+    pub fn watch(&self) -> Option<watch::Receiver<Option<Arc<T>>>> {
+        self.latest.as_ref().map(|latest| latest.subscribe())
+    }
+
+    pub fn with_dead_letter_queue(mut self, capacity: usize) -> Self {
+        self.dead_letters = Some(Arc::new(DeadLetterQueue::new(capacity)));
+        self
+    }
+
+    pub fn dead_letters(&self) -> Option<&DeadLetterQueue<T>> {
+        self.dead_letters.as_deref()
+    }
+
+    /*
+        Delivers the dead letters again, each to the subscriber it failed for. Letters whose subscriber is gone are dropped.
+        Deliveries that fail again end up back in the queue. Returns how many letters were delivered.
+    */
+    pub async fn replay_dead_letters(&self) -> usize {
+        let letters = match &self.dead_letters {
+            Some(dead_letters) => dead_letters.take(),
+            None => return 0,
+        };
+
+        let mut delivered = 0;
+        for letter in letters {
+            let subscriber = self
+                .subscribers
+                .lock()
+                .await
+                .iter()
+                .find(|subscriber| subscriber.uuid == letter.subscriber_uuid)
+                .cloned();
+
+            let subscriber = match subscriber {
+                Some(subscriber) => subscriber,
+                None => continue,
+            };
+
+            let result = subscriber
+                .dispatch_sequenced(letter.sequence, Arc::clone(&letter.payload))
+                .await;
+            if self.is_done(&subscriber, letter.sequence, &letter.payload, &result) {
+                self.unsubscribe(&subscriber.uuid).await;
+            }
+
+            if result.is_ok() {
+                delivered += 1;
+            }
         }
+
+        delivered
+    }
+
+    pub fn metrics(&self) -> EventMetrics {
+        self.counters.snapshot(&self.name)
     }
 
     pub async fn subscriber_count(&self) -> usize {
@@ -42,13 +341,40 @@ where
         subscribers.len()
     }
 
+    // In dispatch order
+    pub async fn subscriber_names(&self) -> Vec<String> {
+        let subscribers = self.subscribers.lock().await;
+        subscribers
+            .iter()
+            .map(|subscriber| subscriber.name.clone())
+            .collect()
+    }
+
+    // Lists the subscribers in dispatch order, one per line
+    pub async fn describe(&self) -> String {
+        let subscribers = self.subscribers.lock().await;
+
+        let mut description = format!(
+            "Event \"{}\" ({}) with {} subscriber(s)",
+            self.name,
+            self.uuid,
+            subscribers.len()
+        );
+        for subscriber in subscribers.iter() {
+            description.push_str("\n  ");
+            description.push_str(&subscriber.describe());
+        }
+
+        description
+    }
+
     pub async fn subscribe_channel<S>(
         &self,
         name: S,
         buffer: usize,
         log_on_error: bool,
         remove_on_error: bool,
-    ) -> (Uuid, Receiver<Arc<T>>)
+    ) -> (SubscriptionHandle<T>, Receiver<Arc<T>>)
     where
         S: Into<String>,
     {
@@ -60,12 +386,153 @@ where
             Callback::Channel(sender),
         );
 
-        let uuid = subscriber.uuid;
+        (self.subscribe(subscriber).await, receiver)
+    }
 
-        let mut subscribers = self.subscribers.lock().await;
-        subscribers.push(subscriber);
+    // Only values the filter accepts are sent to the channel
+    pub async fn subscribe_channel_filtered<S>(
+        &self,
+        name: S,
+        filter: impl Fn(&T) -> bool + Send + Sync + 'static,
+        buffer: usize,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> (SubscriptionHandle<T>, Receiver<Arc<T>>)
+    where
+        S: Into<String>,
+    {
+        let (sender, receiver) = channel(buffer);
+        let mut subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            remove_on_error,
+            Callback::Channel(sender),
+        );
+        subscriber.filter = Some(Box::new(filter));
+
+        (self.subscribe(subscriber).await, receiver)
+    }
+
+    // A full buffer holds up dispatching until the stream is polled. Dropping the stream unsubscribes.
+    pub async fn stream<S>(&self, name: S, buffer: usize) -> EventStream<T>
+    where
+        S: Into<String>,
+    {
+        let (subscription, receiver) = self.subscribe_channel(name, buffer, true, true).await;
+        EventStream::new(subscription, receiver)
+    }
+
+    pub async fn subscribe_channel_with_backpressure<S>(
+        &self,
+        name: S,
+        buffer: usize,
+        backpressure: Backpressure,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> (SubscriptionHandle<T>, Receiver<Arc<T>>)
+    where
+        S: Into<String>,
+    {
+        let (callback, receiver) = match backpressure {
+            Backpressure::DropOldest => channel_dropping_oldest(buffer),
+            _ => {
+                let (sender, receiver) = channel(buffer);
+                (Callback::Channel(sender), receiver)
+            }
+        };
+
+        let subscriber = Subscriber::new(name, log_on_error, remove_on_error, callback)
+            .with_backpressure(backpressure);
+
+        (self.subscribe(subscriber).await, receiver)
+    }
+
+    // Values dispatched with dispatch_urgent are received ahead of queued ones
+    pub async fn subscribe_priority_channel<S>(
+        &self,
+        name: S,
+        buffer: usize,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> (SubscriptionHandle<T>, LaneReceiver<T>)
+    where
+        S: Into<String>,
+    {
+        let (sender, receiver) = lane_channel(buffer);
+        let subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            remove_on_error,
+            Callback::PriorityChannel(sender),
+        );
+
+        (self.subscribe(subscriber).await, receiver)
+    }
+
+    // For bursty events and slow consumers, e.g. at most one status update per second
+    pub async fn subscribe_rate_limited<S>(
+        &self,
+        name: S,
+        rate_limit: RateLimit,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> (SubscriptionHandle<T>, Receiver<Arc<T>>)
+    where
+        S: Into<String>,
+    {
+        let (callback, receiver) = channel_rate_limited(rate_limit);
+        let subscriber = Subscriber::new(name, log_on_error, remove_on_error, callback);
+
+        (self.subscribe(subscriber).await, receiver)
+    }
+
+    // Values arrive with their sequence number, so gaps show which ones were missed
+    pub async fn subscribe_sequenced<S>(
+        &self,
+        name: S,
+        buffer: usize,
+        backpressure: Backpressure,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> (SubscriptionHandle<T>, Receiver<Sequenced<T>>)
+    where
+        S: Into<String>,
+    {
+        let (sender, receiver) = channel(buffer);
+        let subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            remove_on_error,
+            Callback::SequencedChannel(sender),
+        )
+        .with_backpressure(backpressure);
 
-        (uuid, receiver)
+        (self.subscribe(subscriber).await, receiver)
+    }
+
+    /*
+        More consumers can share the subscription through Receiver::resubscribe.
+        Dispatching fails once every receiver is dropped.
+    */
+    pub async fn subscribe_broadcast<S>(
+        &self,
+        name: S,
+        capacity: usize,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> (SubscriptionHandle<T>, broadcast::Receiver<Arc<T>>)
+    where
+        S: Into<String>,
+    {
+        let (sender, receiver) = broadcast::channel(capacity);
+        let subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            remove_on_error,
+            Callback::Broadcast(sender),
+        );
+
+        (self.subscribe(subscriber).await, receiver)
     }
 
     pub async fn subscribe_async_closure<S>(
@@ -74,7 +541,7 @@ where
         closure: impl Fn(Arc<T>) -> PinnedBoxedFutureResult<()> + Send + Sync + 'static,
         log_on_error: bool,
         remove_on_error: bool,
-    ) -> Uuid
+    ) -> SubscriptionHandle<T>
     where
         S: Into<String>,
     {
@@ -85,12 +552,29 @@ where
             Callback::AsyncClosure(Box::new(closure)),
         );
 
-        let uuid = subscriber.uuid;
-
-        let mut subscribers = self.subscribers.lock().await;
-        subscribers.push(subscriber);
+        self.subscribe(subscriber).await
+    }
 
-        uuid
+    // Like subscribe_async_closure, but the closure can return any future, e.g. an async block
+    pub async fn subscribe_async<S, F, Fut>(
+        &self,
+        name: S,
+        closure: F,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> SubscriptionHandle<T>
+    where
+        S: Into<String>,
+        F: Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), BoxedError>> + Send + Sync + 'static,
+    {
+        self.subscribe_async_closure(
+            name,
+            move |data| Box::pin(closure(data)),
+            log_on_error,
+            remove_on_error,
+        )
+        .await
     }
 
     pub async fn subscribe_closure<S>(
@@ -99,7 +583,7 @@ where
         closure: impl Fn(Arc<T>) -> Result<(), BoxedError> + Send + Sync + 'static,
         log_on_error: bool,
         remove_on_error: bool,
-    ) -> Uuid
+    ) -> SubscriptionHandle<T>
     where
         S: Into<String>,
     {
@@ -110,12 +594,187 @@ where
             Callback::Closure(Box::new(closure)),
         );
 
+        self.subscribe(subscriber).await
+    }
+
+    // For subscribers that need more than the subscribe_* shorthands offer, e.g. a priority
+    pub async fn subscribe(&self, subscriber: Subscriber<T>) -> SubscriptionHandle<T> {
+        self.add_subscriber(subscriber, true).await
+    }
+
+    // Without replay, the subscriber only gets values dispatched after it was added
+    async fn add_subscriber(
+        &self,
+        subscriber: Subscriber<T>,
+        replay: bool,
+    ) -> SubscriptionHandle<T> {
         let uuid = subscriber.uuid;
 
         let mut subscribers = self.subscribers.lock().await;
-        subscribers.push(subscriber);
 
-        uuid
+        /*
+            Replayed while locked, so no value dispatched in the meantime is missed or delivered twice.
+            The replay never waits, since the receiver only reaches the caller once this returns:
+            channels get the newest values that fit into their buffer, and deliveries that would block
+            (async closures) continue in the background once the subscriber is added. Until then, the
+            subscriber's replay gate holds back live dispatches, so they can't overtake older values.
+        */
+        let replayed = if replay {
+            self.replay_entries()
+        } else {
+            Vec::new()
+        };
+        let mut entries = replayed
+            .into_iter()
+            .filter(|(_, data)| subscriber.accepts(data))
+            .collect::<Vec<_>>();
+        if let Some(free) = subscriber.free_capacity() {
+            entries.drain(..entries.len().saturating_sub(free));
+        }
+
+        let mut pending = Vec::new();
+        for (sequence, data) in entries {
+            if !pending.is_empty() {
+                pending.push((sequence, data));
+                continue;
+            }
+
+            let result = match subscriber.try_dispatch_sequenced(sequence, Arc::clone(&data)) {
+                Some(result) => result,
+                None => {
+                    pending.push((sequence, data));
+                    continue;
+                }
+            };
+            if self.is_done(&subscriber, sequence, &data, &result) {
+                return SubscriptionHandle::new(uuid, Arc::downgrade(&self.subscribers));
+            }
+        }
+
+        let replay_gate = if pending.is_empty() {
+            None
+        } else {
+            Some(subscriber.hold_replay_gate().await)
+        };
+
+        let subscriber = Arc::new(subscriber);
+        let index = subscribers
+            .iter()
+            .position(|subscribed| subscribed.priority() > subscriber.priority())
+            .unwrap_or(subscribers.len());
+        subscribers.insert(index, Arc::clone(&subscriber));
+        self.counters.observe_subscribers(subscribers.len());
+        drop(subscribers);
+
+        if let Some(replay_gate) = replay_gate {
+            tokio::spawn(replay_in_background(
+                self.name.clone(),
+                subscriber,
+                pending,
+                replay_gate,
+                Arc::downgrade(&self.subscribers),
+                self.dead_letters.clone(),
+            ));
+        }
+
+        SubscriptionHandle::new(uuid, Arc::downgrade(&self.subscribers))
+    }
+
+    // The closure is only called for values the filter accepts
+    pub async fn subscribe_filtered<S>(
+        &self,
+        name: S,
+        filter: impl Fn(&T) -> bool + Send + Sync + 'static,
+        closure: impl Fn(Arc<T>) -> Result<(), BoxedError> + Send + Sync + 'static,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> SubscriptionHandle<T>
+    where
+        S: Into<String>,
+    {
+        let mut subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            remove_on_error,
+            Callback::Closure(Box::new(closure)),
+        );
+        subscriber.filter = Some(Box::new(filter));
+
+        self.subscribe(subscriber).await
+    }
+
+    // The closure is only called for the next dispatch, afterwards it is unsubscribed. Replayed values don't count.
+    pub async fn subscribe_once<S>(
+        &self,
+        name: S,
+        closure: impl Fn(Arc<T>) -> Result<(), BoxedError> + Send + Sync + 'static,
+        log_on_error: bool,
+    ) -> SubscriptionHandle<T>
+    where
+        S: Into<String>,
+    {
+        let mut subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            false,
+            Callback::Closure(Box::new(closure)),
+        );
+        subscriber.once = true;
+
+        self.add_subscriber(subscriber, false).await
+    }
+
+    // Resolves with the next dispatched value. Returns None if the subscription is removed before that.
+    pub async fn next(&self) -> Option<Arc<T>> {
+        let (sender, receiver) = oneshot::channel();
+        let sender = StdMutex::new(Some(sender));
+
+        let _subscription = self
+            .subscribe_once(
+                format!("{}_next", self.name),
+                move |data| {
+                    if let Some(sender) = sender.lock().ok().and_then(|mut sender| sender.take()) {
+                        let _ = sender.send(data);
+                    }
+
+                    Ok(())
+                },
+                false,
+            )
+            .await;
+
+        receiver.await.ok()
+    }
+
+    // Returns false if there is no such subscriber
+    pub async fn set_priority<UUID>(&self, uuid: &UUID, priority: i32) -> bool
+    where
+        UUID: AsRef<Uuid>,
+    {
+        let uuid = uuid.as_ref();
+
+        let mut subscribers = self.subscribers.lock().await;
+        let subscriber = match subscribers
+            .iter()
+            .find(|subscriber| subscriber.uuid == *uuid)
+        {
+            Some(subscriber) => subscriber,
+            None => return false,
+        };
+        subscriber.set_priority(priority);
+
+        // Stable, so subscribers with equal priority stay in subscription order
+        subscribers.sort_by_key(|subscriber| subscriber.priority());
+        true
+    }
+
+    // Dispatches do this as well. Useful for events that are rarely dispatched to. Returns how many were removed.
+    pub async fn prune_closed(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().await;
+        let removed = self.remove_closed(&mut subscribers);
+        self.counters.observe_subscribers(subscribers.len());
+
+        removed
     }
 
     pub async fn unsubscribe<UUID>(&self, uuid: &UUID) -> bool
@@ -132,53 +791,399 @@ where
         match index {
             Some(index) => {
                 subscribers.remove(index);
+                self.counters.observe_subscribers(subscribers.len());
                 true
             }
             None => false,
         }
     }
 
-    pub async fn dispatch(&self, data: Arc<T>) -> Result<(), Vec<DispatchError<T>>> {
+    // Removes every subscriber. Returns how many there were.
+    pub async fn clear(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().await;
+        let count = subscribers.len();
+        subscribers.clear();
+        self.counters.observe_subscribers(0);
+
+        count
+    }
+
+    // A dispatch stopped by middleware counts as successful
+    pub async fn dispatch(&self, data: Arc<T>) -> Result<(), EventError<T>> {
+        self.dispatch_in_lane(Lane::Normal, data).await
+    }
+
+    // Overtakes queued values at subscribers with priority lanes, see subscribe_priority_channel
+    pub async fn dispatch_urgent(&self, data: Arc<T>) -> Result<(), EventError<T>> {
+        self.dispatch_in_lane(Lane::High, data).await
+    }
+
+    #[instrument(
+        name = "dispatch",
+        level = "debug",
+        skip_all,
+        fields(event = %self.name, sequence = Empty, subscribers = Empty, errors = Empty)
+    )]
+    async fn dispatch_in_lane(&self, lane: Lane, data: Arc<T>) -> Result<(), EventError<T>> {
+        let data = match self.run_middleware(data) {
+            Some(data) => data,
+            None => return Ok(()),
+        };
+
+        let start = Instant::now();
+        let result = match self.dispatch_mode {
+            DispatchMode::Sequential => self.dispatch_sequentially(lane, data).await,
+            DispatchMode::Concurrent => self.dispatch_concurrently(lane, data).await,
+        };
+
+        let errors = result
+            .as_ref()
+            .map_or_else(|err| err.dispatch_errors().len(), |_| 0);
+        self.counters.record_dispatch(start.elapsed(), errors);
+        self.record_span(errors);
+
+        result
+    }
+
+    // Never waits, for latency-sensitive callers. Timeouts don't apply, since nothing is awaited.
+    #[instrument(
+        name = "try_dispatch",
+        level = "debug",
+        skip_all,
+        fields(event = %self.name, sequence = Empty, subscribers = Empty, errors = Empty)
+    )]
+    pub fn try_dispatch(&self, data: Arc<T>) -> Result<(), TryDispatchError<T>> {
+        let data = match self.run_middleware(data) {
+            Some(data) => data,
+            None => return Ok(()),
+        };
+
+        let start = Instant::now();
+        let mut subscribers = self
+            .subscribers
+            .try_lock()
+            .map_err(|_| TryDispatchError::Busy)?;
+        self.remove_closed(&mut subscribers);
+        let sequence = self.remember(&data);
+
+        let mut would_block = Vec::new();
         let mut errors = Vec::new();
         let mut subscribers_to_remove = Vec::new();
+        for subscriber in subscribers.iter() {
+            if !subscriber.accepts(&data) {
+                continue;
+            }
 
-        let mut subscribers = self.subscribers.lock().await;
-        for (index, subscriber) in subscribers.iter().enumerate() {
-            let data = Arc::clone(&data);
+            let result = match subscriber.try_dispatch_sequenced(sequence, Arc::clone(&data)) {
+                Some(result) => result,
+                None => {
+                    would_block.push(subscriber.uuid);
+                    continue;
+                }
+            };
+
+            if self.is_done(subscriber, sequence, &data, &result) {
+                subscribers_to_remove.push(subscriber.uuid);
+            }
 
-            let result = subscriber.dispatch(data).await;
             if let Err(err) = result {
-                if subscriber.log_on_error {
-                    log::error!(
-                        "Event \"{}\" failed to dispatch data to subscriber {}: {}.",
-                        self.name,
-                        subscriber.name,
-                        err
-                    );
+                errors.push(EventError::subscriber(&self.name, subscriber, err));
+            }
+        }
+
+        subscribers.retain(|subscriber| !subscribers_to_remove.contains(&subscriber.uuid));
+        self.counters.observe_subscribers(subscribers.len());
+        self.counters.record_dispatch(start.elapsed(), errors.len());
+        self.record_span(errors.len());
+
+        if would_block.is_empty() && errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TryDispatchError::Incomplete {
+                would_block,
+                errors,
+            })
+        }
+    }
+
+    /*
+        Locks the subscribers once for all items, which saves the per-dispatch overhead for high-frequency sources.
+        Each subscriber gets all items in order before the next subscriber is served, regardless of the dispatch mode.
+    */
+    #[instrument(
+        name = "dispatch_batch",
+        level = "debug",
+        skip_all,
+        fields(event = %self.name, items = items.len(), sequence = Empty, subscribers = Empty, errors = Empty)
+    )]
+    pub async fn dispatch_batch(&self, items: Vec<T>) -> Result<(), EventError<T>> {
+        let items = items
+            .into_iter()
+            .filter_map(|item| self.run_middleware(Arc::new(item)))
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let start = Instant::now();
+        let mut errors = Vec::new();
+        let mut subscribers_to_remove = Vec::new();
+
+        let mut subscribers = self.subscribers.lock().await;
+        self.remove_closed(&mut subscribers);
+        let items = items
+            .into_iter()
+            .map(|data| (self.remember(&data), data))
+            .collect::<Vec<_>>();
+
+        for subscriber in subscribers.iter() {
+            for (sequence, data) in items.iter() {
+                if !subscriber.accepts(data) {
+                    continue;
                 }
 
-                if subscriber.remove_on_error {
-                    if subscriber.log_on_error {
-                        log::error!("Subscriber will be unregistered from event.");
-                    }
+                let result = subscriber
+                    .dispatch_sequenced(*sequence, Arc::clone(data))
+                    .await;
+                let is_done = self.is_done(subscriber, *sequence, data, &result);
 
-                    subscribers_to_remove.push(index);
+                if let Err(err) = result {
+                    errors.push(EventError::subscriber(&self.name, subscriber, err));
                 }
 
-                errors.push(err);
+                if is_done {
+                    subscribers_to_remove.push(subscriber.uuid);
+                    break;
+                }
+            }
+        }
+
+        subscribers.retain(|subscriber| !subscribers_to_remove.contains(&subscriber.uuid));
+        self.counters.observe_subscribers(subscribers.len());
+        drop(subscribers);
+
+        self.counters
+            .record_dispatches(items.len() as u64, start.elapsed(), errors.len());
+        self.record_span(errors.len());
+
+        EventError::from_errors(&self.name, errors)
+    }
+
+    async fn dispatch_sequentially(&self, lane: Lane, data: Arc<T>) -> Result<(), EventError<T>> {
+        let mut errors = Vec::new();
+        let mut subscribers_to_remove = Vec::new();
+
+        let mut subscribers = self.subscribers.lock().await;
+        self.remove_closed(&mut subscribers);
+        let sequence = self.remember(&data);
+
+        for subscriber in subscribers.iter() {
+            // Filtered out values don't count as a dispatch, so once-subscribers keep waiting
+            if !subscriber.accepts(&data) {
+                continue;
+            }
+
+            let result = subscriber
+                .dispatch_in_lane(sequence, lane, Arc::clone(&data))
+                .await;
+            if self.is_done(subscriber, sequence, &data, &result) {
+                subscribers_to_remove.push(subscriber.uuid);
+            }
+
+            if let Err(err) = result {
+                errors.push(EventError::subscriber(&self.name, subscriber, err));
             }
         }
 
-        for index in subscribers_to_remove.into_iter().rev() {
-            subscribers.remove(index);
+        subscribers.retain(|subscriber| !subscribers_to_remove.contains(&subscriber.uuid));
+        self.counters.observe_subscribers(subscribers.len());
+
+        EventError::from_errors(&self.name, errors)
+    }
+
+    // Works on a snapshot of the subscribers, so (un)subscribing doesn't have to wait for slow subscribers
+    async fn dispatch_concurrently(&self, lane: Lane, data: Arc<T>) -> Result<(), EventError<T>> {
+        let (sequence, subscribers) = {
+            let mut subscribers = self.subscribers.lock().await;
+            self.remove_closed(&mut subscribers);
+            let sequence = self.remember(&data);
+            self.counters.observe_subscribers(subscribers.len());
+
+            let subscribers = subscribers
+                .iter()
+                .filter(|subscriber| subscriber.accepts(&data))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            (sequence, subscribers)
+        };
+
+        let results = join_all(
+            subscribers
+                .iter()
+                .map(|subscriber| subscriber.dispatch_in_lane(sequence, lane, Arc::clone(&data))),
+        )
+        .await;
+
+        let mut errors = Vec::new();
+        let mut subscribers_to_remove = Vec::new();
+        for (subscriber, result) in subscribers.iter().zip(results) {
+            if self.is_done(subscriber, sequence, &data, &result) {
+                subscribers_to_remove.push(subscriber.uuid);
+            }
+
+            if let Err(err) = result {
+                errors.push(EventError::subscriber(&self.name, subscriber, err));
+            }
         }
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors)
+        if !subscribers_to_remove.is_empty() {
+            let mut subscribers = self.subscribers.lock().await;
+            subscribers.retain(|subscriber| !subscribers_to_remove.contains(&subscriber.uuid));
+            self.counters.observe_subscribers(subscribers.len());
+        }
+
+        EventError::from_errors(&self.name, errors)
+    }
+
+    /*
+        Subscribers whose receivers are gone would only fail and log on every dispatch,
+        or pile up if they aren't removed on error. So they're removed before they are dispatched to.
+    */
+    fn remove_closed(&self, subscribers: &mut Vec<Arc<Subscriber<T>>>) -> usize {
+        let count = subscribers.len();
+        subscribers.retain(|subscriber| {
+            if !subscriber.is_closed() {
+                return true;
+            }
+
+            log::debug!(
+                "Removing subscriber {} from event \"{}\", because its receiver is gone.",
+                subscriber.name,
+                self.name
+            );
+            false
+        });
+
+        count - subscribers.len()
+    }
+
+    fn run_middleware(&self, data: Arc<T>) -> Option<Arc<T>> {
+        // Cloned, so middleware can register more middleware without deadlocking
+        let middleware = self
+            .middleware
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+
+        middleware
+            .iter()
+            .try_fold(data, |data, middleware| middleware(&self.name, data))
+    }
+
+    // Called with the subscribers locked, so sequence numbers follow the order subscribers see the data in
+    fn remember(&self, data: &Arc<T>) -> u64 {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        Span::current().record("sequence", sequence);
+
+        if let Some(latest) = &self.latest {
+            latest.send_replace(Some(Arc::clone(data)));
+        }
+
+        if self.replay_capacity == 0 {
+            return sequence;
+        }
+
+        if let Ok(mut buffer) = self.replay_buffer.lock() {
+            if buffer.len() >= self.replay_capacity {
+                buffer.pop_front();
+            }
+            buffer.push_back((sequence, Arc::clone(data)));
+        }
+
+        sequence
+    }
+
+    fn record_span(&self, errors: usize) {
+        let span = Span::current();
+        span.record("subscribers", self.counters.subscribers());
+        span.record("errors", errors);
+    }
+
+    /*
+        Logs failed dispatches and keeps them as dead letters.
+        Returns true if the subscriber has to be removed, because it failed or was only subscribed once.
+    */
+    fn is_done(
+        &self,
+        subscriber: &Subscriber<T>,
+        sequence: u64,
+        data: &Arc<T>,
+        result: &Result<(), DispatchError<T>>,
+    ) -> bool {
+        settle(
+            &self.name,
+            self.dead_letters.as_deref(),
+            subscriber,
+            sequence,
+            data,
+            result,
+        )
+    }
+}
+
+fn settle<T>(
+    event_name: &str,
+    dead_letters: Option<&DeadLetterQueue<T>>,
+    subscriber: &Subscriber<T>,
+    sequence: u64,
+    data: &Arc<T>,
+    result: &Result<(), DispatchError<T>>,
+) -> bool
+where
+    T: Send + Sync + 'static,
+{
+    match result {
+        Ok(()) => tracing::trace!(subscriber = %subscriber.name, sequence, "Delivered"),
+        Err(err) => {
+            tracing::debug!(subscriber = %subscriber.name, sequence, error = %err, "Delivery failed")
         }
     }
+
+    if let Err(err) = result {
+        if let Some(dead_letters) = dead_letters {
+            dead_letters.push(DeadLetter {
+                event_name: event_name.to_string(),
+                subscriber_name: subscriber.name.clone(),
+                subscriber_uuid: subscriber.uuid,
+                sequence,
+                payload: Arc::clone(data),
+                error: err.to_string(),
+                timestamp: SystemTime::now(),
+            });
+        }
+
+        if subscriber.log_on_error {
+            log::error!(
+                "Event \"{}\" failed to dispatch data to subscriber \"{}\" ({}): {}.",
+                event_name,
+                subscriber.name,
+                subscriber.uuid,
+                err
+            );
+        }
+
+        if subscriber.remove_on_error {
+            if subscriber.log_on_error {
+                log::error!("Subscriber will be unregistered from event.");
+            }
+
+            return true;
+        }
+    }
+
+    subscriber.once
 }
 
 impl<T> PartialEq for Event<T>
@@ -204,3 +1209,41 @@ where
             .finish()
     }
 }
+
+// Delivers replayed values that couldn't be delivered without waiting, after the subscriber was added
+async fn replay_in_background<T>(
+    event_name: String,
+    subscriber: Arc<Subscriber<T>>,
+    entries: Vec<(u64, Arc<T>)>,
+    replay_gate: OwnedRwLockWriteGuard<()>,
+    subscribers: Weak<Mutex<Vec<Arc<Subscriber<T>>>>>,
+    dead_letters: Option<Arc<DeadLetterQueue<T>>>,
+) where
+    T: Send + Sync + 'static,
+{
+    for (sequence, data) in entries {
+        let result = subscriber
+            .dispatch_replayed(sequence, Arc::clone(&data))
+            .await;
+        let done = settle(
+            &event_name,
+            dead_letters.as_deref(),
+            &subscriber,
+            sequence,
+            &data,
+            &result,
+        );
+
+        if done {
+            // A sequential dispatch may be waiting for the gate while it holds the subscribers
+            drop(replay_gate);
+            if let Some(subscribers) = subscribers.upgrade() {
+                subscribers
+                    .lock()
+                    .await
+                    .retain(|subscribed| subscribed.uuid != subscriber.uuid);
+            }
+            return;
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    any::{Any, TypeId, type_name},
+    collections::HashMap,
+    fmt::{self, Debug, Display, Formatter},
+    sync::{Arc, Mutex},
+};
+
+use futures::future::{BoxFuture, join_all};
+
+use super::{Event, EventMetrics, Middleware};
+
+type EventKey = (TypeId, Option<String>);
+
+// What the bus needs from its events without knowing their payload type
+trait BusEvent: Any + Send + Sync {
+    fn metrics(&self) -> EventMetrics;
+    fn payload_type(&self) -> &'static str;
+    fn subscriber_names(&self) -> BoxFuture<'_, Vec<String>>;
+}
+
+impl<T> BusEvent for Event<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn metrics(&self) -> EventMetrics {
+        Event::metrics(self)
+    }
+
+    fn payload_type(&self) -> &'static str {
+        type_name::<T>()
+    }
+
+    fn subscriber_names(&self) -> BoxFuture<'_, Vec<String>> {
+        Box::pin(Event::subscriber_names(self))
+    }
+}
+
+// A snapshot of one event on the bus, e.g. for an admin command listing who listens to what
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventInfo {
+    pub name: String,
+    pub payload_type: &'static str,
+
+    // In dispatch order
+    pub subscribers: Vec<String>,
+}
+
+impl Display for EventInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} subscriber(s)",
+            self.name,
+            self.payload_type,
+            self.subscribers.len()
+        )?;
+
+        match self.subscribers.is_empty() {
+            true => Ok(()),
+            false => write!(f, ": {}", self.subscribers.join(", ")),
+        }
+    }
+}
+
+// Owns events by payload type (and optionally a name), so modules can share them without wiring Arcs by hand
+#[derive(Default)]
+pub struct EventBus {
+    events: Mutex<HashMap<EventKey, Arc<dyn BusEvent>>>,
+
+    // Vec<Middleware<T>> by TypeId::of::<T>, added to every event of that payload type
+    middleware: Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Creates the event on first use
+    pub fn event<T>(&self) -> Arc<Event<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.get_or_create(None)
+    }
+
+    pub fn named_event<T, S>(&self, name: S) -> Arc<Event<T>>
+    where
+        T: Send + Sync + 'static,
+        S: Into<String>,
+    {
+        self.get_or_create(Some(name.into()))
+    }
+
+    // For events that need configuration, like a replay buffer. Returns the event that was registered before, if any.
+    pub fn insert<T>(&self, name: Option<String>, event: Arc<Event<T>>) -> Option<Arc<Event<T>>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut events = self
+            .events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        self.apply_middleware(&event);
+        let previous = events.insert((TypeId::of::<T>(), name), event);
+
+        previous.and_then(|previous| (previous as Arc<dyn Any + Send + Sync>).downcast().ok())
+    }
+
+    // Applies to all events of the payload type on the bus, including ones created or inserted later
+    pub fn add_middleware<T>(
+        &self,
+        middleware: impl Fn(&str, Arc<T>) -> Option<Arc<T>> + Send + Sync + 'static,
+    ) where
+        T: Send + Sync + 'static,
+    {
+        let middleware: Middleware<T> = Arc::new(middleware);
+
+        let events = self
+            .events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for ((type_id, _), event) in events.iter() {
+            if *type_id != TypeId::of::<T>() {
+                continue;
+            }
+
+            if let Ok(event) =
+                (Arc::clone(event) as Arc<dyn Any + Send + Sync>).downcast::<Event<T>>()
+            {
+                let middleware = Arc::clone(&middleware);
+                event.add_middleware(move |name, data| middleware(name, data));
+            }
+        }
+
+        self.middleware
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<Middleware<T>>::new()))
+            .downcast_mut::<Vec<Middleware<T>>>()
+            .expect("EventBus middleware has the wrong type")
+            .push(middleware);
+    }
+
+    // Sorted by event name
+    pub fn metrics(&self) -> Vec<EventMetrics> {
+        let mut metrics = self
+            .events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .map(|event| event.metrics())
+            .collect::<Vec<_>>();
+
+        metrics.sort_by(|a, b| a.event_name.cmp(&b.event_name));
+        metrics
+    }
+
+    // Sorted by event name
+    pub async fn introspect(&self) -> Vec<EventInfo> {
+        // Collected first, so the registry isn't locked while waiting for the events' subscriber lists
+        let events = self
+            .events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut infos = join_all(events.iter().map(|event| async move {
+            EventInfo {
+                name: event.metrics().event_name,
+                payload_type: event.payload_type(),
+                subscribers: event.subscriber_names().await,
+            }
+        }))
+        .await;
+
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    pub fn contains<T>(&self, name: Option<&str>) -> bool
+    where
+        T: Send + Sync + 'static,
+    {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .contains_key(&(TypeId::of::<T>(), name.map(String::from)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_or_create<T>(&self, name: Option<String>) -> Arc<Event<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut events = self
+            .events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let event_name = match &name {
+            Some(name) => name.clone(),
+            None => type_name::<T>().to_string(),
+        };
+
+        let event = events.entry((TypeId::of::<T>(), name)).or_insert_with(|| {
+            let event = Arc::new(Event::<T>::new(event_name));
+            self.apply_middleware(&event);
+            event
+        });
+
+        // Entries are keyed by TypeId::of::<T>, so they always hold an Event<T>
+        (Arc::clone(event) as Arc<dyn Any + Send + Sync>)
+            .downcast::<Event<T>>()
+            .expect("EventBus entry has the wrong type")
+    }
+
+    fn apply_middleware<T>(&self, event: &Event<T>)
+    where
+        T: Send + Sync + 'static,
+    {
+        let middleware = self
+            .middleware
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let middleware = match middleware.get(&TypeId::of::<T>()) {
+            Some(middleware) => middleware,
+            None => return,
+        };
+
+        for middleware in middleware
+            .downcast_ref::<Vec<Middleware<T>>>()
+            .into_iter()
+            .flatten()
+        {
+            let middleware = Arc::clone(middleware);
+            event.add_middleware(move |name, data| middleware(name, data));
+        }
+    }
+}
+
+impl Debug for EventBus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("events", &self.len())
+            .finish()
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventMetrics {
+    pub event_name: String,
+    pub dispatches: u64,
+
+    // Failed deliveries, not failed dispatches. One dispatch can fail for several subscribers.
+    pub errors: u64,
+
+    // As of the last (un)subscription or dispatch, since dropped handles remove their subscriber lazily
+    pub subscribers: usize,
+    pub peak_subscribers: usize,
+
+    pub average_dispatch_latency: Option<Duration>,
+}
+
+impl Display for EventMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} dispatch(es), {} error(s), {} subscriber(s) (peak {})",
+            self.event_name, self.dispatches, self.errors, self.subscribers, self.peak_subscribers
+        )?;
+
+        match self.average_dispatch_latency {
+            Some(latency) => write!(f, ", {}µs average latency", latency.as_micros()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct EventCounters {
+    dispatches: AtomicU64,
+    errors: AtomicU64,
+    subscribers: AtomicUsize,
+    peak_subscribers: AtomicUsize,
+    total_latency_nanos: AtomicU64,
+}
+
+impl EventCounters {
+    pub(crate) fn record_dispatch(&self, latency: Duration, errors: usize) {
+        self.record_dispatches(1, latency, errors);
+    }
+
+    pub(crate) fn record_dispatches(&self, dispatches: u64, latency: Duration, errors: usize) {
+        self.dispatches.fetch_add(dispatches, Ordering::Relaxed);
+        self.errors.fetch_add(errors as u64, Ordering::Relaxed);
+        self.total_latency_nanos
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn observe_subscribers(&self, count: usize) {
+        self.subscribers.store(count, Ordering::Relaxed);
+        self.peak_subscribers.fetch_max(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn subscribers(&self) -> usize {
+        self.subscribers.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn snapshot(&self, event_name: &str) -> EventMetrics {
+        let dispatches = self.dispatches.load(Ordering::Relaxed);
+        let average_dispatch_latency = match dispatches {
+            0 => None,
+            dispatches => Some(Duration::from_nanos(
+                self.total_latency_nanos.load(Ordering::Relaxed) / dispatches,
+            )),
+        };
+
+        EventMetrics {
+            event_name: event_name.to_string(),
+            dispatches,
+            errors: self.errors.load(Ordering::Relaxed),
+            subscribers: self.subscribers.load(Ordering::Relaxed),
+            peak_subscribers: self.peak_subscribers.load(Ordering::Relaxed),
+            average_dispatch_latency,
+        }
+    }
+}
@@ -7,7 +7,7 @@ use thiserror::Error;
 use tokio::{sync::Mutex, task::JoinHandle};
 use uuid::Uuid;
 
-use super::Event;
+use super::{Event, SubscriptionHandle};
 
 #[derive(Debug, Error)]
 pub enum AttachError {
@@ -48,13 +48,16 @@ where
     AttachedEvents(EventRepeater<T>),
 }
 
+// The repeater's subscription to an attached event and the task forwarding its values
+type Subscription<T> = (SubscriptionHandle<T>, JoinHandle<()>);
+
 pub struct EventRepeater<T>
 where
     T: Send + Sync + 'static,
 {
     pub event: Event<T>,
     weak: OnceLock<Weak<Self>>,
-    subscriptions: Mutex<HashMap<Uuid, (Uuid, JoinHandle<()>)>>,
+    subscriptions: Mutex<HashMap<Uuid, Subscription<T>>>,
 }
 
 impl<T> EventRepeater<T>
@@ -129,7 +132,7 @@ where
             });
         }
 
-        let (uuid, mut receiver) = event
+        let (subscription, mut receiver) = event
             .subscribe_channel(&self.event.name, buffer, true, true)
             .await;
 
@@ -138,7 +141,7 @@ where
                 let _ = arc.event.dispatch(value).await;
             }
         });
-        subscriptions.insert(event.uuid, (uuid, join_handle));
+        subscriptions.insert(event.uuid, (subscription, join_handle));
 
         Ok(())
     }
@@ -155,7 +158,9 @@ where
                 });
             }
         };
-        subscription.1.abort();
+        let (subscription, join_handle) = subscription;
+        join_handle.abort();
+        subscription.unsubscribe().await;
 
         Ok(())
     }
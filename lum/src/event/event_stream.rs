@@ -0,0 +1,47 @@
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::Stream;
+use tokio::sync::mpsc::Receiver;
+
+use super::SubscriptionHandle;
+
+// A channel subscription as a Stream. Dropping the stream unsubscribes.
+#[derive(Debug)]
+pub struct EventStream<T>
+where
+    T: Send + Sync + 'static,
+{
+    subscription: SubscriptionHandle<T>,
+    receiver: Receiver<Arc<T>>,
+}
+
+impl<T> EventStream<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub(crate) fn new(subscription: SubscriptionHandle<T>, receiver: Receiver<Arc<T>>) -> Self {
+        Self {
+            subscription,
+            receiver,
+        }
+    }
+
+    pub fn subscription(&self) -> &SubscriptionHandle<T> {
+        &self.subscription
+    }
+}
+
+impl<T> Stream for EventStream<T>
+where
+    T: Send + Sync + 'static,
+{
+    type Item = Arc<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
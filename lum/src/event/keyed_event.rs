@@ -0,0 +1,107 @@
+use std::{
+    any::type_name,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    hash::Hash,
+    sync::Arc,
+};
+
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::{Event, EventError};
+
+// One event per key (e.g. a guild ID), so dispatching only reaches the subscribers of that key
+pub struct KeyedEvent<K, T>
+where
+    K: Eq + Hash + Clone + Debug + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    pub name: String,
+    pub uuid: Uuid,
+    partitions: RwLock<HashMap<K, Arc<Event<T>>>>,
+}
+
+impl<K, T> KeyedEvent<K, T>
+where
+    K: Eq + Hash + Clone + Debug + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            uuid: Uuid::new_v4(),
+            partitions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // Created on first use. Subscribe to it to receive the key's data.
+    pub async fn partition(&self, key: &K) -> Arc<Event<T>> {
+        if let Some(partition) = self.partitions.read().await.get(key) {
+            return Arc::clone(partition);
+        }
+
+        let mut partitions = self.partitions.write().await;
+        let partition = partitions
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(Event::new(format!("{}[{:?}]", self.name, key))));
+
+        Arc::clone(partition)
+    }
+
+    // Keys nobody subscribed to have no partition, so there is nothing to dispatch to
+    pub async fn dispatch(&self, key: &K, data: Arc<T>) -> Result<(), EventError<T>> {
+        let partition = match self.partitions.read().await.get(key) {
+            Some(partition) => Arc::clone(partition),
+            None => return Ok(()),
+        };
+
+        partition.dispatch(data).await
+    }
+
+    pub async fn remove_partition(&self, key: &K) -> Option<Arc<Event<T>>> {
+        self.partitions.write().await.remove(key)
+    }
+
+    pub async fn keys(&self) -> Vec<K> {
+        self.partitions.read().await.keys().cloned().collect()
+    }
+
+    pub async fn partition_count(&self) -> usize {
+        self.partitions.read().await.len()
+    }
+
+    // Removes partitions without subscribers. Returns how many were removed.
+    pub async fn prune(&self) -> usize {
+        let mut partitions = self.partitions.write().await;
+
+        let mut empty = Vec::new();
+        for (key, partition) in partitions.iter() {
+            if partition.subscriber_count().await == 0 {
+                empty.push(key.clone());
+            }
+        }
+
+        for key in empty.iter() {
+            partitions.remove(key);
+        }
+
+        empty.len()
+    }
+}
+
+impl<K, T> Debug for KeyedEvent<K, T>
+where
+    K: Eq + Hash + Clone + Debug + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(type_name::<Self>())
+            .field("uuid", &self.uuid)
+            .field("name", &self.name)
+            .finish()
+    }
+}
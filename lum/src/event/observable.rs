@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use tokio::sync::Mutex;
 
-use super::{DispatchError, Event};
+use super::{Event, EventError};
 
 #[derive(Debug)]
 pub enum ObservableResult<T>
@@ -10,7 +10,7 @@ where
     T: Send + Sync + 'static,
 {
     Unchanged,
-    Changed(Result<(), Vec<DispatchError<T>>>),
+    Changed(Result<(), EventError<T>>),
 }
 
 #[derive(Debug)]
@@ -56,7 +56,7 @@ where
 
         match dispatch_result {
             Ok(_) => ObservableResult::Changed(Ok(())),
-            Err(errors) => ObservableResult::Changed(Err(errors)),
+            Err(err) => ObservableResult::Changed(Err(err)),
         }
     }
 }
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use tokio::sync::mpsc::{
+    Receiver, Sender, channel,
+    error::{TryRecvError, TrySendError},
+};
+
+// Which queue a dispatch goes through for subscribers with priority lanes. Other subscribers ignore it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Lane {
+    #[default]
+    Normal,
+
+    // Delivered ahead of queued Normal values, e.g. shutdown or error notifications
+    High,
+}
+
+pub struct LaneSender<T> {
+    high: Sender<Arc<T>>,
+    normal: Sender<Arc<T>>,
+}
+
+impl<T> LaneSender<T> {
+    pub fn sender(&self, lane: Lane) -> &Sender<Arc<T>> {
+        match lane {
+            Lane::Normal => &self.normal,
+            Lane::High => &self.high,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.normal.is_closed() && self.high.is_closed()
+    }
+
+    pub fn try_send(&self, lane: Lane, data: Arc<T>) -> Result<(), TrySendError<Arc<T>>> {
+        self.sender(lane).try_send(data)
+    }
+}
+
+impl<T> Clone for LaneSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            high: self.high.clone(),
+            normal: self.normal.clone(),
+        }
+    }
+}
+
+// Always drains the High lane first. Values within a lane keep their order.
+pub struct LaneReceiver<T> {
+    high: Receiver<Arc<T>>,
+    normal: Receiver<Arc<T>>,
+}
+
+impl<T> LaneReceiver<T> {
+    // None once both lanes are closed and empty
+    pub async fn recv(&mut self) -> Option<(Lane, Arc<T>)> {
+        tokio::select! {
+            biased;
+
+            Some(data) = self.high.recv() => Some((Lane::High, data)),
+            Some(data) = self.normal.recv() => Some((Lane::Normal, data)),
+            else => None,
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<(Lane, Arc<T>), TryRecvError> {
+        match self.high.try_recv() {
+            Ok(data) => Ok((Lane::High, data)),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => {
+                self.normal.try_recv().map(|data| (Lane::Normal, data))
+            }
+        }
+    }
+}
+
+// Each lane buffers up to buffer values on its own, so a full Normal lane never holds back High values
+pub fn lane_channel<T>(buffer: usize) -> (LaneSender<T>, LaneReceiver<T>) {
+    let (high, high_receiver) = channel(buffer);
+    let (normal, normal_receiver) = channel(buffer);
+
+    (
+        LaneSender { high, normal },
+        LaneReceiver {
+            high: high_receiver,
+            normal: normal_receiver,
+        },
+    )
+}
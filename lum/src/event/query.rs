@@ -0,0 +1,143 @@
+use std::{
+    any::type_name,
+    fmt::{self, Debug, Formatter},
+    future::ready,
+    sync::Arc,
+    time::Duration,
+};
+
+use thiserror::Error;
+use tokio::{sync::RwLock, time::timeout};
+use uuid::Uuid;
+
+use crate::service::{BoxedError, PinnedBoxedFutureResult};
+
+type Responder<Req, Resp> = Arc<dyn Fn(Arc<Req>) -> PinnedBoxedFutureResult<Resp> + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum QueryError {
+    #[error("Query {0} has no responder")]
+    NoResponder(String),
+
+    #[error("Query {0} already has a responder")]
+    ResponderTaken(String),
+
+    #[error("Query {name} was not answered within {}ms", timeout.as_millis())]
+    Timeout { name: String, timeout: Duration },
+
+    #[error("Responder of query {name} failed: {source}")]
+    Responder {
+        name: String,
+        #[source]
+        source: BoxedError,
+    },
+}
+
+// A request answered by exactly one responder, for when a oneshot channel would otherwise be put into an event's payload
+pub struct Query<Req, Resp>
+where
+    Req: Send + Sync + 'static,
+    Resp: Send + 'static,
+{
+    pub name: String,
+    pub uuid: Uuid,
+    pub timeout: Duration,
+    responder: RwLock<Option<(Uuid, Responder<Req, Resp>)>>,
+}
+
+impl<Req, Resp> Query<Req, Resp>
+where
+    Req: Send + Sync + 'static,
+    Resp: Send + 'static,
+{
+    pub fn new<S>(name: S, timeout: Duration) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            uuid: Uuid::new_v4(),
+            timeout,
+            responder: RwLock::new(None),
+        }
+    }
+
+    pub async fn respond_async_closure(
+        &self,
+        closure: impl Fn(Arc<Req>) -> PinnedBoxedFutureResult<Resp> + Send + Sync + 'static,
+    ) -> Result<Uuid, QueryError> {
+        let mut responder = self.responder.write().await;
+        if responder.is_some() {
+            return Err(QueryError::ResponderTaken(self.name.clone()));
+        }
+
+        let uuid = Uuid::new_v4();
+        *responder = Some((uuid, Arc::new(closure)));
+
+        Ok(uuid)
+    }
+
+    pub async fn respond_closure(
+        &self,
+        closure: impl Fn(Arc<Req>) -> Result<Resp, BoxedError> + Send + Sync + 'static,
+    ) -> Result<Uuid, QueryError>
+    where
+        Resp: Sync,
+    {
+        self.respond_async_closure(move |request| Box::pin(ready(closure(request))))
+            .await
+    }
+
+    // Only removes the responder if it is still the one with this UUID
+    pub async fn remove_responder<UUID>(&self, uuid: &UUID) -> bool
+    where
+        UUID: AsRef<Uuid>,
+    {
+        let mut responder = self.responder.write().await;
+        match &*responder {
+            Some((current, _)) if current == uuid.as_ref() => {
+                *responder = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub async fn has_responder(&self) -> bool {
+        self.responder.read().await.is_some()
+    }
+
+    pub async fn request(&self, request: Req) -> Result<Resp, QueryError> {
+        // Cloned out, so the responder can be replaced while a request is in flight
+        let responder = match &*self.responder.read().await {
+            Some((_, responder)) => Arc::clone(responder),
+            None => return Err(QueryError::NoResponder(self.name.clone())),
+        };
+
+        match timeout(self.timeout, responder(Arc::new(request))).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(source)) => Err(QueryError::Responder {
+                name: self.name.clone(),
+                source,
+            }),
+            Err(_) => Err(QueryError::Timeout {
+                name: self.name.clone(),
+                timeout: self.timeout,
+            }),
+        }
+    }
+}
+
+impl<Req, Resp> Debug for Query<Req, Resp>
+where
+    Req: Send + Sync + 'static,
+    Resp: Send + 'static,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(type_name::<Self>())
+            .field("uuid", &self.uuid)
+            .field("name", &self.name)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
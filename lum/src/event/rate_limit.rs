@@ -0,0 +1,162 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::{
+    select,
+    sync::{
+        Notify,
+        mpsc::{Receiver, Sender, channel},
+    },
+    time::{Instant, sleep, sleep_until},
+};
+
+use crate::service::BoxedError;
+
+use super::Callback;
+
+// How a rate-limited subscriber thins out bursts. Values that are skipped are dropped, not queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimit {
+    // Delivers a value, then drops everything dispatched during the interval
+    Throttle(Duration),
+
+    // Delivers the last value once nothing new was dispatched for the duration
+    Debounce(Duration),
+
+    // Delivers the latest value at most once per period, starting a period after a value arrives
+    Sample(Duration),
+}
+
+// Only the latest value is kept, so a slow receiver gets the most recent state instead of a backlog
+struct Slot<T> {
+    latest: Mutex<Option<Arc<T>>>,
+    notify: Notify,
+    subscriber_dropped: AtomicBool,
+    receiver_dropped: AtomicBool,
+}
+
+impl<T> Slot<T> {
+    fn take(&self) -> Option<Arc<T>> {
+        self.latest
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+    }
+
+    // None once the subscriber is gone
+    async fn next(&self) -> Option<Arc<T>> {
+        loop {
+            if let Some(data) = self.take() {
+                return Some(data);
+            }
+
+            if self.subscriber_dropped.load(Ordering::Acquire) {
+                return None;
+            }
+
+            self.notify.notified().await;
+        }
+    }
+}
+
+// Owned by the subscriber's callback, so the forwarding task stops once the subscriber is gone
+struct SlotInput<T> {
+    slot: Arc<Slot<T>>,
+}
+
+impl<T> SlotInput<T> {
+    fn push(&self, data: Arc<T>) -> Result<(), BoxedError> {
+        if self.slot.receiver_dropped.load(Ordering::Acquire) {
+            return Err("Receiver was dropped".into());
+        }
+
+        *self
+            .slot
+            .latest
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(data);
+
+        self.slot.notify.notify_one();
+        Ok(())
+    }
+}
+
+impl<T> Drop for SlotInput<T> {
+    fn drop(&mut self) {
+        self.slot.subscriber_dropped.store(true, Ordering::Release);
+        self.slot.notify.notify_one();
+    }
+}
+
+async fn forward<T>(slot: Arc<Slot<T>>, rate_limit: RateLimit, sender: Sender<Arc<T>>) {
+    while let Some(mut data) = slot.next().await {
+        match rate_limit {
+            RateLimit::Throttle(interval) => {
+                if sender.send(data).await.is_err() {
+                    break;
+                }
+
+                sleep(interval).await;
+                slot.take();
+                continue;
+            }
+            RateLimit::Debounce(quiet) => {
+                let mut deadline = Instant::now() + quiet;
+                loop {
+                    select! {
+                        _ = slot.notify.notified() => {
+                            if let Some(newer) = slot.take() {
+                                data = newer;
+                                deadline = Instant::now() + quiet;
+                            } else if slot.subscriber_dropped.load(Ordering::Acquire) {
+                                return;
+                            }
+                        }
+                        _ = sleep_until(deadline) => break,
+                    }
+                }
+            }
+            RateLimit::Sample(period) => {
+                sleep(period).await;
+                if let Some(newer) = slot.take() {
+                    data = newer;
+                }
+            }
+        }
+
+        if sender.send(data).await.is_err() {
+            break;
+        }
+    }
+
+    slot.receiver_dropped.store(true, Ordering::Release);
+}
+
+pub(crate) fn channel_rate_limited<T>(rate_limit: RateLimit) -> (Callback<T>, Receiver<Arc<T>>)
+where
+    T: Send + Sync + 'static,
+{
+    let (sender, receiver) = channel(1);
+    let slot = Arc::new(Slot {
+        latest: Mutex::new(None),
+        notify: Notify::new(),
+        subscriber_dropped: AtomicBool::new(false),
+        receiver_dropped: AtomicBool::new(false),
+    });
+
+    let closed = sender.clone();
+    tokio::spawn(forward(Arc::clone(&slot), rate_limit, sender));
+
+    let input = SlotInput { slot };
+    let callback = Callback::Relay(
+        Box::new(move |data| input.push(data)),
+        Box::new(move || closed.is_closed()),
+    );
+
+    (callback, receiver)
+}
@@ -1,20 +1,117 @@
-use std::sync::Arc;
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicI32, Ordering},
+    },
+    time::Duration,
+};
 
 use thiserror::Error;
-use tokio::sync::mpsc::{Sender, error::SendError};
+use tokio::{
+    sync::{
+        OwnedRwLockWriteGuard, RwLock, broadcast,
+        mpsc::{
+            Sender,
+            error::{SendError, TrySendError},
+        },
+    },
+    time::timeout,
+};
+use tracing::{Instrument, Span, trace_span};
 use uuid::Uuid;
 
 use crate::service::{BoxedError, PinnedBoxedFutureResult};
 
+use super::{Lane, LaneSender};
+
 pub enum Callback<T>
 where
     T: Send + Sync + 'static,
 {
     Channel(Sender<Arc<T>>),
+
+    // Like Channel, but each value comes with its sequence number, so receivers can tell when they missed some
+    SequencedChannel(Sender<Sequenced<T>>),
+
+    // A High and a Normal queue, so urgent dispatches overtake values that are still queued
+    PriorityChannel(LaneSender<T>),
+
+    // Never blocks the dispatch. Receivers that fall behind get a Lagged error instead.
+    Broadcast(broadcast::Sender<Arc<T>>),
     Closure(Box<dyn Fn(Arc<T>) -> Result<(), BoxedError> + Send + Sync>),
+
+    // A closure feeding a task that forwards to a channel, e.g. for Backpressure::DropOldest.
+    // The second closure tells whether the channel's receiver is gone, see Subscriber::is_closed.
+    Relay(
+        Box<dyn Fn(Arc<T>) -> Result<(), BoxedError> + Send + Sync>,
+        Box<dyn Fn() -> bool + Send + Sync>,
+    ),
     AsyncClosure(Box<dyn Fn(Arc<T>) -> PinnedBoxedFutureResult<()> + Send + Sync>),
 }
 
+impl<T> Callback<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Callback::Channel(_) => "channel",
+            Callback::SequencedChannel(_) => "sequenced channel",
+            Callback::PriorityChannel(_) => "priority channel",
+            Callback::Broadcast(_) => "broadcast",
+            Callback::Closure(_) => "closure",
+            Callback::Relay(..) => "relay",
+            Callback::AsyncClosure(_) => "async closure",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Sequenced<T>
+where
+    T: Send + Sync + 'static,
+{
+    // Counted per event, starting at 1. Subscribers dispatched to outside of an event get 0.
+    pub sequence: u64,
+    pub data: Arc<T>,
+
+    // The delivery's span, so the receiver's logs can be correlated with the dispatch
+    pub span: Span,
+}
+
+impl<T> Clone for Sequenced<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            sequence: self.sequence,
+            data: Arc::clone(&self.data),
+            span: self.span.clone(),
+        }
+    }
+}
+
+// What a channel subscriber does when its buffer is full
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Backpressure {
+    // Wait for the receiver to make room, which holds up the dispatch
+    #[default]
+    Block,
+    DropNewest,
+
+    // Only possible with Event::subscribe_channel_with_backpressure, which owns the queue.
+    // Channel callbacks created elsewhere drop the newest value instead.
+    DropOldest,
+
+    // Fail the dispatch with DispatchError::ChannelFull
+    Error,
+}
+
+// Decides at dispatch time whether a subscriber receives the data
+pub type Filter<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
 #[derive(Debug, Error)]
 pub enum DispatchError<T>
 where
@@ -23,11 +120,20 @@ where
     #[error("Failed to send data to channel: {0}")]
     ChannelSend(#[from] SendError<Arc<T>>),
 
+    #[error("Channel is full")]
+    ChannelFull(Arc<T>),
+
+    #[error("Failed to send data to broadcast channel: {0}")]
+    BroadcastSend(#[from] broadcast::error::SendError<Arc<T>>),
+
     #[error("Failed to dispatch data to closure: {0}")]
     Closure(BoxedError),
 
     #[error("Failed to dispatch data to async closure: {0}")]
     AsyncClosure(BoxedError),
+
+    #[error("Dispatching data took longer than {}ms", .0.as_millis())]
+    Timeout(Duration),
 }
 
 pub struct Subscriber<T>
@@ -39,7 +145,24 @@ where
     pub remove_on_error: bool,
     pub callback: Callback<T>,
 
+    // Removed after its first dispatch, whether that succeeded or not
+    pub once: bool,
+    pub filter: Option<Filter<T>>,
+
+    // Lower values are dispatched to first. Subscribers with the same priority keep their subscription order.
+    // Atomic, so it can be changed while the subscriber is shared with a concurrent dispatch.
+    priority: AtomicI32,
+
+    // A full channel or a hanging closure fails the dispatch after this long, instead of stalling the event
+    pub timeout: Option<Duration>,
+
+    // Only applies to channel callbacks
+    pub backpressure: Backpressure,
+
     pub uuid: Uuid,
+
+    // Write-locked while Event::subscribe replays in the background, so live dispatches can't overtake the replay
+    replay_gate: Arc<RwLock<()>>,
 }
 
 impl<T> Subscriber<T>
@@ -55,16 +178,270 @@ where
             log_on_error,
             remove_on_error,
             callback,
+            once: false,
+            filter: None,
+            priority: AtomicI32::new(0),
+            timeout: None,
+            backpressure: Backpressure::default(),
             uuid: Uuid::new_v4(),
+            replay_gate: Arc::new(RwLock::new(())),
+        }
+    }
+
+    pub fn with_priority(self, priority: i32) -> Self {
+        self.priority.store(priority, Ordering::Relaxed);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+
+    // One line for diagnostics, e.g. Event::describe()
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "\"{}\" ({}): {}, priority {}",
+            self.name,
+            self.uuid,
+            self.callback.kind(),
+            self.priority()
+        );
+
+        if self.filter.is_some() {
+            description.push_str(", filtered");
+        }
+        if self.once {
+            description.push_str(", once");
+        }
+        if let Some(timeout) = self.timeout {
+            description.push_str(&format!(", {}ms timeout", timeout.as_millis()));
+        }
+        if self.is_closed() {
+            description.push_str(", closed");
+        }
+
+        description
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_priority(&self, priority: i32) {
+        self.priority.store(priority, Ordering::Relaxed);
+    }
+
+    // How many more values a channel callback takes without waiting. None for other callbacks.
+    pub(crate) fn free_capacity(&self) -> Option<usize> {
+        match &self.callback {
+            Callback::Channel(sender) => Some(sender.capacity()),
+            Callback::SequencedChannel(sender) => Some(sender.capacity()),
+            Callback::PriorityChannel(sender) => Some(sender.sender(Lane::Normal).capacity()),
+            _ => None,
+        }
+    }
+
+    // True if the channel's receiving side is gone, so no dispatch can succeed anymore
+    pub fn is_closed(&self) -> bool {
+        match &self.callback {
+            Callback::Channel(sender) => sender.is_closed(),
+            Callback::SequencedChannel(sender) => sender.is_closed(),
+            Callback::PriorityChannel(sender) => sender.is_closed(),
+            Callback::Broadcast(sender) => sender.receiver_count() == 0,
+            Callback::Relay(_, is_closed) => is_closed(),
+            Callback::Closure(_) | Callback::AsyncClosure(_) => false,
+        }
+    }
+
+    pub fn accepts(&self, data: &T) -> bool {
+        match &self.filter {
+            Some(filter) => filter(data),
+            None => true,
         }
     }
 
     pub async fn dispatch(&self, data: Arc<T>) -> Result<(), DispatchError<T>> {
+        self.dispatch_sequenced(0, data).await
+    }
+
+    pub(crate) async fn dispatch_sequenced(
+        &self,
+        sequence: u64,
+        data: Arc<T>,
+    ) -> Result<(), DispatchError<T>> {
+        self.dispatch_in_lane(sequence, Lane::Normal, data).await
+    }
+
+    pub(crate) async fn dispatch_in_lane(
+        &self,
+        sequence: u64,
+        lane: Lane,
+        data: Arc<T>,
+    ) -> Result<(), DispatchError<T>> {
+        let span = trace_span!("deliver", subscriber = %self.name, subscriber_uuid = %self.uuid);
+        let delivery = async {
+            let _replayed = self.replay_gate.read().await;
+            self.deliver(sequence, lane, data).await
+        }
+        .instrument(span);
+
+        self.with_timeout(delivery).await
+    }
+
+    // For the replay that holds the replay gate, see hold_replay_gate
+    pub(crate) async fn dispatch_replayed(
+        &self,
+        sequence: u64,
+        data: Arc<T>,
+    ) -> Result<(), DispatchError<T>> {
+        let span = trace_span!("deliver", subscriber = %self.name, subscriber_uuid = %self.uuid);
+        let delivery = self.deliver(sequence, Lane::Normal, data).instrument(span);
+
+        self.with_timeout(delivery).await
+    }
+
+    // Other dispatches wait until the guard is dropped
+    pub(crate) async fn hold_replay_gate(&self) -> OwnedRwLockWriteGuard<()> {
+        Arc::clone(&self.replay_gate).write_owned().await
+    }
+
+    async fn with_timeout(
+        &self,
+        delivery: impl Future<Output = Result<(), DispatchError<T>>>,
+    ) -> Result<(), DispatchError<T>> {
+        match self.timeout {
+            Some(duration) => timeout(duration, delivery)
+                .await
+                .unwrap_or(Err(DispatchError::Timeout(duration))),
+            None => delivery.await,
+        }
+    }
+
+    // Returns None instead of waiting, if the channel is full, the callback is an async closure or a replay is pending
+    pub fn try_dispatch(&self, data: Arc<T>) -> Option<Result<(), DispatchError<T>>> {
+        self.try_dispatch_sequenced(0, data)
+    }
+
+    pub(crate) fn try_dispatch_sequenced(
+        &self,
+        sequence: u64,
+        data: Arc<T>,
+    ) -> Option<Result<(), DispatchError<T>>> {
+        self.try_dispatch_in_lane(sequence, Lane::Normal, data)
+    }
+
+    fn try_dispatch_in_lane(
+        &self,
+        sequence: u64,
+        lane: Lane,
+        data: Arc<T>,
+    ) -> Option<Result<(), DispatchError<T>>> {
+        let _replayed = self.replay_gate.try_read().ok()?;
+        let _span =
+            trace_span!("deliver", subscriber = %self.name, subscriber_uuid = %self.uuid).entered();
+
+        self.try_deliver(sequence, lane, data)
+    }
+
+    fn try_deliver(
+        &self,
+        sequence: u64,
+        lane: Lane,
+        data: Arc<T>,
+    ) -> Option<Result<(), DispatchError<T>>> {
         match &self.callback {
-            Callback::Channel(sender) => {
+            Callback::Channel(sender) => match sender.try_send(data) {
+                Ok(()) => Some(Ok(())),
+                Err(TrySendError::Full(data)) => self.on_full(data),
+                Err(TrySendError::Closed(data)) => {
+                    Some(Err(DispatchError::ChannelSend(SendError(data))))
+                }
+            },
+            Callback::PriorityChannel(sender) => match sender.try_send(lane, data) {
+                Ok(()) => Some(Ok(())),
+                Err(TrySendError::Full(data)) => self.on_full(data),
+                Err(TrySendError::Closed(data)) => {
+                    Some(Err(DispatchError::ChannelSend(SendError(data))))
+                }
+            },
+            Callback::SequencedChannel(sender) => {
+                match sender.try_send(Sequenced {
+                    sequence,
+                    data,
+                    span: Span::current(),
+                }) {
+                    Ok(()) => Some(Ok(())),
+                    Err(TrySendError::Full(sequenced)) => self.on_full(sequenced.data),
+                    Err(TrySendError::Closed(sequenced)) => {
+                        Some(Err(DispatchError::ChannelSend(SendError(sequenced.data))))
+                    }
+                }
+            }
+            Callback::Broadcast(sender) => Some(
+                sender
+                    .send(data)
+                    .map(|_| ())
+                    .map_err(DispatchError::BroadcastSend),
+            ),
+            Callback::Closure(closure) | Callback::Relay(closure, _) => {
+                Some(closure(data).map_err(DispatchError::Closure))
+            }
+            Callback::AsyncClosure(_) => None,
+        }
+    }
+
+    fn on_full(&self, data: Arc<T>) -> Option<Result<(), DispatchError<T>>> {
+        match self.backpressure {
+            Backpressure::Block => None,
+            Backpressure::DropNewest | Backpressure::DropOldest => Some(Ok(())),
+            Backpressure::Error => Some(Err(DispatchError::ChannelFull(data))),
+        }
+    }
+
+    async fn deliver(
+        &self,
+        sequence: u64,
+        lane: Lane,
+        data: Arc<T>,
+    ) -> Result<(), DispatchError<T>> {
+        match &self.callback {
+            Callback::Channel(sender) if self.backpressure == Backpressure::Block => {
                 sender.send(data).await.map_err(DispatchError::ChannelSend)
             }
-            Callback::Closure(closure) => closure(data).map_err(DispatchError::Closure),
+            Callback::SequencedChannel(sender) if self.backpressure == Backpressure::Block => {
+                sender
+                    .send(Sequenced {
+                        sequence,
+                        data,
+                        span: Span::current(),
+                    })
+                    .await
+                    .map_err(|SendError(sequenced)| {
+                        DispatchError::ChannelSend(SendError(sequenced.data))
+                    })
+            }
+            Callback::PriorityChannel(sender) if self.backpressure == Backpressure::Block => sender
+                .sender(lane)
+                .send(data)
+                .await
+                .map_err(DispatchError::ChannelSend),
+            Callback::Channel(_) | Callback::SequencedChannel(_) | Callback::PriorityChannel(_) => {
+                self.try_deliver(sequence, lane, data)
+                    .unwrap_or_else(|| unreachable!("Only blocking channels have to wait"))
+            }
+            Callback::Broadcast(sender) => sender
+                .send(data)
+                .map(|_| ())
+                .map_err(DispatchError::BroadcastSend),
+            Callback::Closure(closure) | Callback::Relay(closure, _) => {
+                closure(data).map_err(DispatchError::Closure)
+            }
             Callback::AsyncClosure(closure) => {
                 closure(data).await.map_err(DispatchError::AsyncClosure)
             }
@@ -0,0 +1,79 @@
+use std::fmt::{self, Debug, Formatter};
+
+use futures::future::{BoxFuture, join_all};
+use uuid::Uuid;
+
+use super::SubscriptionHandle;
+
+trait GroupedHandle: Send + Sync {
+    fn uuid(&self) -> Uuid;
+    fn unsubscribe(self: Box<Self>) -> BoxFuture<'static, bool>;
+}
+
+impl<T> GroupedHandle for SubscriptionHandle<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn uuid(&self) -> Uuid {
+        SubscriptionHandle::uuid(self)
+    }
+
+    fn unsubscribe(self: Box<Self>) -> BoxFuture<'static, bool> {
+        Box::pin(SubscriptionHandle::unsubscribe(*self))
+    }
+}
+
+// Holds the subscriptions of one component across events of any payload type, so they can be torn down together.
+// Dropping the group unsubscribes everything in it, like dropping the handles would.
+#[derive(Default)]
+#[must_use = "Dropping a SubscriptionGroup unsubscribes all of its subscriptions immediately."]
+pub struct SubscriptionGroup {
+    handles: Vec<Box<dyn GroupedHandle>>,
+}
+
+impl SubscriptionGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<T>(&mut self, handle: SubscriptionHandle<T>)
+    where
+        T: Send + Sync + 'static,
+    {
+        self.handles.push(Box::new(handle));
+    }
+
+    pub fn with<T>(mut self, handle: SubscriptionHandle<T>) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.push(handle);
+        self
+    }
+
+    pub fn uuids(&self) -> Vec<Uuid> {
+        self.handles.iter().map(|handle| handle.uuid()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    // Unlike dropping the group, waits until every subscriber is removed. Returns how many were still subscribed.
+    pub async fn unsubscribe_all(&mut self) -> usize {
+        let results = join_all(self.handles.drain(..).map(|handle| handle.unsubscribe())).await;
+        results.into_iter().filter(|removed| *removed).count()
+    }
+}
+
+impl Debug for SubscriptionGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubscriptionGroup")
+            .field("subscriptions", &self.handles.len())
+            .finish()
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    any::type_name,
+    fmt::{self, Debug, Formatter},
+    sync::{Arc, Weak},
+};
+
+use tokio::{runtime::Handle, sync::Mutex};
+use uuid::Uuid;
+
+use super::Subscriber;
+
+type Subscribers<T> = Mutex<Vec<Arc<Subscriber<T>>>>;
+
+// Unsubscribes when dropped, unless it was detached
+#[must_use = "Dropping a SubscriptionHandle unsubscribes immediately. Use detach() to keep the subscription."]
+pub struct SubscriptionHandle<T>
+where
+    T: Send + Sync + 'static,
+{
+    uuid: Uuid,
+    subscribers: Weak<Subscribers<T>>,
+    detached: bool,
+}
+
+impl<T> SubscriptionHandle<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub(crate) fn new(uuid: Uuid, subscribers: Weak<Subscribers<T>>) -> Self {
+        Self {
+            uuid,
+            subscribers,
+            detached: false,
+        }
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    // Returns false if the subscriber was already gone, e.g. because it was removed on error
+    pub async fn unsubscribe(mut self) -> bool {
+        self.detached = true;
+
+        let subscribers = match self.subscribers.upgrade() {
+            Some(subscribers) => subscribers,
+            None => return false,
+        };
+
+        let mut subscribers = subscribers.lock().await;
+        let count = subscribers.len();
+        subscribers.retain(|subscriber| subscriber.uuid != self.uuid);
+
+        subscribers.len() != count
+    }
+
+    // Keeps the subscription for as long as the event lives. It can still be removed with Event::unsubscribe.
+    pub fn detach(mut self) -> Uuid {
+        self.detached = true;
+        self.uuid
+    }
+}
+
+impl<T> Drop for SubscriptionHandle<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn drop(&mut self) {
+        if self.detached {
+            return;
+        }
+
+        let subscribers = match self.subscribers.upgrade() {
+            Some(subscribers) => subscribers,
+            None => return,
+        };
+
+        let uuid = self.uuid;
+        if let Ok(mut subscribers) = subscribers.try_lock() {
+            subscribers.retain(|subscriber| subscriber.uuid != uuid);
+            return;
+        }
+
+        // The event is busy, e.g. dispatching, so the subscriber is removed as soon as it is done
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    subscribers
+                        .lock()
+                        .await
+                        .retain(|subscriber| subscriber.uuid != uuid);
+                });
+            }
+            Err(_) => {
+                subscribers
+                    .blocking_lock()
+                    .retain(|subscriber| subscriber.uuid != uuid);
+            }
+        }
+    }
+}
+
+impl<T> AsRef<Uuid> for SubscriptionHandle<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn as_ref(&self) -> &Uuid {
+        &self.uuid
+    }
+}
+
+impl<T> Debug for SubscriptionHandle<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(type_name::<Self>())
+            .field("uuid", &self.uuid)
+            .field("detached", &self.detached)
+            .finish()
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+use tokio::sync::mpsc::{Receiver, channel};
+
+use crate::service::BoxedError;
+
+use super::{Callback, Event, EventError, Subscriber, SubscriptionHandle};
+
+#[derive(Debug)]
+pub struct TopicMessage<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub topic: String,
+    pub payload: Arc<T>,
+}
+
+/*
+    Topics are dot-separated, like "discord.message.created".
+    A "*" segment matches exactly one segment, except as the last segment, where it matches all remaining ones.
+    So "discord.*" matches "discord.message.created", and "discord.*.created" matches "discord.message.created" only.
+*/
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct TopicPattern {
+    segments: Vec<String>,
+}
+
+impl TopicPattern {
+    pub fn new<S>(pattern: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self {
+            segments: pattern.as_ref().split('.').map(String::from).collect(),
+        }
+    }
+
+    pub fn matches(&self, topic: &str) -> bool {
+        let topic = topic.split('.').collect::<Vec<_>>();
+
+        for (index, segment) in self.segments.iter().enumerate() {
+            let is_last = index == self.segments.len() - 1;
+            if is_last && segment == "*" {
+                return topic.len() > index;
+            }
+
+            match topic.get(index) {
+                Some(part) if segment == "*" || segment == part => {}
+                _ => return false,
+            }
+        }
+
+        topic.len() == self.segments.len()
+    }
+}
+
+impl Display for TopicPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.segments.join("."))
+    }
+}
+
+impl<S> From<S> for TopicPattern
+where
+    S: AsRef<str>,
+{
+    fn from(pattern: S) -> Self {
+        Self::new(pattern)
+    }
+}
+
+// Publishes to string topics instead of one typed event, for cases like plugins where types are too rigid
+pub struct TopicEvent<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub event: Event<TopicMessage<T>>,
+}
+
+impl<T> TopicEvent<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            event: Event::new(name),
+        }
+    }
+
+    pub async fn publish<S>(
+        &self,
+        topic: S,
+        payload: Arc<T>,
+    ) -> Result<(), EventError<TopicMessage<T>>>
+    where
+        S: Into<String>,
+    {
+        let message = TopicMessage {
+            topic: topic.into(),
+            payload,
+        };
+
+        self.event.dispatch(Arc::new(message)).await
+    }
+
+    // A filter the subscriber already has still applies, in addition to the pattern
+    pub async fn subscribe(
+        &self,
+        pattern: impl Into<TopicPattern>,
+        mut subscriber: Subscriber<TopicMessage<T>>,
+    ) -> SubscriptionHandle<TopicMessage<T>> {
+        let pattern = pattern.into();
+        let filter = subscriber.filter.take();
+
+        subscriber.filter = Some(Box::new(move |message: &TopicMessage<T>| {
+            pattern.matches(&message.topic) && filter.as_ref().is_none_or(|filter| filter(message))
+        }));
+
+        self.event.subscribe(subscriber).await
+    }
+
+    pub async fn subscribe_channel<S>(
+        &self,
+        pattern: impl Into<TopicPattern>,
+        name: S,
+        buffer: usize,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> (
+        SubscriptionHandle<TopicMessage<T>>,
+        Receiver<Arc<TopicMessage<T>>>,
+    )
+    where
+        S: Into<String>,
+    {
+        let (sender, receiver) = channel(buffer);
+        let subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            remove_on_error,
+            Callback::Channel(sender),
+        );
+
+        (self.subscribe(pattern, subscriber).await, receiver)
+    }
+
+    pub async fn subscribe_closure<S>(
+        &self,
+        pattern: impl Into<TopicPattern>,
+        name: S,
+        closure: impl Fn(Arc<TopicMessage<T>>) -> Result<(), BoxedError> + Send + Sync + 'static,
+        log_on_error: bool,
+        remove_on_error: bool,
+    ) -> SubscriptionHandle<TopicMessage<T>>
+    where
+        S: Into<String>,
+    {
+        let subscriber = Subscriber::new(
+            name,
+            log_on_error,
+            remove_on_error,
+            Callback::Closure(Box::new(closure)),
+        );
+
+        self.subscribe(pattern, subscriber).await
+    }
+}
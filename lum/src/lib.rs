@@ -1,5 +1,5 @@
-use crate::service::OverallStatus;
-use ::log::{error, info};
+use crate::service::{EscalationPolicy, OverallStatus};
+use ::log::{error, info, warn};
 use bot::Bot;
 use std::time::SystemTime;
 
@@ -32,14 +32,35 @@ pub async fn run(mut bot: Bot) {
         }
     };
 
-    if bot.service_manager.overall_status().await != OverallStatus::Healthy {
-        let status_overview = bot.service_manager.status_overview().await;
+    match bot.service_manager.overall_status().await {
+        OverallStatus::Healthy => {}
+        OverallStatus::Degraded => {
+            let status_overview = bot.service_manager.status_overview().await;
 
-        error!(
-            "{} is not healthy! Some essential services did not start up successfully. {} will now exit ungracefully.\n\n{}",
-            bot.name, bot.name, status_overview
-        );
-        return;
+            warn!(
+                "{} is degraded! Some optional services did not start up successfully.\n\n{}",
+                bot.name, status_overview
+            );
+        }
+        OverallStatus::Unhealthy
+            if bot.service_manager.escalation_policy != EscalationPolicy::Shutdown =>
+        {
+            let status_overview = bot.service_manager.status_overview().await;
+
+            warn!(
+                "{} is not healthy! Some essential services did not start up successfully. {} keeps running because of the escalation policy ({}).\n\n{}",
+                bot.name, bot.name, bot.service_manager.escalation_policy, status_overview
+            );
+        }
+        OverallStatus::Unhealthy => {
+            let status_overview = bot.service_manager.status_overview().await;
+
+            error!(
+                "{} is not healthy! Some essential services did not start up successfully. {} will now exit ungracefully.\n\n{}",
+                bot.name, bot.name, status_overview
+            );
+            return;
+        }
     }
 
     info!("{} is alive", bot.name,);
@@ -1,13 +1,11 @@
-use std::sync::Arc;
-
 use ::log::{error, warn};
 use lum::{
     bot::Bot,
     config::{ConfigHandler, FileConfig},
     log,
-    service::{Service, discord::DiscordService},
+    service::{ServiceHandle, discord::DiscordService},
 };
-use tokio::sync::Mutex;
+use std::sync::Arc;
 
 const BOT_NAME: &str = "Lum";
 
@@ -19,8 +17,9 @@ async fn main() {
         warn!("THIS IS A DEBUG RELEASE!");
     }
 
-    let config_handler = ConfigHandler::new(BOT_NAME.to_lowercase().as_str());
-    let config = match config_handler.load_config() {
+    let config_handler =
+        ConfigHandler::new(BOT_NAME.to_lowercase().as_str()).with_schema(FileConfig::schema());
+    let config: FileConfig = match config_handler.load_config() {
         Ok(config) => config,
         Err(err) => {
             error!(
@@ -32,10 +31,19 @@ async fn main() {
     };
 
     let bot = Bot::builder(BOT_NAME)
+        .with_config(Arc::new(config.clone()))
+        .with_timeouts_from_config(&config)
         .with_services(initialize_services(&config))
         .await
         .build()
         .await;
+    let bot = match bot {
+        Ok(bot) => bot,
+        Err(err) => {
+            error!("Error building the bot: {}\n{} will exit.", err, BOT_NAME);
+            return;
+        }
+    };
 
     lum::run(bot).await;
 }
@@ -49,11 +57,11 @@ fn setup_logger() {
     }
 }
 
-fn initialize_services(config: &FileConfig) -> Vec<Arc<Mutex<dyn Service>>> {
+fn initialize_services(config: &FileConfig) -> Vec<ServiceHandle> {
     //TODO: Add services
     //...
 
     let discord_service = DiscordService::new(config.discord_token.as_str());
 
-    vec![Arc::new(Mutex::new(discord_service))]
+    vec![ServiceHandle::new(discord_service)]
 }
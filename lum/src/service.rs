@@ -1,13 +1,34 @@
 pub mod discord;
+#[cfg(feature = "redis-bridge")]
+pub mod redis_bridge;
+#[allow(clippy::module_inception)]
 pub mod service; // Will be fixed when lum gets seperated into multiple workspaces
+pub mod service_actor;
+pub mod service_context;
 pub mod service_manager;
+pub mod service_manager_events;
+pub mod status_report;
 pub mod taskchain;
 pub mod types;
 
-pub use service::{Service, ServiceInfo};
+#[cfg(feature = "redis-bridge")]
+pub use redis_bridge::{RedisBridgeError, RedisEventBridge, RedisMessage};
+pub use service::{Service, ServiceHandle, ServiceInfo};
+pub use service_actor::{ServiceActor, ServiceActorHandle};
+pub use service_context::ServiceContext;
 pub use service_manager::{ServiceManager, ServiceManagerBuilder};
+pub use service_manager_events::ServiceManagerEvents;
+pub use status_report::{ServiceReport, StatusCounts, StatusReport, StatusSummary};
 pub use taskchain::Taskchain;
+pub use tokio_util::sync::CancellationToken;
 pub use types::{
-    BoxedError, LifetimedPinnedBoxedFuture, LifetimedPinnedBoxedFutureResult, OverallStatus,
-    PinnedBoxedFuture, PinnedBoxedFutureResult, Priority, ShutdownError, StartupError, Status,
+    ActorError, Backoff, BoxedError, BuildError, CrashLoopDetection, DEFAULT_DRAIN_TIMEOUT,
+    DEFAULT_SHUTDOWN_DEADLINE, DEFAULT_SHUTDOWN_TIMEOUT, DEFAULT_STARTUP_TIMEOUT,
+    DEFAULT_STATUS_HISTORY_CAPACITY, EscalationPolicy, HealthCheck, InvalidServiceIdError,
+    LifetimedPinnedBoxedFuture, LifetimedPinnedBoxedFutureResult, NamedTask, OverallStatus,
+    PauseError, PinnedBoxedFuture, PinnedBoxedFutureResult, PlanError, Priority, Readiness,
+    ReadinessError, RegistrationError, RemovalError, ReplaceError, RestartError, RestartPolicy,
+    ResumeError, ServiceId, ServiceMetrics, ShutdownError, ShutdownOrder, StartupError,
+    StartupMode, StartupPlan, Status, StatusChange, SupervisionGroup, SupervisionStrategy,
+    TaskStatus, TimeoutOverride, WaitError,
 };
@@ -1,4 +1,4 @@
-use super::{BoxedError, Priority, Service, ServiceInfo, ServiceManager};
+use super::{BoxedError, Priority, Service, ServiceContext, ServiceId, ServiceInfo};
 use log::{error, info, warn};
 #[allow(deprecated)] //TODO: Remove
 use serenity::{
@@ -38,8 +38,10 @@ pub struct DiscordService {
 
 impl DiscordService {
     pub fn new(discord_token: &str) -> Self {
+        let id = ServiceId::new("lum_builtin_discord").expect("Discord service ID is invalid");
+
         Self {
-            info: ServiceInfo::new("lum_builtin_discord", "Discord", Priority::Essential),
+            info: ServiceInfo::new(id, "Discord", Priority::Essential),
             discord_token: discord_token.to_string(),
             ready: Arc::new(OnceLock::new()),
             client_handle: None,
@@ -60,7 +62,7 @@ impl Service for DiscordService {
         &self.info
     }
 
-    async fn start(&mut self, _service_manager: Arc<ServiceManager>) -> Result<(), BoxedError> {
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
         let client_ready_notify = Arc::new(Notify::new());
 
         #[allow(deprecated)] //TODO: Remove
@@ -1,38 +1,301 @@
 use std::{
+    any::Any,
     cmp::Ordering,
+    collections::VecDeque,
+    future::Future,
     hash::{Hash, Hasher},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{self, AtomicU32, AtomicU64},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
 use async_trait::async_trait;
 use downcast_rs::{DowncastSync, impl_downcast};
+use log::warn;
+use serde_json::Value;
+use tokio::sync::{
+    Mutex,
+    watch::{self, error::RecvError},
+};
+use tokio_util::sync::CancellationToken;
 
-use crate::event::Observable;
+use crate::event::{Event, Observable, ObservableResult};
 
 use super::{
     BoxedError, LifetimedPinnedBoxedFutureResult,
-    service_manager::ServiceManager,
-    types::{Priority, Status},
+    service_context::ServiceContext,
+    types::{
+        Backoff, DEFAULT_STATUS_HISTORY_CAPACITY, HealthCheck, Heartbeat, NamedTask, Priority,
+        Readiness, RestartPolicy, ServiceId, ServiceMetrics, StartupMode, Status, StatusChange,
+    },
 };
 
+#[derive(Debug, Default)]
+struct MetricsTracker {
+    last_started: Option<SystemTime>,
+    running_since: Option<Instant>,
+    previous_uptime: Duration,
+    restarts: u32,
+    failures: u32,
+}
+
+impl MetricsTracker {
+    fn track(&mut self, old: &Status, new: &Status) {
+        if !old.is_running() && new.is_running() {
+            self.last_started = Some(SystemTime::now());
+            self.running_since = Some(Instant::now());
+        }
+
+        if old.is_running()
+            && !new.is_running()
+            && let Some(running_since) = self.running_since.take()
+        {
+            self.previous_uptime += running_since.elapsed();
+        }
+
+        if new.is_failed() {
+            self.failures += 1;
+        }
+    }
+}
+
+// Shared with the service's tasks, which update it without awaiting
+#[derive(Debug, Default)]
+pub(super) struct TaskUsage {
+    spawned: AtomicU32,
+    cpu_time_nanos: AtomicU64,
+}
+
+impl TaskUsage {
+    pub(super) fn record_spawn(&self) {
+        self.spawned.fetch_add(1, atomic::Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "task-cpu-time"), allow(dead_code))]
+    pub(super) fn record_cpu_time(&self, cpu_time: Duration) {
+        let nanos = u64::try_from(cpu_time.as_nanos()).unwrap_or(u64::MAX);
+        self.cpu_time_nanos
+            .fetch_add(nanos, atomic::Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug)]
 pub struct ServiceInfo {
-    pub id: String,
+    pub id: ServiceId,
     pub name: String,
     pub priority: Priority,
+    pub restart_policy: RestartPolicy,
+
+    // Retries a failed start this many times before the service is declared failed
+    pub startup_retries: Option<Backoff>,
+    pub health_check: Option<HealthCheck>,
+
+    // The service is marked as failed if its heartbeat isn't beaten within this long
+    pub heartbeat_timeout: Option<Duration>,
+    pub readiness: Readiness,
+    pub startup_mode: StartupMode,
+
+    // Lower values start earlier, as far as dependencies allow
+    pub startup_priority: i32,
+
+    // Services that must be running for this one to work
+    pub dependencies: Vec<ServiceId>,
+
+    // Free-form labels like "network" or "storage" for operating on groups of services
+    pub tags: Vec<String>,
 
     pub status: Observable<Status>,
+    pub on_status_change: Event<StatusChange>,
+    status_watch: watch::Sender<Status>,
+    metrics: Mutex<MetricsTracker>,
+    history: Mutex<VecDeque<StatusChange>>,
+    history_capacity: usize,
+    heartbeat: Heartbeat,
+    task_usage: Arc<TaskUsage>,
 }
 
 impl ServiceInfo {
-    pub fn new(id: &str, name: &str, priority: Priority) -> Self {
+    pub fn new(id: ServiceId, name: &str, priority: Priority) -> Self {
         Self {
-            id: id.to_string(),
+            status: Observable::new(Status::Stopped, format!("{}_status_change", id)),
+            on_status_change: Event::new(format!("{}_on_status_change", id)),
+            status_watch: watch::Sender::new(Status::Stopped),
+            metrics: Mutex::new(MetricsTracker::default()),
+            history: Mutex::new(VecDeque::new()),
+            history_capacity: DEFAULT_STATUS_HISTORY_CAPACITY,
+            id,
             name: name.to_string(),
             priority,
-            status: Observable::new(Status::Stopped, format!("{}_status_change", id)),
+            restart_policy: RestartPolicy::default(),
+            startup_retries: None,
+            health_check: None,
+            heartbeat_timeout: None,
+            heartbeat: Heartbeat::new(),
+            task_usage: Arc::new(TaskUsage::default()),
+            readiness: Readiness::default(),
+            startup_mode: StartupMode::default(),
+            startup_priority: 0,
+            dependencies: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = restart_policy;
+        self
+    }
+
+    pub fn with_startup_retries(mut self, startup_retries: Backoff) -> Self {
+        self.startup_retries = Some(startup_retries);
+        self
+    }
+
+    pub fn with_health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = Some(health_check);
+        self
+    }
+
+    pub fn with_heartbeat(mut self, heartbeat_timeout: Duration) -> Self {
+        self.heartbeat_timeout = Some(heartbeat_timeout);
+        self
+    }
+
+    pub fn with_readiness(mut self, readiness: Readiness) -> Self {
+        self.readiness = readiness;
+        self
+    }
+
+    pub fn with_dependency(mut self, service_id: ServiceId) -> Self {
+        self.dependencies.push(service_id);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own_tag| own_tag == tag)
+    }
+
+    pub fn with_startup_mode(mut self, startup_mode: StartupMode) -> Self {
+        self.startup_mode = startup_mode;
+        self
+    }
+
+    pub fn with_startup_priority(mut self, startup_priority: i32) -> Self {
+        self.startup_priority = startup_priority;
+        self
+    }
+
+    pub fn with_history_capacity(mut self, history_capacity: usize) -> Self {
+        self.history_capacity = history_capacity;
+        self
+    }
+
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.heartbeat.clone()
+    }
+
+    // Oldest transition first
+    pub async fn history(&self) -> Vec<StatusChange> {
+        self.history.lock().await.iter().cloned().collect()
+    }
+
+    // Returns false if the service isn't Started, e.g. because it is already ready or has failed
+    pub async fn mark_ready(&self) -> bool {
+        if self.status.get().await != Status::Started {
+            return false;
+        }
+
+        self.set_status(Status::Ready).await;
+        true
+    }
+
+    pub async fn metrics(&self) -> ServiceMetrics {
+        let metrics = self.metrics.lock().await;
+        let current_uptime = metrics
+            .running_since
+            .map(|running_since| running_since.elapsed());
+
+        ServiceMetrics {
+            service_id: self.id.clone(),
+            last_started: metrics.last_started,
+            current_uptime,
+            total_uptime: metrics.previous_uptime + current_uptime.unwrap_or_default(),
+            restarts: metrics.restarts,
+            failures: metrics.failures,
+            tasks_spawned: self.task_usage.spawned.load(atomic::Ordering::Relaxed),
+            task_cpu_time: cfg!(feature = "task-cpu-time").then(|| {
+                Duration::from_nanos(
+                    self.task_usage
+                        .cpu_time_nanos
+                        .load(atomic::Ordering::Relaxed),
+                )
+            }),
+        }
+    }
+
+    pub(super) fn task_usage(&self) -> Arc<TaskUsage> {
+        Arc::clone(&self.task_usage)
+    }
+
+    pub async fn record_restart(&self) {
+        self.metrics.lock().await.restarts += 1;
+    }
+
+    pub fn watch_status(&self) -> watch::Receiver<Status> {
+        self.status_watch.subscribe()
+    }
+
+    /*
+        The returned future doesn't borrow self, so the service's lock can (and should) be released before awaiting it.
+        Otherwise, the service can't transition into the awaited status.
+        Only the latest status is observed, so short-lived statuses like Stopping might be skipped.
+    */
+    pub fn wait_for_status(
+        &self,
+        status: Status,
+    ) -> impl Future<Output = Result<Status, RecvError>> + Send + 'static {
+        let mut receiver = self.watch_status();
+        async move {
+            let current_status = receiver.wait_for(|current| *current == status).await?;
+            Ok(current_status.clone())
         }
     }
+
+    // Unlike setting the status Observable directly, this also dispatches a StatusChange to on_status_change
+    pub async fn set_status(&self, status: Status) {
+        let old = self.status.get().await;
+        if let ObservableResult::Unchanged = self.status.set(status.clone()).await {
+            return;
+        }
+        self.status_watch.send_replace(status.clone());
+        self.metrics.lock().await.track(&old, &status);
+
+        let status_change = StatusChange {
+            service_id: self.id.clone(),
+            old,
+            new: status,
+            timestamp: SystemTime::now(),
+        };
+
+        if self.history_capacity > 0 {
+            let mut history = self.history.lock().await;
+            if history.len() >= self.history_capacity {
+                history.pop_front();
+            }
+            history.push_back(status_change.clone());
+        }
+
+        let _ = self
+            .on_status_change
+            .dispatch(Arc::new(status_change))
+            .await;
+    }
 }
 
 impl PartialEq for ServiceInfo {
@@ -64,14 +327,89 @@ impl Hash for ServiceInfo {
 #[async_trait]
 pub trait Service: DowncastSync {
     fn info(&self) -> &ServiceInfo;
-    async fn start(&mut self, service_manager: Arc<ServiceManager>) -> Result<(), BoxedError>;
+    async fn start(&mut self, context: ServiceContext) -> Result<(), BoxedError>;
     async fn stop(&mut self) -> Result<(), BoxedError>;
-    fn task<'a>(&self) -> Option<LifetimedPinnedBoxedFutureResult<'a, ()>> {
+
+    // Runs before any start hook, usually by deserializing context.config_section()
+    fn validate_config(&self, _context: &ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    // Failing pre-hooks abort the transition, failing post-hooks are only logged
+    async fn pre_start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn post_start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    // Suspends the service without tearing it down. Its background tasks keep running.
+    async fn pause(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn resume(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    // Taken before the manager restarts the service and handed to restore() before it starts again
+    async fn snapshot(&self) -> Result<Option<Value>, BoxedError> {
+        Ok(None)
+    }
+
+    async fn restore(&mut self, _snapshot: Value) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    // Called before stopping. Stop taking new work and return once in-flight work is done.
+    // Background tasks keep running meanwhile, and may end on their own.
+    async fn drain(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn pre_stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn post_stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+    // The token is cancelled when the service is stopped. Tasks should return Ok(()) once it is.
+    fn task<'a>(
+        &self,
+        _cancellation_token: CancellationToken,
+    ) -> Option<LifetimedPinnedBoxedFutureResult<'a, ()>> {
         None
     }
 
+    // For services with more than one background task. Defaults to task(), reported as "main".
+    fn tasks<'a>(&self, cancellation_token: CancellationToken) -> Vec<NamedTask<'a>> {
+        self.task(cancellation_token)
+            .map(|task| vec![("main".to_string(), task)])
+            .unwrap_or_default()
+    }
+
+    // None falls back to the ServiceManager's default
+    fn startup_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn shutdown_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn drain_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    // Only probed if the service's ServiceInfo has a HealthCheck configured
+    async fn health_check(&self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
     async fn is_available(&self) -> bool {
-        matches!(self.info().status.get().await, Status::Started)
+        matches!(self.info().status.get().await, Status::Ready)
     }
 }
 
@@ -102,3 +440,69 @@ impl Hash for dyn Service {
         self.info().hash(state);
     }
 }
+
+// Keeps the concrete Arc<Mutex<T>> next to the trait object, so typed lookups can downcast safely
+#[derive(Clone)]
+pub struct ServiceHandle {
+    service: Arc<Mutex<dyn Service>>,
+    typed: Arc<dyn Any + Send + Sync>,
+
+    // The same service as Arc<Mutex<dyn C>> for every capability trait C it was registered with
+    capabilities: Vec<Arc<dyn Any + Send + Sync>>,
+}
+
+impl ServiceHandle {
+    pub fn new<T: Service>(service: T) -> Self {
+        Self::from(Arc::new(Mutex::new(service)))
+    }
+
+    pub fn service(&self) -> &Arc<Mutex<dyn Service>> {
+        &self.service
+    }
+
+    pub fn downcast<T: Service>(&self) -> Option<Arc<Mutex<T>>> {
+        Arc::clone(&self.typed).downcast::<Mutex<T>>().ok()
+    }
+
+    /*
+        Registers the service under a capability trait, e.g. with_capability::<dyn MessageSink>(Arc::clone(&sink)).
+        The capability has to be the very same Arc the handle was created from, anything else is ignored.
+    */
+    pub fn with_capability<C>(mut self, capability: Arc<Mutex<C>>) -> Self
+    where
+        C: ?Sized + Send + 'static,
+    {
+        if !std::ptr::addr_eq(Arc::as_ptr(&capability), Arc::as_ptr(&self.service)) {
+            warn!(
+                "Ignoring capability {} that doesn't belong to this service",
+                std::any::type_name::<C>()
+            );
+            return self;
+        }
+
+        self.capabilities.push(Arc::new(capability));
+        self
+    }
+
+    pub fn capability<C>(&self) -> Option<Arc<Mutex<C>>>
+    where
+        C: ?Sized + Send + 'static,
+    {
+        self.capabilities
+            .iter()
+            .find_map(|capability| capability.downcast_ref::<Arc<Mutex<C>>>())
+            .cloned()
+    }
+}
+
+impl<T: Service> From<Arc<Mutex<T>>> for ServiceHandle {
+    fn from(service: Arc<Mutex<T>>) -> Self {
+        let typed: Arc<dyn Any + Send + Sync> = service.clone();
+
+        Self {
+            service,
+            typed,
+            capabilities: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use log::{error, info, warn};
+use tokio::{
+    select,
+    sync::{
+        mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+        oneshot, watch,
+    },
+    task::JoinHandle,
+    time::timeout,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, info_span};
+
+use super::{
+    Service, ServiceContext,
+    service_manager::{catch_panic, spawn_for_service, spawn_named},
+    types::{
+        ActorError, DEFAULT_DRAIN_TIMEOUT, DEFAULT_SHUTDOWN_TIMEOUT, DEFAULT_STARTUP_TIMEOUT,
+        PauseError, Priority, Readiness, ResumeError, ServiceId, ShutdownError, StartupError,
+        Status,
+    },
+};
+
+const COMMAND_BUFFER: usize = 16;
+
+enum ServiceCommand {
+    Start(ServiceContext, oneshot::Sender<Result<(), StartupError>>),
+    Stop(oneshot::Sender<Result<(), ShutdownError>>),
+    Pause(oneshot::Sender<Result<(), PauseError>>),
+    Resume(oneshot::Sender<Result<(), ResumeError>>),
+}
+
+/*
+    An alternative to sharing services as Arc<Mutex<dyn Service>>.
+    The actor owns its service and runs it in a task of its own. Everything else talks to it through a
+    ServiceActorHandle, so lifecycle transitions are serialized by the command channel instead of a lock,
+    and reading a status never waits for a transition to finish.
+*/
+pub struct ServiceActor<S: Service> {
+    service: S,
+    commands: Receiver<ServiceCommand>,
+    // Task name and error of background tasks that ended on their own
+    task_failure_sender: UnboundedSender<(String, String)>,
+    task_failures: UnboundedReceiver<(String, String)>,
+    cancellation_token: CancellationToken,
+    background_tasks: Vec<(String, JoinHandle<()>)>,
+    paused_status: Option<Status>,
+}
+
+impl<S: Service> ServiceActor<S> {
+    pub fn spawn(service: S) -> ServiceActorHandle {
+        let (sender, receiver) = mpsc::channel(COMMAND_BUFFER);
+        let info = service.info();
+        let handle = ServiceActorHandle {
+            id: info.id.clone(),
+            name: info.name.clone(),
+            priority: info.priority,
+            commands: sender,
+            status: info.watch_status(),
+        };
+
+        let span = info_span!(
+            "service_actor",
+            service_id = %handle.id,
+            service_name = %handle.name
+        );
+        let (task_failure_sender, task_failures) = mpsc::unbounded_channel();
+        let actor = Self {
+            service,
+            commands: receiver,
+            task_failure_sender,
+            task_failures,
+            cancellation_token: CancellationToken::new(),
+            background_tasks: Vec::new(),
+            paused_status: None,
+        };
+        spawn_named(format!("{}/actor", handle.id), actor.run().instrument(span));
+
+        handle
+    }
+
+    async fn run(mut self) {
+        loop {
+            select! {
+                command = self.commands.recv() => match command {
+                    Some(command) => self.handle(command).await,
+                    None => break,
+                },
+                Some((task_name, error)) = self.task_failures.recv() => {
+                    self.fail(task_name, error).await;
+                }
+            }
+        }
+
+        // Every handle is gone, so nobody could stop the service anymore
+        if self.service.info().status.get().await.is_active() {
+            let _ = self.stop().await;
+        }
+    }
+
+    async fn handle(&mut self, command: ServiceCommand) {
+        match command {
+            ServiceCommand::Start(context, reply) => {
+                let _ = reply.send(self.start(context).await);
+            }
+            ServiceCommand::Stop(reply) => {
+                let _ = reply.send(self.stop().await);
+            }
+            ServiceCommand::Pause(reply) => {
+                let _ = reply.send(self.pause().await);
+            }
+            ServiceCommand::Resume(reply) => {
+                let _ = reply.send(self.resume().await);
+            }
+        }
+    }
+
+    // Failed services can be started again, as the actor has no restart policy of its own. Killed is terminal though.
+    async fn start(&mut self, context: ServiceContext) -> Result<(), StartupError> {
+        let service_id = self.service.info().id.clone();
+
+        let status = self.service.info().status.get().await;
+        if status == Status::Killed || (status != Status::Stopped && !status.is_failed()) {
+            return Err(StartupError::ServiceNotStopped(service_id));
+        }
+        self.stop_background_tasks().await;
+
+        let info = self.service.info();
+        if let Err(error) = self.service.validate_config(&context) {
+            error!("Service {} has an invalid config: {}", info.name, error);
+            info.set_status(Status::FailedToStart(format!("Invalid config: {}", error)))
+                .await;
+            return Err(StartupError::InvalidConfig(service_id, error.to_string()));
+        }
+
+        info.set_status(Status::Starting).await;
+
+        let startup_timeout = self
+            .service
+            .startup_timeout()
+            .unwrap_or(DEFAULT_STARTUP_TIMEOUT);
+        let description = format!("Service {} while starting", info.name);
+        let service = &mut self.service;
+        let start = catch_panic(description, async {
+            service.pre_start(context.clone()).await?;
+            service.start(context.clone()).await
+        });
+
+        let error = match timeout(startup_timeout, start).await {
+            Ok(Ok(())) => None,
+            Ok(Err(error)) => Some(error.to_string()),
+            Err(error) => Some(error.to_string()),
+        };
+        if let Some(error) = error {
+            self.service
+                .info()
+                .set_status(Status::FailedToStart(error))
+                .await;
+            return Err(StartupError::FailedToStartService(service_id));
+        }
+
+        self.service.info().set_status(Status::Started).await;
+        self.start_background_tasks();
+        info!("Started service {}", self.service.info().name);
+
+        // The service is already started at this point, so a failing hook doesn't change that
+        if let Err(error) = self.service.post_start(context).await {
+            warn!(
+                "Service {} failed to run its post-start hook: {}",
+                self.service.info().name,
+                error
+            );
+        }
+
+        if self.service.info().readiness == Readiness::Immediate {
+            self.service.info().mark_ready().await;
+        }
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), ShutdownError> {
+        let service_id = self.service.info().id.clone();
+
+        let status = self.service.info().status.get().await;
+        if !status.is_active() {
+            return Err(ShutdownError::ServiceNotStarted(service_id));
+        }
+
+        self.paused_status = None;
+        self.drain().await;
+        self.stop_background_tasks().await;
+        self.service.info().set_status(Status::Stopping).await;
+
+        let shutdown_timeout = self
+            .service
+            .shutdown_timeout()
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT);
+        let description = format!("Service {} while stopping", self.service.info().name);
+        let service = &mut self.service;
+        let stop = catch_panic(description, async {
+            service.pre_stop().await?;
+            service.stop().await
+        });
+
+        match timeout(shutdown_timeout, stop).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => {
+                self.service
+                    .info()
+                    .set_status(Status::FailedToStop(error.to_string()))
+                    .await;
+                return Err(ShutdownError::FailedToStopService(service_id));
+            }
+            // Background tasks are already gone, so dropping the stop future is all that's left
+            Err(_) => {
+                error!(
+                    "Service {} did not stop within {}ms and was killed",
+                    self.service.info().name,
+                    shutdown_timeout.as_millis()
+                );
+                self.service.info().set_status(Status::Killed).await;
+                return Err(ShutdownError::Killed(service_id));
+            }
+        }
+
+        self.service.info().set_status(Status::Stopped).await;
+        info!("Stopped service {}", self.service.info().name);
+
+        // The service is already stopped at this point, so a failing hook doesn't change that
+        if let Err(error) = self.service.post_stop().await {
+            warn!(
+                "Service {} failed to run its post-stop hook: {}",
+                self.service.info().name,
+                error
+            );
+        }
+
+        Ok(())
+    }
+
+    // Tasks that end while draining don't fail the service, as it isn't active anymore
+    async fn drain(&mut self) {
+        self.service.info().set_status(Status::Draining).await;
+
+        let drain_timeout = self
+            .service
+            .drain_timeout()
+            .unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+        let description = format!("Service {} while draining", self.service.info().name);
+        let drain = catch_panic(description, self.service.drain());
+        match timeout(drain_timeout, drain).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => warn!(
+                "Service {} failed to drain: {}",
+                self.service.info().name,
+                error
+            ),
+            Err(_) => warn!(
+                "Service {} did not drain within {}ms. Stopping it anyway.",
+                self.service.info().name,
+                drain_timeout.as_millis()
+            ),
+        }
+    }
+
+    async fn pause(&mut self) -> Result<(), PauseError> {
+        let service_id = self.service.info().id.clone();
+
+        let status = self.service.info().status.get().await;
+        if !status.is_running() {
+            return Err(PauseError::ServiceNotRunning(service_id, status));
+        }
+
+        if let Err(error) = self.service.pause().await {
+            warn!(
+                "Service {} failed to pause: {}",
+                self.service.info().name,
+                error
+            );
+            return Err(PauseError::FailedToPause(service_id, error.to_string()));
+        }
+
+        self.paused_status = Some(status);
+        self.service.info().set_status(Status::Paused).await;
+        info!("Paused service {}", self.service.info().name);
+
+        Ok(())
+    }
+
+    async fn resume(&mut self) -> Result<(), ResumeError> {
+        let service_id = self.service.info().id.clone();
+
+        let status = self.service.info().status.get().await;
+        if status != Status::Paused {
+            return Err(ResumeError::ServiceNotPaused(service_id, status));
+        }
+
+        if let Err(error) = self.service.resume().await {
+            warn!(
+                "Service {} failed to resume: {}",
+                self.service.info().name,
+                error
+            );
+            return Err(ResumeError::FailedToResume(service_id, error.to_string()));
+        }
+
+        let previous_status = self.paused_status.take().unwrap_or(Status::Started);
+        self.service.info().set_status(previous_status).await;
+        info!("Resumed service {}", self.service.info().name);
+
+        Ok(())
+    }
+
+    // Each task reports back through the failure channel, so the actor never has to poll them
+    fn start_background_tasks(&mut self) {
+        self.cancellation_token = CancellationToken::new();
+
+        for (task_name, task) in self.service.tasks(self.cancellation_token.clone()) {
+            let description = format!(
+                "Background task {} of service {}",
+                task_name,
+                self.service.info().name
+            );
+            let cancellation_token = self.cancellation_token.clone();
+            let task_failures = self.task_failure_sender.clone();
+            let name = task_name.clone();
+
+            let task_id = format!("{}/{}", self.service.info().id, task_name);
+            let handle = spawn_for_service(self.service.info(), task_id, async move {
+                let result = catch_panic(description, task).await;
+                if cancellation_token.is_cancelled() {
+                    return;
+                }
+
+                let error = match result {
+                    Ok(()) => "ended unexpectedly".to_string(),
+                    Err(error) => format!("ended with error: {}", error),
+                };
+                let _ = task_failures.send((name, error));
+            });
+            self.background_tasks.push((task_name, handle));
+        }
+    }
+
+    async fn stop_background_tasks(&mut self) {
+        self.cancellation_token.cancel();
+
+        let shutdown_timeout = self
+            .service
+            .shutdown_timeout()
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT);
+        for (task_name, mut handle) in self.background_tasks.drain(..) {
+            if timeout(shutdown_timeout, &mut handle).await.is_err() {
+                warn!(
+                    "Background task {} of service {} did not stop in time. Aborting it.",
+                    task_name,
+                    self.service.info().name
+                );
+                handle.abort();
+            }
+        }
+    }
+
+    // One failed task fails the whole service, so its other tasks are stopped too
+    async fn fail(&mut self, task_name: String, error: String) {
+        if !self.service.info().status.get().await.is_active() {
+            return;
+        }
+
+        self.cancellation_token.cancel();
+        error!(
+            "Background task {} of service {} {}",
+            task_name,
+            self.service.info().name,
+            error
+        );
+        self.service
+            .info()
+            .set_status(Status::RuntimeError(format!(
+                "Background task {} {}",
+                task_name, error
+            )))
+            .await;
+    }
+}
+
+// Cheap to clone. The actor stops its service and exits once every handle is dropped.
+#[derive(Clone)]
+pub struct ServiceActorHandle {
+    pub id: ServiceId,
+    pub name: String,
+    pub priority: Priority,
+    commands: Sender<ServiceCommand>,
+    status: watch::Receiver<Status>,
+}
+
+impl ServiceActorHandle {
+    pub fn status(&self) -> Status {
+        self.status.borrow().clone()
+    }
+
+    pub fn watch_status(&self) -> watch::Receiver<Status> {
+        self.status.clone()
+    }
+
+    pub async fn start(&self, context: ServiceContext) -> Result<(), ActorError> {
+        Ok(self
+            .request(|reply| ServiceCommand::Start(context, reply))
+            .await??)
+    }
+
+    pub async fn stop(&self) -> Result<(), ActorError> {
+        Ok(self.request(ServiceCommand::Stop).await??)
+    }
+
+    pub async fn pause(&self) -> Result<(), ActorError> {
+        Ok(self.request(ServiceCommand::Pause).await??)
+    }
+
+    pub async fn resume(&self) -> Result<(), ActorError> {
+        Ok(self.request(ServiceCommand::Resume).await??)
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> ServiceCommand,
+    ) -> Result<T, ActorError> {
+        let (reply, response) = oneshot::channel();
+        self.commands
+            .send(command(reply))
+            .await
+            .map_err(|_| ActorError::ActorStopped(self.id.clone()))?;
+
+        response
+            .await
+            .map_err(|_| ActorError::ActorStopped(self.id.clone()))
+    }
+}
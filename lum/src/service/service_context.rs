@@ -0,0 +1,59 @@
+use std::sync::{Arc, Weak};
+
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
+
+use crate::config::{ConfigSectionError, FileConfig};
+
+use super::{Service, ServiceId, ServiceManager};
+
+// Everything a service gets handed when it is started
+#[derive(Clone)]
+pub struct ServiceContext {
+    pub service_id: ServiceId,
+
+    // Services keep their context around, so a strong reference would keep the manager alive forever
+    pub service_manager: Weak<ServiceManager>,
+    pub bot_name: String,
+    pub config: Arc<FileConfig>,
+}
+
+impl ServiceContext {
+    pub fn new(
+        service_id: ServiceId,
+        service_manager: Weak<ServiceManager>,
+        bot_name: String,
+        config: Arc<FileConfig>,
+    ) -> Self {
+        Self {
+            service_id,
+            service_manager,
+            bot_name,
+            config,
+        }
+    }
+
+    // See FileConfig::section
+    pub fn config_section<T>(&self) -> Result<T, ConfigSectionError>
+    where
+        T: DeserializeOwned,
+    {
+        self.config.section(self.service_id.as_str())
+    }
+
+    pub async fn service<T>(&self) -> Option<Arc<Mutex<T>>>
+    where
+        T: Service,
+    {
+        self.service_manager()?.get_service::<T>().await
+    }
+
+    pub async fn service_by_id(&self, service_id: &ServiceId) -> Option<Arc<Mutex<dyn Service>>> {
+        self.service_manager()?.get_service_by_id(service_id).await
+    }
+
+    // None once the manager is gone
+    pub fn service_manager(&self) -> Option<Arc<ServiceManager>> {
+        self.service_manager.upgrade()
+    }
+}
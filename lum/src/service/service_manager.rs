@@ -1,95 +1,470 @@
 use super::{
-    service::Service,
-    types::{OverallStatus, Priority, ShutdownError, StartupError, Status},
+    service::{Service, ServiceHandle, ServiceInfo},
+    service_actor::ServiceActorHandle,
+    service_context::ServiceContext,
+    service_manager_events::ServiceManagerEvents,
+    status_report::{ServiceReport, StatusReport, StatusSummary},
+    types::{
+        ActorError, BoxedError, BuildError, CrashLoopDetection, DEFAULT_DRAIN_TIMEOUT,
+        DEFAULT_SHUTDOWN_DEADLINE, DEFAULT_SHUTDOWN_TIMEOUT, DEFAULT_STARTUP_TIMEOUT,
+        EscalationPolicy, OverallStatus, PauseError, PlanError, Priority, Readiness,
+        ReadinessError, RegistrationError, RemovalError, ReplaceError, RestartError, ResumeError,
+        ServiceId, ServiceMetrics, ShutdownError, ShutdownOrder, StartupError, StartupMode,
+        StartupPlan, Status, StatusChange, SupervisionGroup, TaskStatus, TimeoutOverride,
+        WaitError,
+    },
 };
-use crate::{event::EventRepeater, service::Taskchain};
+use crate::{
+    config::FileConfig,
+    event::{Event, EventRepeater},
+    service::Taskchain,
+};
+use futures::FutureExt;
 use log::{error, info, warn};
+use serde_json::Value;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     error::Error,
     fmt::{self, Display},
-    mem,
-    sync::{Arc, OnceLock, Weak},
-    time::Duration,
+    future::Future,
+    ops::{Deref, DerefMut},
+    panic::{AssertUnwindSafe, Location},
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock, Weak},
+    time::{Duration, Instant},
 };
 use tokio::{
     spawn,
-    sync::{Mutex, MutexGuard},
-    task::JoinHandle,
-    time::timeout,
+    sync::{Mutex, MutexGuard, RwLock, mpsc::channel},
+    task::{JoinHandle, JoinSet},
+    time::{sleep, timeout},
 };
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, info_span, instrument};
+
+type BackgroundTaskHandle = JoinHandle<Result<(), Box<dyn Error + Send + Sync>>>;
+
+// All tasks of a service share one token, so they are stopped together
+struct BackgroundTasks {
+    handles: Vec<(String, BackgroundTaskHandle)>,
+    cancellation_token: CancellationToken,
+
+    // Cancelled once the service drains, so tasks winding down meanwhile don't count as failures
+    draining: CancellationToken,
+}
+
+type LockHolders = StdMutex<HashMap<usize, (String, &'static Location<'static>)>>;
+
+// A service's lock as handed out by lock_service. Releasing it also forgets who held it.
+struct ServiceLock<'a> {
+    service_lock: MutexGuard<'a, dyn Service>,
+    holder: Option<(&'a LockHolders, usize)>,
+}
+
+impl<'a> Deref for ServiceLock<'a> {
+    type Target = MutexGuard<'a, dyn Service>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.service_lock
+    }
+}
+
+impl DerefMut for ServiceLock<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.service_lock
+    }
+}
+
+// Runs before the guard is released, so the next holder can't be forgotten instead
+impl Drop for ServiceLock<'_> {
+    fn drop(&mut self) {
+        if let Some((lock_holders, key)) = self.holder
+            && let Ok(mut lock_holders) = lock_holders.lock()
+        {
+            lock_holders.remove(&key);
+        }
+    }
+}
+
+fn lock_key(service: &Arc<Mutex<dyn Service>>) -> usize {
+    Arc::as_ptr(service) as *const () as usize
+}
+
+// Turns a panic into an error, so a misbehaving service can't take down whoever awaits it
+pub(super) async fn catch_panic<T>(
+    description: String,
+    future: impl Future<Output = Result<T, BoxedError>>,
+) -> Result<T, BoxedError> {
+    match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Unknown panic payload".to_string());
+            error!("{} panicked: {}", description, message);
+
+            Err(format!("panicked: {}", message).into())
+        }
+    }
+}
+
+// With the named-tasks feature and --cfg tokio_unstable, tokio-console shows tasks under this name
+pub(super) fn spawn_named<F>(name: String, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(all(feature = "named-tasks", tokio_unstable))]
+    match tokio::task::Builder::new().name(&name).spawn(future) {
+        Ok(handle) => handle,
+        Err(error) => {
+            error!("Unable to spawn task {}: {}", name, error);
+            unreachable!("Unable to spawn task {}: {}", name, error);
+        }
+    }
+
+    #[cfg(not(all(feature = "named-tasks", tokio_unstable)))]
+    {
+        let _ = name;
+        spawn(future)
+    }
+}
+
+// Spawns a task on behalf of a service, so it shows up in the service's metrics
+pub(super) fn spawn_for_service<F>(
+    info: &ServiceInfo,
+    name: String,
+    future: F,
+) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let task_usage = info.task_usage();
+    task_usage.record_spawn();
+
+    #[cfg(feature = "task-cpu-time")]
+    let future = PollTimed {
+        future: Box::pin(future),
+        task_usage,
+    };
+
+    spawn_named(name, future)
+}
+
+// Adds up the time spent in every poll, which is as close to per-task CPU time as tokio allows
+#[cfg(feature = "task-cpu-time")]
+struct PollTimed<F> {
+    future: Pin<Box<F>>,
+    task_usage: Arc<super::service::TaskUsage>,
+}
+
+#[cfg(feature = "task-cpu-time")]
+impl<F: Future> Future for PollTimed<F> {
+    type Output = F::Output;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let started = Instant::now();
+        let poll = self.future.as_mut().poll(cx);
+        self.task_usage.record_cpu_time(started.elapsed());
+
+        poll
+    }
+}
 
+// Filled in by index, so services that miss the shutdown deadline stay None
+type ShutdownResults = Arc<Mutex<Vec<Option<Result<(), ShutdownError>>>>>;
+
+// Services indexed by ID, while still remembering the order they were registered in
 #[derive(Default)]
+struct ServiceRegistry {
+    services: HashMap<ServiceId, ServiceHandle>,
+    order: Vec<ServiceId>,
+}
+
+impl ServiceRegistry {
+    fn contains(&self, service_id: &ServiceId) -> bool {
+        self.services.contains_key(service_id)
+    }
+
+    fn get(&self, service_id: &ServiceId) -> Option<&ServiceHandle> {
+        self.services.get(service_id)
+    }
+
+    // Returns false if a service with that ID is already registered
+    fn insert(&mut self, service_id: ServiceId, service: ServiceHandle) -> bool {
+        if self.contains(&service_id) {
+            return false;
+        }
+
+        self.order.push(service_id.clone());
+        self.services.insert(service_id, service);
+        true
+    }
+
+    // Keeps the replaced service's place in the registration order
+    fn replace(&mut self, service_id: &ServiceId, service: ServiceHandle) -> Option<ServiceHandle> {
+        let registered_service = self.services.get_mut(service_id)?;
+
+        Some(std::mem::replace(registered_service, service))
+    }
+
+    fn remove(&mut self, service_id: &ServiceId) -> Option<ServiceHandle> {
+        let service = self.services.remove(service_id)?;
+        self.order
+            .retain(|registered_service_id| registered_service_id != service_id);
+
+        Some(service)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &ServiceHandle> {
+        self.order
+            .iter()
+            .filter_map(|service_id| self.services.get(service_id))
+    }
+}
+
 pub struct ServiceManagerBuilder {
-    services: Vec<Arc<Mutex<dyn Service>>>,
+    services: ServiceRegistry,
+    bot_name: String,
+    config: Arc<FileConfig>,
+    shutdown_order: ShutdownOrder,
+    crash_loop_detection: CrashLoopDetection,
+    escalation_policy: EscalationPolicy,
+    supervision_groups: Vec<SupervisionGroup>,
+    default_startup_timeout: Duration,
+    default_shutdown_timeout: Duration,
+    default_drain_timeout: Duration,
+    shutdown_deadline: Duration,
+    timeout_overrides: HashMap<ServiceId, TimeoutOverride>,
+    lock_diagnostics: Option<Duration>,
+    duplicates: Vec<ServiceId>,
+}
+
+impl Default for ServiceManagerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ServiceManagerBuilder {
     pub fn new() -> Self {
         Self {
-            services: Vec::new(),
+            services: ServiceRegistry::default(),
+            bot_name: String::new(),
+            config: Arc::new(FileConfig::default()),
+            shutdown_order: ShutdownOrder::default(),
+            crash_loop_detection: CrashLoopDetection::default(),
+            escalation_policy: EscalationPolicy::default(),
+            supervision_groups: Vec::new(),
+            default_startup_timeout: DEFAULT_STARTUP_TIMEOUT,
+            default_shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            default_drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            shutdown_deadline: DEFAULT_SHUTDOWN_DEADLINE,
+            timeout_overrides: HashMap::new(),
+            lock_diagnostics: None,
+            duplicates: Vec::new(),
         }
     }
 
-    //TODO: When Rust allows async closures, refactor this to use iterator methods instead of for loop
-    pub async fn with_service(mut self, service: Arc<Mutex<dyn Service>>) -> Self {
-        let lock = service.lock().await;
+    pub fn with_bot_name(mut self, bot_name: &str) -> Self {
+        self.bot_name = bot_name.to_string();
+        self
+    }
 
-        let mut found = false;
-        for registered_service in self.services.iter() {
-            let registered_service = registered_service.lock().await;
+    pub fn with_config(mut self, config: Arc<FileConfig>) -> Self {
+        self.config = config;
+        self
+    }
 
-            if registered_service.info().id == lock.info().id {
-                found = true;
-            }
+    pub fn with_default_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.default_startup_timeout = timeout;
+        self
+    }
+
+    pub fn with_default_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.default_shutdown_timeout = timeout;
+        self
+    }
+
+    pub fn with_default_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.default_drain_timeout = timeout;
+        self
+    }
+
+    pub fn with_shutdown_deadline(mut self, deadline: Duration) -> Self {
+        self.shutdown_deadline = deadline;
+        self
+    }
+
+    // Logs where a service's lock was last acquired whenever waiting for it takes longer than threshold
+    pub fn with_lock_diagnostics(mut self, threshold: Duration) -> Self {
+        self.lock_diagnostics = Some(threshold);
+        self
+    }
+
+    pub fn with_timeout_override(
+        mut self,
+        service_id: ServiceId,
+        timeout_override: TimeoutOverride,
+    ) -> Self {
+        self.timeout_overrides.insert(service_id, timeout_override);
+        self
+    }
+
+    pub fn with_timeouts_from_config(mut self, config: &FileConfig) -> Self {
+        let default_timeouts = TimeoutOverride::from(&config.default_timeouts);
+        if let Some(startup) = default_timeouts.startup {
+            self.default_startup_timeout = startup;
+        }
+        if let Some(shutdown) = default_timeouts.shutdown {
+            self.default_shutdown_timeout = shutdown;
         }
 
-        if found {
-            warn!(
-                "Tried to add service {} ({}), but a service with that ID already exists. Ignoring.",
-                lock.info().name,
-                lock.info().id
-            );
-            return self;
+        for (service_id, timeouts) in config.service_timeouts.iter() {
+            match ServiceId::new(service_id) {
+                Ok(service_id) => {
+                    self.timeout_overrides
+                        .insert(service_id, TimeoutOverride::from(timeouts));
+                }
+                Err(error) => warn!(
+                    "Ignoring configured timeouts for service {}: {}",
+                    service_id, error
+                ),
+            }
         }
 
-        drop(lock);
+        self
+    }
+
+    pub fn with_crash_loop_detection(mut self, crash_loop_detection: CrashLoopDetection) -> Self {
+        self.crash_loop_detection = crash_loop_detection;
+        self
+    }
 
-        self.services.push(service);
+    pub fn with_escalation_policy(mut self, escalation_policy: EscalationPolicy) -> Self {
+        self.escalation_policy = escalation_policy;
         self
     }
 
-    pub async fn build(self) -> Arc<ServiceManager> {
-        let service_manager = ServiceManager {
-            weak: OnceLock::new(),
-            services: self.services,
-            background_tasks: Mutex::new(HashMap::new()),
-            on_status_change: EventRepeater::new("service_manager_on_status_change").await,
-        };
+    pub fn with_supervision_group(mut self, supervision_group: SupervisionGroup) -> Self {
+        self.supervision_groups.push(supervision_group);
+        self
+    }
+
+    pub fn with_shutdown_order(mut self, shutdown_order: ShutdownOrder) -> Self {
+        self.shutdown_order = shutdown_order;
+        self
+    }
 
-        let arc = Arc::new(service_manager);
-        let weak = Arc::downgrade(&arc);
+    // Duplicates are collected here and reported by build()
+    pub async fn with_service(mut self, service: impl Into<ServiceHandle>) -> Self {
+        let service = service.into();
+        let service_id = service.service().lock().await.info().id.clone();
 
-        let result = arc.weak.set(weak);
-        if result.is_err() {
-            error!(
-                "Unable to set ServiceManager's Weak self-reference in ServiceManagerBuilder because it was already set. This should never happen. Shutting down ungracefully to prevent further undefined behavior."
-            );
-            unreachable!(
-                "Unable to set ServiceManager's Weak self-reference in ServiceManagerBuilder because it was already set."
-            );
+        if !self.services.insert(service_id.clone(), service) {
+            self.duplicates.push(service_id);
+        }
+
+        self
+    }
+
+    pub async fn with_services<I>(mut self, services: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<ServiceHandle>,
+    {
+        for service in services {
+            self = self.with_service(service).await;
+        }
+
+        self
+    }
+
+    pub async fn build(self) -> Result<Arc<ServiceManager>, BuildError> {
+        if !self.duplicates.is_empty() {
+            return Err(BuildError::DuplicateServices(self.duplicates));
         }
 
-        arc
+        let on_status_change = EventRepeater::new("service_manager_on_status_change").await;
+        let arc = Arc::new_cyclic(|weak| ServiceManager {
+            weak: weak.clone(),
+            services: RwLock::new(self.services),
+            bot_name: self.bot_name,
+            config: self.config,
+            registration: Mutex::new(()),
+            summary: StdRwLock::new(String::new()),
+            shutdown_order: self.shutdown_order,
+            crash_loop_detection: self.crash_loop_detection,
+            escalation_policy: self.escalation_policy,
+            supervision_groups: self.supervision_groups,
+            default_startup_timeout: self.default_startup_timeout,
+            default_shutdown_timeout: self.default_shutdown_timeout,
+            default_drain_timeout: self.default_drain_timeout,
+            shutdown_deadline: self.shutdown_deadline,
+            timeout_overrides: self.timeout_overrides,
+            lock_diagnostics: self.lock_diagnostics,
+            lock_holders: StdMutex::new(HashMap::new()),
+            startup_order: Mutex::new(Vec::new()),
+            restart_history: Mutex::new(HashMap::new()),
+            background_tasks: Mutex::new(HashMap::new()),
+            task_statuses: Mutex::new(HashMap::new()),
+            paused_statuses: Mutex::new(HashMap::new()),
+            monitors: Mutex::new(HashMap::new()),
+            on_status_change,
+            on_crash_loop: Event::new("service_manager_on_crash_loop"),
+            events: ServiceManagerEvents::new(),
+        });
+
+        arc.refresh_overall_status().await;
+        arc.watch_overall_status().await;
+
+        Ok(arc)
     }
 }
 
 pub struct ServiceManager {
-    weak: OnceLock<Weak<Self>>,
-    background_tasks: Mutex<HashMap<String, JoinHandle<Result<(), Box<dyn Error + Send + Sync>>>>>,
-
-    pub services: Vec<Arc<Mutex<dyn Service>>>,
-    pub on_status_change: Arc<EventRepeater<Status>>,
+    weak: Weak<Self>,
+    background_tasks: Mutex<HashMap<ServiceId, BackgroundTasks>>,
+    task_statuses: Mutex<HashMap<ServiceId, BTreeMap<String, TaskStatus>>>,
+
+    // What paused services return to when resumed
+    paused_statuses: Mutex<HashMap<ServiceId, Status>>,
+    // Health checks and heartbeat monitors
+    monitors: Mutex<HashMap<ServiceId, Vec<JoinHandle<()>>>>,
+    startup_order: Mutex<Vec<ServiceId>>,
+    restart_history: Mutex<HashMap<ServiceId, Vec<Instant>>>,
+    services: RwLock<ServiceRegistry>,
+    registration: Mutex<()>,
+    events: ServiceManagerEvents,
+
+    // What Display renders. Kept up to date with describe(), so formatting never has to await.
+    summary: StdRwLock<String>,
+
+    // Service name and call site of whoever currently holds each service's lock, keyed by the lock's address
+    lock_holders: LockHolders,
+
+    // Handed to services through their ServiceContext
+    pub bot_name: String,
+    pub config: Arc<FileConfig>,
+
+    pub shutdown_order: ShutdownOrder,
+    pub crash_loop_detection: CrashLoopDetection,
+    pub escalation_policy: EscalationPolicy,
+    pub supervision_groups: Vec<SupervisionGroup>,
+    pub default_startup_timeout: Duration,
+    pub default_shutdown_timeout: Duration,
+    pub default_drain_timeout: Duration,
+    pub shutdown_deadline: Duration,
+    pub timeout_overrides: HashMap<ServiceId, TimeoutOverride>,
+    pub lock_diagnostics: Option<Duration>,
+    pub on_status_change: Arc<EventRepeater<StatusChange>>,
+    pub on_crash_loop: Event<ServiceId>,
 }
 
 impl ServiceManager {
@@ -97,451 +472,2354 @@ impl ServiceManager {
         ServiceManagerBuilder::new()
     }
 
-    pub async fn manages_service(&self, service_id: &str) -> bool {
-        for service in self.services.iter() {
-            let service_lock = service.lock().await;
+    pub fn events(&self) -> &ServiceManagerEvents {
+        &self.events
+    }
+
+    // Returns a snapshot, so services can be locked without holding the registry lock
+    pub async fn services(&self) -> Vec<Arc<Mutex<dyn Service>>> {
+        self.services
+            .read()
+            .await
+            .iter()
+            .map(|handle| Arc::clone(handle.service()))
+            .collect()
+    }
 
-            if service_lock.info().id == service_id {
-                return true;
+    pub async fn services_with_tag(&self, tag: &str) -> Vec<Arc<Mutex<dyn Service>>> {
+        let mut services = Vec::new();
+        for service in self.services().await {
+            if self.lock_service(&service).await.info().has_tag(tag) {
+                services.push(service);
             }
         }
 
-        false
+        services
     }
 
-    pub async fn start_service(
+    // E.g. services_in_status(Status::is_failed) to walk only the broken services
+    pub async fn services_in_status(
         &self,
-        service: Arc<Mutex<dyn Service>>,
-    ) -> Result<(), StartupError> {
-        let service_id = service.lock().await.info().id.clone();
-        if !self.manages_service(&service_id).await {
-            return Err(StartupError::ServiceNotManaged(service_id.clone()));
+        filter: impl Fn(&Status) -> bool,
+    ) -> Vec<Arc<Mutex<dyn Service>>> {
+        let mut services = Vec::new();
+        for service in self.services().await {
+            let status = self.lock_service(&service).await.info().status.get().await;
+            if filter(&status) {
+                services.push(service);
+            }
         }
 
-        let mut service_lock = service.lock().await;
+        services
+    }
 
-        let status = service_lock.info().status.get().await;
-        if !matches!(status, Status::Stopped) {
-            return Err(StartupError::ServiceNotStopped(service_id.clone()));
-        }
+    pub async fn add_service(
+        &self,
+        service: impl Into<ServiceHandle>,
+    ) -> Result<(), RegistrationError> {
+        let service = service.into();
 
-        if self.has_background_task_registered(&service_id).await {
-            return Err(StartupError::BackgroundTaskAlreadyRunning(
-                service_id.clone(),
-            ));
-        }
+        // Serializes registrations, so two services with the same ID can't both pass the check below
+        let _registration = self.registration.lock().await;
 
-        let service_status_event = service_lock.info().status.as_ref();
-        let attachment_result = self.on_status_change.attach(service_status_event, 2).await;
-        if let Err(err) = attachment_result {
-            return Err(StartupError::StatusAttachmentFailed(
-                service_id.clone(),
-                err,
-            ));
+        let service_id = service.service().lock().await.info().id.clone();
+        if !self
+            .services
+            .write()
+            .await
+            .insert(service_id.clone(), service)
+        {
+            return Err(RegistrationError::ServiceAlreadyManaged(service_id));
         }
 
-        service_lock.info().status.set(Status::Starting).await;
-        self.init_service(&mut service_lock).await?;
-        self.start_background_task(&service_lock, Arc::clone(&service))
+        info!("Added service {}", service_id);
+
+        let _ = self
+            .events
+            .on_service_added
+            .dispatch(Arc::new(service_id))
             .await;
+        self.refresh_overall_status().await;
 
-        info!("Started service {}", service_lock.info().name);
+        Ok(())
+    }
+
+    pub async fn add_and_start_service(
+        &self,
+        service: impl Into<ServiceHandle>,
+    ) -> Result<(), RegistrationError> {
+        let service = service.into();
+        self.add_service(service.clone()).await?;
+        self.start_service(Arc::clone(service.service())).await?;
 
         Ok(())
     }
 
-    //TODO: Clean up
-    pub async fn stop_service(
+    pub async fn remove_service(
         &self,
-        service: Arc<Mutex<dyn Service>>,
-    ) -> Result<(), ShutdownError> {
-        let service_id = service.lock().await.info().id.clone();
-        if !(self.manages_service(&service_id).await) {
-            return Err(ShutdownError::ServiceNotManaged(service_id.clone()));
-        }
+        service_id: &ServiceId,
+    ) -> Result<Arc<Mutex<dyn Service>>, RemovalError> {
+        let _registration = self.registration.lock().await;
 
-        let mut service_lock = service.lock().await;
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(RemovalError::ServiceNotManaged(service_id.clone())),
+        };
 
-        let status = service_lock.info().status.get().await;
-        if !matches!(status, Status::Started) {
-            return Err(ShutdownError::ServiceNotStarted(service_id.clone()));
+        let status = self.lock_service(&service).await.info().status.get().await;
+        if status.is_active() {
+            self.stop_service(Arc::clone(&service)).await?;
+        } else {
+            self.clean_up_inactive_service(&self.lock_service(&service).await)
+                .await;
         }
 
-        self.stop_background_task(&service_lock).await;
+        self.services.write().await.remove(service_id);
+        self.forget_lock_holder(&service);
+        self.startup_order
+            .lock()
+            .await
+            .retain(|started_service_id| started_service_id != service_id);
+        self.restart_history.lock().await.remove(service_id);
+        self.task_statuses.lock().await.remove(service_id);
 
-        service_lock.info().status.set(Status::Stopping).await;
+        info!("Removed service {}", service_id);
 
-        self.shutdown_service(&mut service_lock).await?;
+        let _ = self
+            .events
+            .on_service_removed
+            .dispatch(Arc::new(service_id.clone()))
+            .await;
+        self.refresh_overall_status().await;
 
-        let service_status_event = service_lock.info().status.as_ref();
-        let detach_result = self.on_status_change.detach(service_status_event).await;
-        if let Err(err) = detach_result {
-            return Err(ShutdownError::StatusDetachmentFailed(
+        Ok(service)
+    }
+
+    /*
+        Swaps in a new implementation under the same ID, handing it the old instance's snapshot.
+        The replacement is only started if the old instance was active. If it fails to start,
+        it stays registered in its failed state, as the old instance is already stopped.
+    */
+    pub async fn replace_service(
+        &self,
+        service_id: &ServiceId,
+        new_service: impl Into<ServiceHandle>,
+    ) -> Result<Arc<Mutex<dyn Service>>, ReplaceError> {
+        let new_service = new_service.into();
+        let new_service_id = new_service.service().lock().await.info().id.clone();
+        if new_service_id != *service_id {
+            return Err(ReplaceError::ServiceIdMismatch(
                 service_id.clone(),
-                err,
+                new_service_id,
             ));
         }
 
-        info!("Stopped service {}", service_lock.info().name);
-
-        Ok(())
-    }
+        let _registration = self.registration.lock().await;
 
-    pub async fn start_services(&self) -> Vec<Result<(), StartupError>> {
-        let mut results = Vec::new();
+        let old_service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(ReplaceError::ServiceNotManaged(service_id.clone())),
+        };
 
-        for service in &self.services {
-            let service_arc_clone = Arc::clone(service);
-            let result = self.start_service(service_arc_clone).await;
+        let (snapshot, was_active) = {
+            let service_lock = self.lock_service(&old_service).await;
+            let snapshot = self.snapshot_service(&service_lock).await;
+            (snapshot, service_lock.info().status.get().await.is_active())
+        };
 
-            results.push(result);
+        if was_active {
+            self.stop_service(Arc::clone(&old_service)).await?;
+        } else {
+            self.clean_up_inactive_service(&self.lock_service(&old_service).await)
+                .await;
         }
 
-        results
-    }
-
-    pub async fn stop_services(&self) -> Vec<Result<(), ShutdownError>> {
-        let mut results = Vec::new();
+        let replacement = Arc::clone(new_service.service());
+        self.services.write().await.replace(service_id, new_service);
+        self.forget_lock_holder(&old_service);
+        self.restart_history.lock().await.remove(service_id);
+        self.task_statuses.lock().await.remove(service_id);
+        self.restore_service(&mut self.lock_service(&replacement).await, snapshot)
+            .await;
 
-        for service in &self.services {
-            let service_arc_clone = Arc::clone(service);
-            let result = self.stop_service(service_arc_clone).await;
+        info!("Replaced service {}", service_id);
 
-            results.push(result);
+        if was_active {
+            self.start_service(replacement).await?;
         }
+        self.refresh_overall_status().await;
 
-        results
+        Ok(old_service)
     }
 
-    /*
-        I tried to do this in safe rust for 3 days, but I couldn't figure it out
-        Should you come up with a way to do this in safe rust, please make a PR! :)
-        Anyways, this should never cause any issues, since we checked if the service is of type T
-    */
-    pub async fn get_service<T>(&self) -> Option<Arc<Mutex<T>>>
-    where
-        T: Service,
-    {
-        for service in self.services.iter() {
-            let lock = service.lock().await;
+    async fn clean_up_inactive_service(&self, service_lock: &MutexGuard<'_, dyn Service>) {
+        self.stop_background_tasks(service_lock).await;
+        self.stop_monitors(service_lock).await;
 
-            let is_t = lock.as_any().is::<T>();
-            if is_t {
-                let service_ptr: *const Arc<Mutex<dyn Service>> = service;
+        // Not being attached is fine here, as the service might have never been started
+        let service_status_event = &service_lock.info().on_status_change;
+        let _ = self.on_status_change.detach(service_status_event).await;
 
-                unsafe {
-                    let t_ptr: *const Arc<Mutex<T>> = mem::transmute(service_ptr);
-                    return Some(Arc::clone(&*t_ptr));
-                }
-            }
-        }
+        // Also cancels pending restarts of a failed service
+        service_lock.info().set_status(Status::Stopped).await;
+    }
 
-        None
+    pub async fn manages_service(&self, service_id: &ServiceId) -> bool {
+        self.services.read().await.contains(service_id)
     }
 
-    //TODO: When Rust allows async closures, refactor this to use iterator methods instead of for loop
-    pub async fn overall_status(&self) -> OverallStatus {
-        for service in self.services.iter() {
-            let service = service.lock().await;
+    pub async fn get_service_by_id(
+        &self,
+        service_id: &ServiceId,
+    ) -> Option<Arc<Mutex<dyn Service>>> {
+        self.services
+            .read()
+            .await
+            .get(service_id)
+            .map(|handle| Arc::clone(handle.service()))
+    }
 
-            if service.info().priority != Priority::Essential {
-                continue;
-            }
+    pub async fn start_service(
+        &self,
+        service: Arc<Mutex<dyn Service>>,
+    ) -> Result<(), StartupError> {
+        let result = self
+            .start_service_without_escalation(Arc::clone(&service))
+            .await;
 
-            let status = service.info().status.get().await;
-            if status != Status::Started {
-                return OverallStatus::Unhealthy;
-            }
+        if let Err(
+            StartupError::FailedToStartService(service_id)
+            | StartupError::InvalidConfig(service_id, _),
+        ) = &result
+        {
+            self.escalate(service_id).await;
         }
 
-        OverallStatus::Healthy
+        result
     }
 
-    //TODO: When Rust allows async closures, refactor this to use iterator methods instead of for loop
-    pub async fn status_overview(&self) -> String {
-        let mut text_buffer = String::new();
-
-        let mut failed_essentials = Vec::new();
-        let mut failed_optionals = Vec::new();
-        let mut non_failed_essentials = Vec::new();
-        let mut non_failed_optionals = Vec::new();
-        let mut others = Vec::new();
+    // Used by restarts, which only escalate once they give up
+    async fn start_service_without_escalation(
+        &self,
+        service: Arc<Mutex<dyn Service>>,
+    ) -> Result<(), StartupError> {
+        let service_id = self.lock_service(&service).await.info().id.clone();
+        if !self.manages_service(&service_id).await {
+            return Err(StartupError::ServiceNotManaged(service_id.clone()));
+        }
 
-        for service in self.services.iter() {
-            let service = service.lock().await;
-            let info = service.info();
-            let priority = &info.priority;
-            let status = info.status.get().await;
+        let mut service_lock = self.lock_service(&service).await;
+        let result = self.start_locked_service(&service, &mut service_lock).await;
+        drop(service_lock);
 
-            match status {
-                Status::Started | Status::Stopped => match priority {
-                    Priority::Essential => {
-                        non_failed_essentials.push(format!(" - {}: {}", info.name, status));
-                    }
-                    Priority::Optional => {
-                        non_failed_optionals.push(format!(" - {}: {}", info.name, status));
-                    }
-                },
-                Status::FailedToStart(_) | Status::FailedToStop(_) | Status::RuntimeError(_) => {
-                    match priority {
-                        Priority::Essential => {
-                            failed_essentials.push(format!(" - {}: {}", info.name, status));
-                        }
-                        Priority::Optional => {
-                            failed_optionals.push(format!(" - {}: {}", info.name, status));
-                        }
-                    }
-                }
-                _ => {
-                    others.push(format!(" - {}: {}", info.name, status));
-                }
-            }
-        }
+        self.refresh_overall_status().await;
+        result
+    }
 
-        if !failed_essentials.is_empty() {
-            text_buffer.push_str(&format!("{}:\n", "Failed essential services"));
-            text_buffer.push_str(failed_essentials.join("\n").as_str());
-        }
+    #[instrument(
+        name = "start_service",
+        skip_all,
+        fields(service_id = %service_lock.info().id, service_name = %service_lock.info().name)
+    )]
+    async fn start_locked_service(
+        &self,
+        service: &Arc<Mutex<dyn Service>>,
+        service_lock: &mut MutexGuard<'_, dyn Service>,
+    ) -> Result<(), StartupError> {
+        let service_id = service_lock.info().id.clone();
 
-        if !failed_optionals.is_empty() {
-            text_buffer.push_str(&format!("{}:\n", "Failed optional services"));
-            text_buffer.push_str(failed_optionals.join("\n").as_str());
+        let status = service_lock.info().status.get().await;
+        if !matches!(status, Status::Stopped) {
+            return Err(StartupError::ServiceNotStopped(service_id.clone()));
         }
 
-        if !non_failed_essentials.is_empty() {
-            text_buffer.push_str(&format!("{}:\n", "Essential services"));
-            text_buffer.push_str(non_failed_essentials.join("\n").as_str());
+        if self.has_background_task_registered(&service_id).await {
+            return Err(StartupError::BackgroundTaskAlreadyRunning(
+                service_id.clone(),
+            ));
         }
 
-        if !non_failed_optionals.is_empty() {
-            text_buffer.push_str(&format!("{}:\n", "Optional services"));
-            text_buffer.push_str(non_failed_optionals.join("\n").as_str());
-        }
+        let service_status_event = &service_lock.info().on_status_change;
+        let attachment_result = self.on_status_change.attach(service_status_event, 2).await;
+        if let Err(err) = attachment_result {
+            return Err(StartupError::StatusAttachmentFailed(
+                service_id.clone(),
+                err,
+            ));
+        }
+
+        service_lock.info().set_status(Status::Starting).await;
+        self.init_service_with_retries(service_lock).await?;
+        self.start_background_tasks(service_lock, Arc::clone(service))
+            .await;
+        self.start_monitors(service_lock, Arc::clone(service)).await;
+        self.startup_order.lock().await.push(service_id);
+
+        info!("Started service {}", service_lock.info().name);
+
+        // The service is already started at this point, so a failing hook doesn't change that
+        let context = self.context(service_lock.info());
+        if let Err(error) = service_lock.post_start(context).await {
+            warn!(
+                "Service {} failed to run its post-start hook: {}",
+                service_lock.info().name,
+                error
+            );
+        }
+
+        if service_lock.info().readiness == Readiness::Immediate {
+            service_lock.info().mark_ready().await;
+        }
+
+        Ok(())
+    }
+
+    // Starts the service unless it is already running, e.g. to bring up a Lazy service ahead of its first use
+    pub async fn ensure_started(&self, service_id: &ServiceId) -> Result<(), StartupError> {
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(StartupError::ServiceNotManaged(service_id.clone())),
+        };
+
+        self.start_inactive_service(service).await
+    }
+
+    // Checks the status under the same lock it starts the service with, so concurrent callers can't both see it stopped
+    async fn start_inactive_service(
+        &self,
+        service: Arc<Mutex<dyn Service>>,
+    ) -> Result<(), StartupError> {
+        let mut service_lock = self.lock_service(&service).await;
+        if service_lock.info().status.get().await.is_active() {
+            return Ok(());
+        }
+
+        let result = self.start_locked_service(&service, &mut service_lock).await;
+        drop(service_lock);
+
+        self.refresh_overall_status().await;
+        if let Err(
+            StartupError::FailedToStartService(service_id)
+            | StartupError::InvalidConfig(service_id, _),
+        ) = &result
+        {
+            self.escalate(service_id).await;
+        }
+
+        result
+    }
+
+    pub async fn start_service_by_id(&self, service_id: &ServiceId) -> Result<(), StartupError> {
+        match self.get_service_by_id(service_id).await {
+            Some(service) => self.start_service(service).await,
+            None => Err(StartupError::ServiceNotManaged(service_id.clone())),
+        }
+    }
+
+    // Essential services that fail to stop or have to be killed escalate, see EscalationPolicy
+    pub async fn stop_service(
+        &self,
+        service: Arc<Mutex<dyn Service>>,
+    ) -> Result<(), ShutdownError> {
+        let result = self
+            .stop_service_without_escalation(Arc::clone(&service))
+            .await;
+
+        if let Err(
+            ShutdownError::FailedToStopService(service_id)
+            | ShutdownError::Killed(service_id)
+            | ShutdownError::BackgroundTaskFailed(service_id, _),
+        ) = &result
+        {
+            self.escalate(service_id).await;
+        }
+
+        result
+    }
+
+    //TODO: Clean up
+    // Used by full shutdowns and by escalations themselves, which escalating again would only repeat
+    async fn stop_service_without_escalation(
+        &self,
+        service: Arc<Mutex<dyn Service>>,
+    ) -> Result<(), ShutdownError> {
+        let service_id = self.lock_service(&service).await.info().id.clone();
+        if !(self.manages_service(&service_id).await) {
+            return Err(ShutdownError::ServiceNotManaged(service_id.clone()));
+        }
+
+        let mut service_lock = self.lock_service(&service).await;
+        let result = self.stop_locked_service(&mut service_lock).await;
+        drop(service_lock);
+
+        // The service's status event is detached by now, so its last changes don't reach the watcher
+        self.refresh_overall_status().await;
+        result
+    }
+
+    #[instrument(
+        name = "stop_service",
+        skip_all,
+        fields(service_id = %service_lock.info().id, service_name = %service_lock.info().name)
+    )]
+    async fn stop_locked_service(
+        &self,
+        service_lock: &mut MutexGuard<'_, dyn Service>,
+    ) -> Result<(), ShutdownError> {
+        let service_id = service_lock.info().id.clone();
+
+        let status = service_lock.info().status.get().await;
+        if !status.is_active() {
+            return Err(ShutdownError::ServiceNotStarted(service_id.clone()));
+        }
+
+        self.paused_statuses.lock().await.remove(&service_id);
+
+        self.stop_monitors(service_lock).await;
+        self.drain_service(service_lock).await;
+        let task_outcome = self.stop_background_tasks(service_lock).await;
+
+        service_lock.info().set_status(Status::Stopping).await;
+
+        let shutdown_result = self.shutdown_service(service_lock).await;
+
+        // Killed is terminal, so the service is unwired like a stopped one
+        if let Err(ShutdownError::Killed(_)) = shutdown_result {
+            if let Err(err) = self.unwire_service(service_lock).await {
+                warn!("{}", err);
+            }
+            return shutdown_result;
+        }
+        shutdown_result?;
+        self.unwire_service(service_lock).await?;
+
+        // The service itself stopped fine, but a task that didn't end cleanly shouldn't go unnoticed
+        if let Some(task_outcome) = task_outcome {
+            warn!(
+                "Stopped service {}, but {}",
+                service_lock.info().name,
+                task_outcome
+            );
+            service_lock
+                .info()
+                .set_status(Status::FailedToStop(task_outcome.clone()))
+                .await;
+            return Err(ShutdownError::BackgroundTaskFailed(
+                service_id.clone(),
+                task_outcome,
+            ));
+        }
+
+        info!("Stopped service {}", service_lock.info().name);
+
+        Ok(())
+    }
+
+    // Detaches the service's status event and removes it from the startup order
+    async fn unwire_service(
+        &self,
+        service_lock: &MutexGuard<'_, dyn Service>,
+    ) -> Result<(), ShutdownError> {
+        let service_id = service_lock.info().id.clone();
+
+        let service_status_event = &service_lock.info().on_status_change;
+        let detach_result = self.on_status_change.detach(service_status_event).await;
+        if let Err(err) = detach_result {
+            return Err(ShutdownError::StatusDetachmentFailed(service_id, err));
+        }
+
+        self.startup_order
+            .lock()
+            .await
+            .retain(|started_service_id| *started_service_id != service_id);
+
+        Ok(())
+    }
+
+    pub async fn stop_service_by_id(&self, service_id: &ServiceId) -> Result<(), ShutdownError> {
+        match self.get_service_by_id(service_id).await {
+            Some(service) => self.stop_service(service).await,
+            None => Err(ShutdownError::ServiceNotManaged(service_id.clone())),
+        }
+    }
+
+    // Locks the service, so don't call this from within the service's own start or stop
+    pub async fn mark_ready(&self, service_id: &ServiceId) -> Result<(), ReadinessError> {
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(ReadinessError::ServiceNotManaged(service_id.clone())),
+        };
+
+        let service_lock = self.lock_service(&service).await;
+        if service_lock.info().mark_ready().await {
+            return Ok(());
+        }
+
+        let status = service_lock.info().status.get().await;
+        match status {
+            Status::Ready => Ok(()),
+            _ => Err(ReadinessError::ServiceNotStarted(
+                service_id.clone(),
+                status,
+            )),
+        }
+    }
+
+    // Resolves once the service is Ready. Waits through Stopped, as the service might just not be started yet.
+    pub async fn wait_until_ready(&self, service_id: &ServiceId) -> Result<(), ReadinessError> {
+        match self
+            .wait_for_service(service_id, |status| *status == Status::Ready)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(WaitError::ServiceNotManaged(service_id))
+            | Err(WaitError::ServiceDropped(service_id)) => {
+                Err(ReadinessError::ServiceNotManaged(service_id))
+            }
+            Err(WaitError::ServiceFailed(service_id, status)) => {
+                Err(ReadinessError::ServiceFailed(service_id, status))
+            }
+        }
+    }
+
+    // Resolves once the service is running, which includes it being Ready
+    pub async fn wait_for_service_started(&self, service_id: &ServiceId) -> Result<(), WaitError> {
+        self.wait_for_service(service_id, Status::is_running)
+            .await
+            .map(|_| ())
+    }
+
+    // Fails early if the service fails before reaching the awaited status
+    pub async fn wait_for_service(
+        &self,
+        service_id: &ServiceId,
+        mut predicate: impl FnMut(&Status) -> bool,
+    ) -> Result<Status, WaitError> {
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(WaitError::ServiceNotManaged(service_id.clone())),
+        };
+
+        let mut receiver = self.lock_service(&service).await.info().watch_status();
+        drop(service);
+
+        let status = match receiver
+            .wait_for(|status| predicate(status) || status.is_failed())
+            .await
+        {
+            Ok(status) => status.clone(),
+            Err(_) => return Err(WaitError::ServiceDropped(service_id.clone())),
+        };
+
+        if predicate(&status) {
+            Ok(status)
+        } else {
+            Err(WaitError::ServiceFailed(service_id.clone(), status))
+        }
+    }
+
+    // Health checks and heartbeats are suspended while the service is paused
+    pub async fn pause_service(&self, service_id: &ServiceId) -> Result<(), PauseError> {
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(PauseError::ServiceNotManaged(service_id.clone())),
+        };
+
+        let mut service_lock = self.lock_service(&service).await;
+        let status = service_lock.info().status.get().await;
+        if !status.is_running() {
+            return Err(PauseError::ServiceNotRunning(service_id.clone(), status));
+        }
+
+        if let Err(error) = service_lock.pause().await {
+            warn!(
+                "Service {} failed to pause: {}",
+                service_lock.info().name,
+                error
+            );
+            return Err(PauseError::FailedToPause(
+                service_id.clone(),
+                error.to_string(),
+            ));
+        }
+
+        self.stop_monitors(&service_lock).await;
+        self.paused_statuses
+            .lock()
+            .await
+            .insert(service_id.clone(), status);
+        service_lock.info().set_status(Status::Paused).await;
+        info!("Paused service {}", service_lock.info().name);
+        drop(service_lock);
+
+        self.refresh_overall_status().await;
+        Ok(())
+    }
+
+    pub async fn resume_service(&self, service_id: &ServiceId) -> Result<(), ResumeError> {
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(ResumeError::ServiceNotManaged(service_id.clone())),
+        };
+
+        let mut service_lock = self.lock_service(&service).await;
+        let status = service_lock.info().status.get().await;
+        if status != Status::Paused {
+            return Err(ResumeError::ServiceNotPaused(service_id.clone(), status));
+        }
+
+        if let Err(error) = service_lock.resume().await {
+            warn!(
+                "Service {} failed to resume: {}",
+                service_lock.info().name,
+                error
+            );
+            return Err(ResumeError::FailedToResume(
+                service_id.clone(),
+                error.to_string(),
+            ));
+        }
+
+        let previous_status = self
+            .paused_statuses
+            .lock()
+            .await
+            .remove(service_id)
+            .unwrap_or(Status::Started);
+        service_lock.info().set_status(previous_status).await;
+        self.start_monitors(&service_lock, Arc::clone(&service))
+            .await;
+        info!("Resumed service {}", service_lock.info().name);
+        drop(service_lock);
+
+        self.refresh_overall_status().await;
+        Ok(())
+    }
+
+    // Holds the service's lock for the whole restart, so no other lifecycle transition can interleave
+    #[instrument(skip(self), fields(service_id = %service_id))]
+    pub async fn restart_service(&self, service_id: &ServiceId) -> Result<(), RestartError> {
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return Err(RestartError::ServiceNotManaged(service_id.clone())),
+        };
+
+        let mut service_lock = self.lock_service(&service).await;
+        let result = self
+            .restart_locked_service(&service, &mut service_lock)
+            .await;
+        drop(service_lock);
+
+        // Also after a failed restart, which may have left the service stopped or failed
+        self.refresh_overall_status().await;
+        result
+    }
+
+    async fn restart_locked_service(
+        &self,
+        service: &Arc<Mutex<dyn Service>>,
+        service_lock: &mut MutexGuard<'_, dyn Service>,
+    ) -> Result<(), RestartError> {
+        let service_id = service_lock.info().id.clone();
+        let snapshot = self.snapshot_service(service_lock).await;
+
+        let status = service_lock.info().status.get().await;
+        match status {
+            Status::Started | Status::Ready | Status::Paused => {
+                self.stop_locked_service(service_lock).await?
+            }
+            Status::Stopped => {}
+            _ => {
+                if !self.reset_locked_failed_service(service_lock).await {
+                    return Err(RestartError::ServiceNotRestartable(service_id, status));
+                }
+            }
+        }
+
+        // A manual restart gives a crash looping service a fresh start
+        self.restart_history.lock().await.remove(&service_id);
+
+        self.restore_service(service_lock, snapshot).await;
+        self.start_locked_service(service, service_lock).await?;
+        service_lock.info().record_restart().await;
+
+        Ok(())
+    }
+
+    // A dry run of start_services, e.g. to validate a deployment in CI. Nothing is started.
+    pub async fn plan(&self) -> StartupPlan {
+        let (ordered, circular) = self.resolve_startup_order().await;
+
+        let mut plan = StartupPlan::default();
+        if !circular.is_empty() {
+            plan.errors.push(PlanError::CircularDependencies(circular));
+        }
+
+        for (service_id, service) in ordered {
+            let service_lock = self.lock_service(&service).await;
+            let info = service_lock.info();
+
+            for dependency in info.dependencies.iter() {
+                if !self.manages_service(dependency).await {
+                    plan.errors.push(PlanError::MissingDependency(
+                        service_id.clone(),
+                        dependency.clone(),
+                    ));
+                }
+            }
+
+            if let Err(error) = service_lock.validate_config(&self.context(info)) {
+                plan.errors.push(PlanError::InvalidConfig(
+                    service_id.clone(),
+                    error.to_string(),
+                ));
+            }
+
+            if info.startup_mode != StartupMode::Lazy {
+                plan.order.push(service_id);
+            }
+        }
+
+        plan
+    }
+
+    pub async fn start_services(&self) -> Vec<Result<(), StartupError>> {
+        let mut results = Vec::new();
+
+        for service in self.services_in_startup_order().await.iter() {
+            if self.lock_service(service).await.info().startup_mode == StartupMode::Lazy {
+                continue;
+            }
+
+            let service_arc_clone = Arc::clone(service);
+            let result = self.start_service(service_arc_clone).await;
+
+            results.push(result);
+        }
+
+        if results.iter().all(Result::is_ok) {
+            let _ = self.events.on_all_started.dispatch(Arc::new(())).await;
+        }
+
+        results
+    }
+
+    // Unlike start_services, this also starts Lazy services carrying the tag
+    pub async fn start_services_with_tag(&self, tag: &str) -> Vec<Result<(), StartupError>> {
+        let mut results = Vec::new();
+
+        for service in self.services_with_tag(tag).await {
+            results.push(self.start_service(service).await);
+        }
+
+        results
+    }
+
+    // Stops in reverse startup order, like stop_services, but leaves untagged services running
+    pub async fn stop_services_with_tag(&self, tag: &str) -> Vec<Result<(), ShutdownError>> {
+        let mut results = Vec::new();
+
+        for (_, service) in self.services_in_reverse_startup_order().await {
+            if !self.lock_service(&service).await.info().has_tag(tag) {
+                continue;
+            }
+
+            results.push(self.stop_service(service).await);
+        }
+
+        results
+    }
+
+    pub async fn stop_services(&self) -> Vec<Result<(), ShutdownError>> {
+        let _ = self.events.on_shutdown.dispatch(Arc::new(())).await;
+
+        self.stop_services_in_order(self.shutdown_order).await
+    }
+
+    // Stops all services, then aborts whatever is still running, so nothing outlives the manager
+    pub async fn shutdown(&self) -> Vec<Result<(), ShutdownError>> {
+        let results = self.stop_services().await;
+        self.abort_all_tasks().await;
+
+        results
+    }
+
+    // Services still stopping when the deadline passes are abandoned and their tasks aborted
+    pub async fn stop_services_in_order(
+        &self,
+        shutdown_order: ShutdownOrder,
+    ) -> Vec<Result<(), ShutdownError>> {
+        // Nothing is locked before the deadline applies, so a busy service can't hold up the shutdown
+        let (service_ids, services): (Vec<_>, Vec<_>) = match shutdown_order {
+            ShutdownOrder::ReverseStartup | ShutdownOrder::Concurrent => {
+                self.services_in_reverse_startup_order().await
+            }
+            ShutdownOrder::Unordered => self.services_with_ids().await,
+        }
+        .into_iter()
+        .unzip();
+
+        let results: ShutdownResults =
+            Arc::new(Mutex::new((0..services.len()).map(|_| None).collect()));
+        let service_manager = self.arc("stop_services");
+        let mut stop_task = {
+            let results = Arc::clone(&results);
+            spawn(async move {
+                match shutdown_order {
+                    ShutdownOrder::Concurrent => {
+                        service_manager
+                            .stop_services_concurrently(services, results)
+                            .await
+                    }
+                    ShutdownOrder::ReverseStartup | ShutdownOrder::Unordered => {
+                        for (index, service) in services.into_iter().enumerate() {
+                            let result = service_manager
+                                .stop_service_without_escalation(service)
+                                .await;
+                            results.lock().await[index] = Some(result);
+                        }
+                    }
+                }
+            })
+        };
+
+        if timeout(self.shutdown_deadline, &mut stop_task)
+            .await
+            .is_err()
+        {
+            stop_task.abort();
+            let _ = stop_task.await;
+
+            error!(
+                "Services did not stop within the shutdown deadline of {}ms. Aborting their remaining tasks.",
+                self.shutdown_deadline.as_millis()
+            );
+            self.abort_all_tasks().await;
+        }
+
+        let mut results = results.lock().await;
+        results
+            .iter_mut()
+            .zip(service_ids)
+            .map(|(result, service_id)| {
+                result
+                    .take()
+                    .unwrap_or(Err(ShutdownError::DeadlineExceeded(service_id)))
+            })
+            .collect()
+    }
+
+    // Stops services in waves. Each wave holds the services nothing left running depends on.
+    async fn stop_services_concurrently(
+        self: Arc<Self>,
+        services: Vec<Arc<Mutex<dyn Service>>>,
+        results: ShutdownResults,
+    ) {
+        let mut remaining = Vec::new();
+        for (index, service) in services.into_iter().enumerate() {
+            let (service_id, dependencies) = {
+                let service_lock = self.lock_service(&service).await;
+                let info = service_lock.info();
+                (info.id.clone(), info.dependencies.clone())
+            };
+            remaining.push((index, service_id, dependencies, service));
+        }
+
+        while !remaining.is_empty() {
+            let (mut wave, rest): (Vec<_>, Vec<_>) =
+                remaining
+                    .iter()
+                    .cloned()
+                    .partition(|(_, service_id, _, _)| {
+                        !remaining
+                            .iter()
+                            .any(|(_, _, dependencies, _)| dependencies.contains(service_id))
+                    });
+
+            // Circular dependencies can't be ordered, so they are stopped together
+            if wave.is_empty() {
+                warn!(
+                    "Found circular service dependencies. Stopping the remaining services together."
+                );
+                wave = rest.clone();
+            }
+            remaining.retain(|(index, _, _, _)| {
+                !wave.iter().any(|(wave_index, _, _, _)| wave_index == index)
+            });
+
+            let mut stops = JoinSet::new();
+            for (index, _, _, service) in wave {
+                let service_manager = Arc::clone(&self);
+                stops.spawn(async move {
+                    let result = service_manager
+                        .stop_service_without_escalation(service)
+                        .await;
+                    (index, result)
+                });
+            }
+
+            while let Some(stop) = stops.join_next().await {
+                if let Ok((index, result)) = stop {
+                    results.lock().await[index] = Some(result);
+                }
+            }
+        }
+    }
+
+    // Dependencies come first. Among the services whose dependencies are placed, the lowest
+    // startup priority wins, then registration order.
+    async fn services_in_startup_order(&self) -> Vec<Arc<Mutex<dyn Service>>> {
+        let (ordered, circular) = self.resolve_startup_order().await;
+
+        // Circular dependencies can't be ordered, so they fall back to priority alone
+        if !circular.is_empty() {
+            warn!(
+                "Found circular service dependencies. Starting the remaining services by priority."
+            );
+        }
+
+        ordered.into_iter().map(|(_, service)| service).collect()
+    }
+
+    // Also returns the services that had to be placed by priority alone, because of circular dependencies
+    async fn resolve_startup_order(
+        &self,
+    ) -> (Vec<(ServiceId, Arc<Mutex<dyn Service>>)>, Vec<ServiceId>) {
+        let mut remaining = Vec::new();
+        for service in self.services().await {
+            let (service_id, dependencies, startup_priority) = {
+                let service_lock = self.lock_service(&service).await;
+                let info = service_lock.info();
+                (
+                    info.id.clone(),
+                    info.dependencies.clone(),
+                    info.startup_priority,
+                )
+            };
+            remaining.push((service_id, dependencies, startup_priority, service));
+        }
+
+        let mut ordered = Vec::new();
+        let mut circular = Vec::new();
+        while !remaining.is_empty() {
+            let is_ready = |dependencies: &Vec<ServiceId>| {
+                !dependencies.iter().any(|dependency| {
+                    remaining
+                        .iter()
+                        .any(|(service_id, _, _, _)| service_id == dependency)
+                })
+            };
+
+            let next = remaining
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, dependencies, _, _))| is_ready(dependencies))
+                .min_by_key(|(_, (_, _, startup_priority, _))| *startup_priority)
+                .map(|(position, _)| position);
+
+            let position = match next {
+                Some(position) => position,
+                None => {
+                    let position = remaining
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, (_, _, startup_priority, _))| *startup_priority)
+                        .map(|(position, _)| position)
+                        .unwrap_or(0);
+                    circular.push(remaining[position].0.clone());
+
+                    position
+                }
+            };
+
+            let (service_id, _, _, service) = remaining.remove(position);
+            ordered.push((service_id, service));
+        }
+
+        (ordered, circular)
+    }
+
+    // Services that were started are stopped last-started-first. Services that were never started
+    // (or failed to start) follow in reverse registration order, so they still get a result.
+    async fn services_in_reverse_startup_order(&self) -> Vec<(ServiceId, Arc<Mutex<dyn Service>>)> {
+        let startup_order = self.startup_order.lock().await.clone();
+
+        let mut started = Vec::new();
+        let mut not_started = Vec::new();
+        for (service_id, service) in self.services_with_ids().await {
+            match startup_order.iter().position(|id| *id == service_id) {
+                Some(position) => started.push((position, (service_id, service))),
+                None => not_started.push((service_id, service)),
+            }
+        }
+
+        started.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        started
+            .into_iter()
+            .map(|(_, service)| service)
+            .chain(not_started.into_iter().rev())
+            .collect()
+    }
+
+    // Like services, with the IDs taken from the registry instead of locking each service
+    async fn services_with_ids(&self) -> Vec<(ServiceId, Arc<Mutex<dyn Service>>)> {
+        let services = self.services.read().await;
+        services
+            .order
+            .iter()
+            .filter_map(|service_id| {
+                let handle = services.get(service_id)?;
+                Some((service_id.clone(), Arc::clone(handle.service())))
+            })
+            .collect()
+    }
+
+    pub async fn get_service<T>(&self) -> Option<Arc<Mutex<T>>>
+    where
+        T: Service,
+    {
+        let (typed_service, service) = self.services.read().await.iter().find_map(|handle| {
+            handle
+                .downcast::<T>()
+                .map(|typed_service| (typed_service, Arc::clone(handle.service())))
+        })?;
+
+        self.start_lazy_service(service).await;
+
+        Some(typed_service)
+    }
+
+    // Every service registered with capability C, e.g. services_with_capability::<dyn MessageSink>()
+    pub async fn services_with_capability<C>(&self) -> Vec<Arc<Mutex<C>>>
+    where
+        C: ?Sized + Send + 'static,
+    {
+        let services = self
+            .services
+            .read()
+            .await
+            .iter()
+            .filter_map(|handle| {
+                handle
+                    .capability::<C>()
+                    .map(|capability| (capability, Arc::clone(handle.service())))
+            })
+            .collect::<Vec<_>>();
+
+        let mut capabilities = Vec::new();
+        for (capability, service) in services {
+            self.start_lazy_service(service).await;
+            capabilities.push(capability);
+        }
+
+        capabilities
+    }
+
+    // Only starts Lazy services that were never started or have been stopped since
+    async fn start_lazy_service(&self, service: Arc<Mutex<dyn Service>>) {
+        let (service_name, should_start) = {
+            let service_lock = self.lock_service(&service).await;
+            let info = service_lock.info();
+
+            (
+                info.name.clone(),
+                info.startup_mode == StartupMode::Lazy
+                    && info.status.get().await == Status::Stopped,
+            )
+        };
+
+        if !should_start {
+            return;
+        }
+
+        info!("Starting lazy service {} on first use", service_name);
+        if let Err(error) = self.start_inactive_service(service).await {
+            warn!("Failed to start lazy service {}: {}", service_name, error);
+        }
+    }
+
+    pub async fn metrics(&self) -> Vec<ServiceMetrics> {
+        let mut metrics = Vec::new();
+        for service in self.services().await.iter() {
+            metrics.push(self.lock_service(service).await.info().metrics().await);
+        }
+
+        metrics
+    }
+
+    // Statuses of the service's background tasks from when it was last started
+    pub async fn task_statuses(&self, service_id: &ServiceId) -> BTreeMap<String, TaskStatus> {
+        self.task_statuses
+            .lock()
+            .await
+            .get(service_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub async fn overall_status(&self) -> OverallStatus {
+        self.status_report().await.overall_status
+    }
+
+    //TODO: When Rust allows async closures, refactor this to use iterator methods instead of for loop
+    pub async fn status_report(&self) -> StatusReport {
+        let mut services = Vec::new();
+
+        for service in self.services().await.iter() {
+            let service = self.lock_service(service).await;
+            let info = service.info();
+            let status = info.status.get().await;
+
+            services.push(ServiceReport {
+                id: info.id.clone(),
+                name: info.name.clone(),
+                priority: info.priority,
+                error: status.error().map(String::from),
+                status,
+                history: info.history().await,
+                tasks: self.task_statuses(&info.id).await,
+            });
+        }
+
+        StatusReport::new(services)
+    }
+
+    // Unlike status_report, this doesn't collect histories and task statuses
+    pub async fn summary(&self) -> StatusSummary {
+        let mut services = Vec::new();
+        for service in self.services().await.iter() {
+            let service = self.lock_service(service).await;
+            let info = service.info();
+            services.push((info.priority, info.status.get().await));
+        }
+
+        StatusSummary::of(services.iter().map(|(priority, status)| (priority, status)))
+    }
+
+    pub async fn describe(&self) -> String {
+        let mut entries = Vec::new();
+        for service in self.services().await {
+            let service = self.lock_service(&service).await;
+            let info = service.info();
+            entries.push(format!(
+                "{} ({}): {}",
+                info.name,
+                info.id,
+                info.status.get().await
+            ));
+        }
+
+        if entries.is_empty() {
+            return "Services: None".to_string();
+        }
+
+        format!("Services: {}", entries.join(", "))
+    }
+
+    pub async fn status_overview(&self) -> String {
+        self.status_report().await.to_string()
+    }
+
+    /*
+        Locks the service like service.lock() does. With lock diagnostics enabled, it also remembers who holds the lock,
+        so that a caller waiting longer than the threshold can tell where the lock it is stuck on was taken.
+    */
+    #[track_caller]
+    fn lock_service<'a>(
+        &'a self,
+        service: &'a Arc<Mutex<dyn Service>>,
+    ) -> impl Future<Output = ServiceLock<'a>> {
+        let location = Location::caller();
+
+        async move {
+            let threshold = match self.lock_diagnostics {
+                Some(threshold) => threshold,
+                None => {
+                    return ServiceLock {
+                        service_lock: service.lock().await,
+                        holder: None,
+                    };
+                }
+            };
+
+            let key = lock_key(service);
+            let service_lock = match timeout(threshold, service.lock()).await {
+                Ok(service_lock) => service_lock,
+                Err(_) => {
+                    let holder = self
+                        .lock_holders
+                        .lock()
+                        .ok()
+                        .and_then(|lock_holders| lock_holders.get(&key).cloned());
+
+                    match holder {
+                        Some((service_name, holder_location)) => warn!(
+                            "Waiting for the lock of service {} at {} for over {}ms. It was acquired at {}.",
+                            service_name,
+                            location,
+                            threshold.as_millis(),
+                            holder_location
+                        ),
+                        None => warn!(
+                            "Waiting for a service lock at {} for over {}ms. Whoever holds it didn't acquire it through the Service Manager.",
+                            location,
+                            threshold.as_millis()
+                        ),
+                    }
+
+                    service.lock().await
+                }
+            };
+
+            if let Ok(mut lock_holders) = self.lock_holders.lock() {
+                lock_holders.insert(key, (service_lock.info().name.clone(), location));
+            }
+
+            ServiceLock {
+                service_lock,
+                holder: Some((&self.lock_holders, key)),
+            }
+        }
+    }
+
+    // Entries already go away with their guards. This makes sure none outlives the service itself.
+    fn forget_lock_holder(&self, service: &Arc<Mutex<dyn Service>>) {
+        if let Ok(mut lock_holders) = self.lock_holders.lock() {
+            lock_holders.remove(&lock_key(service));
+        }
+    }
+
+    // Configured overrides win over what the service declares, which wins over the default
+    pub fn startup_timeout(&self, service: &dyn Service) -> Duration {
+        self.timeout_overrides
+            .get(&service.info().id)
+            .and_then(|timeout_override| timeout_override.startup)
+            .or_else(|| service.startup_timeout())
+            .unwrap_or(self.default_startup_timeout)
+    }
+
+    pub fn drain_timeout(&self, service: &dyn Service) -> Duration {
+        service
+            .drain_timeout()
+            .unwrap_or(self.default_drain_timeout)
+    }
+
+    pub fn shutdown_timeout(&self, service: &dyn Service) -> Duration {
+        self.timeout_overrides
+            .get(&service.info().id)
+            .and_then(|timeout_override| timeout_override.shutdown)
+            .or_else(|| service.shutdown_timeout())
+            .unwrap_or(self.default_shutdown_timeout)
+    }
+
+    // Actors aren't registered, so they only get the manager's context and config
+    pub async fn start_actor(&self, actor: &ServiceActorHandle) -> Result<(), ActorError> {
+        let context = ServiceContext::new(
+            actor.id.clone(),
+            self.weak.clone(),
+            self.bot_name.clone(),
+            Arc::clone(&self.config),
+        );
+
+        actor.start(context).await
+    }
+
+    fn context(&self, info: &ServiceInfo) -> ServiceContext {
+        ServiceContext::new(
+            info.id.clone(),
+            self.weak.clone(),
+            self.bot_name.clone(),
+            Arc::clone(&self.config),
+        )
+    }
+
+    fn arc(&self, service_name: &str) -> Arc<Self> {
+        // This can't fail because the Arc is guaranteed to be valid as long as &self is valid.
+        match self.weak.upgrade() {
+            Some(arc) => arc,
+            None => {
+                error!(
+                    "ServiceManager's Weak self-reference could not be upgraded to Arc while handling service {}. This should never happen. Shutting down ungracefully to prevent further undefined behavior.",
+                    service_name
+                );
+                unreachable!(
+                    "ServiceManager's Weak self-reference could not be upgraded to Arc while handling service {}.",
+                    service_name
+                );
+            }
+        }
+    }
+
+    // Every failed attempt stays in the status history as a FailedToStart followed by Starting again
+    async fn init_service_with_retries(
+        &self,
+        service: &mut MutexGuard<'_, dyn Service>,
+    ) -> Result<(), StartupError> {
+        let mut attempt = 0;
+        loop {
+            let result = self.init_service(service).await;
+
+            // An invalid config fails the same way on every attempt
+            let startup_retries = match (&result, &service.info().startup_retries) {
+                (Err(StartupError::FailedToStartService(_)), Some(startup_retries)) => {
+                    *startup_retries
+                }
+                _ => return result,
+            };
+
+            attempt += 1;
+            if !startup_retries.allows_attempt(attempt) {
+                error!(
+                    "Service {} failed to start after {} retries",
+                    service.info().name,
+                    attempt - 1
+                );
+                return result;
+            }
+
+            let delay = startup_retries.delay(attempt);
+            warn!(
+                "Service {} failed to start. Retrying in {}ms (retry {}).",
+                service.info().name,
+                delay.as_millis(),
+                attempt
+            );
+            sleep(delay).await;
+
+            service.info().set_status(Status::Starting).await;
+        }
+    }
+
+    #[instrument(
+        name = "init_service",
+        skip_all,
+        fields(service_id = %service.info().id, service_name = %service.info().name)
+    )]
+    async fn init_service(
+        &self,
+        service: &mut MutexGuard<'_, dyn Service>,
+    ) -> Result<(), StartupError> {
+        let context = self.context(service.info());
+
+        // Misconfigured services fail before any of their code runs
+        if let Err(error) = service.validate_config(&context) {
+            error!(
+                "Service {} has an invalid config: {}",
+                service.info().name,
+                error
+            );
+            service
+                .info()
+                .set_status(Status::FailedToStart(format!("Invalid config: {}", error)))
+                .await;
+            return Err(StartupError::InvalidConfig(
+                service.info().id.clone(),
+                error.to_string(),
+            ));
+        }
+
+        let startup_timeout = self.startup_timeout(&**service);
+        let description = format!("Service {} while starting", service.info().name);
+        let start = catch_panic(description, async {
+            service.pre_start(context.clone()).await?;
+            service.start(context).await
+        });
+        let timeout_result = timeout(startup_timeout, start).await;
+
+        match timeout_result {
+            Ok(start_result) => match start_result {
+                Ok(()) => {
+                    service.info().set_status(Status::Started).await;
+                }
+                Err(error) => {
+                    service
+                        .info()
+                        .set_status(Status::FailedToStart(error.to_string()))
+                        .await;
+                    return Err(StartupError::FailedToStartService(
+                        service.info().id.clone(),
+                    ));
+                }
+            },
+            Err(error) => {
+                service
+                    .info()
+                    .set_status(Status::FailedToStart(error.to_string()))
+                    .await;
+                return Err(StartupError::FailedToStartService(
+                    service.info().id.clone(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[instrument(
+        name = "shutdown_service",
+        skip_all,
+        fields(service_id = %service.info().id, service_name = %service.info().name)
+    )]
+    async fn shutdown_service(
+        &self,
+        service: &mut MutexGuard<'_, dyn Service>,
+    ) -> Result<(), ShutdownError> {
+        let shutdown_timeout = self.shutdown_timeout(&**service);
+        let description = format!("Service {} while stopping", service.info().name);
+        let stop = catch_panic(description, async {
+            service.pre_stop().await?;
+            service.stop().await
+        });
+        let timeout_result = timeout(shutdown_timeout, stop).await;
+
+        match timeout_result {
+            Ok(stop_result) => match stop_result {
+                Ok(()) => {
+                    service.info().set_status(Status::Stopped).await;
+
+                    // The service is already stopped at this point, so a failing hook doesn't change that
+                    if let Err(error) = service.post_stop().await {
+                        warn!(
+                            "Service {} failed to run its post-stop hook: {}",
+                            service.info().name,
+                            error
+                        );
+                    }
+                }
+                Err(error) => {
+                    service
+                        .info()
+                        .set_status(Status::FailedToStop(error.to_string()))
+                        .await;
+                    return Err(ShutdownError::FailedToStopService(
+                        service.info().id.clone(),
+                    ));
+                }
+            },
+            // Timing out already dropped the stop future, so only leftovers need tearing down
+            Err(_) => {
+                self.kill_locked_service(service).await;
+                return Err(ShutdownError::Killed(service.info().id.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Draining is best effort. Whatever isn't done by the drain timeout is cut off by the stop.
+    #[instrument(
+        name = "drain_service",
+        skip_all,
+        fields(service_id = %service_lock.info().id, service_name = %service_lock.info().name)
+    )]
+    async fn drain_service(&self, service_lock: &mut MutexGuard<'_, dyn Service>) {
+        if let Some(background_tasks) = self
+            .background_tasks
+            .lock()
+            .await
+            .get(&service_lock.info().id)
+        {
+            background_tasks.draining.cancel();
+        }
+        service_lock.info().set_status(Status::Draining).await;
+
+        let drain_timeout = self.drain_timeout(&**service_lock);
+        let description = format!("Service {} while draining", service_lock.info().name);
+        let drain = catch_panic(description, service_lock.drain());
+        match timeout(drain_timeout, drain).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => warn!(
+                "Service {} failed to drain: {}",
+                service_lock.info().name,
+                error
+            ),
+            Err(_) => warn!(
+                "Service {} did not drain within {}ms. Stopping it anyway.",
+                service_lock.info().name,
+                drain_timeout.as_millis()
+            ),
+        }
+    }
+
+    async fn kill_locked_service(&self, service_lock: &MutexGuard<'_, dyn Service>) {
+        let service_id = service_lock.info().id.clone();
+
+        if let Some(background_tasks) = self.background_tasks.lock().await.remove(&service_id) {
+            background_tasks.cancellation_token.cancel();
+            for (task_name, handle) in background_tasks.handles {
+                if handle.is_finished() {
+                    continue;
+                }
+
+                handle.abort();
+                self.set_task_status(&service_id, &task_name, TaskStatus::Aborted)
+                    .await;
+            }
+        }
+        self.stop_monitors(service_lock).await;
+
+        error!(
+            "Service {} did not stop within {}ms and was killed",
+            service_lock.info().name,
+            self.shutdown_timeout(&**service_lock).as_millis()
+        );
+        service_lock.info().set_status(Status::Killed).await;
+    }
+
+    async fn refresh_overall_status(&self) {
+        let overall_status = self.overall_status().await;
+        let _ = self.events.overall_status.set(overall_status).await;
+
+        let summary = self.describe().await;
+        if let Ok(mut cached_summary) = self.summary.write() {
+            *cached_summary = summary;
+        }
+    }
+
+    /*
+        Status changes are dispatched while the changed service is locked, but computing the overall status locks every service.
+        So instead of computing it in the subscriber, a separate task is only notified and does the work.
+    */
+    async fn watch_overall_status(&self) {
+        let (sender, mut receiver) = channel(1);
+        self.on_status_change
+            .event
+            .subscribe_closure(
+                "service_manager_overall_status",
+                move |_| {
+                    // A full channel already means a refresh is pending
+                    let _ = sender.try_send(());
+                    Ok(())
+                },
+                true,
+                false,
+            )
+            .await
+            // Lives as long as the Service Manager, which owns the event
+            .detach();
+
+        let weak = self.weak.clone();
+        spawn(async move {
+            while receiver.recv().await.is_some() {
+                let service_manager = match weak.upgrade() {
+                    Some(service_manager) => service_manager,
+                    None => return,
+                };
+
+                service_manager.refresh_overall_status().await;
+            }
+        });
+    }
+
+    // Only essential services escalate. Callers must not hold the failed service's lock.
+    async fn escalate(&self, service_id: &ServiceId) {
+        let service = match self.get_service_by_id(service_id).await {
+            Some(service) => service,
+            None => return,
+        };
+
+        let (service_name, priority) = {
+            let service_lock = self.lock_service(&service).await;
+            let info = service_lock.info();
+            (info.name.clone(), info.priority)
+        };
+
+        if priority != Priority::Essential {
+            return;
+        }
+
+        match self.escalation_policy {
+            EscalationPolicy::Ignore => {
+                warn!(
+                    "Essential service {} failed. Ignoring it as configured by the escalation policy.",
+                    service_name
+                );
+            }
+            EscalationPolicy::StopDependents => {
+                let dependents = self.dependents_of(service_id).await;
+                error!(
+                    "Essential service {} failed. Stopping {} service(s) that depend on it.",
+                    service_name,
+                    dependents.len()
+                );
+
+                for dependent in dependents {
+                    if !self
+                        .lock_service(&dependent)
+                        .await
+                        .info()
+                        .status
+                        .get()
+                        .await
+                        .is_active()
+                    {
+                        continue;
+                    }
+
+                    if let Err(error) = self.stop_service_without_escalation(dependent).await {
+                        warn!("Failed to stop dependent service: {}", error);
+                    }
+                }
+            }
+            EscalationPolicy::Shutdown => {
+                error!(
+                    "Essential service {} failed. Requesting a shutdown.",
+                    service_name
+                );
+                let _ = self
+                    .events
+                    .on_shutdown_requested
+                    .dispatch(Arc::new(service_id.clone()))
+                    .await;
+            }
+        }
+    }
+
+    // Direct and transitive dependents, ordered so that a service comes before its own dependencies
+    async fn dependents_of(&self, service_id: &ServiceId) -> Vec<Arc<Mutex<dyn Service>>> {
+        let mut dependencies = Vec::new();
+        for service in self.services().await {
+            let service_dependencies = self
+                .lock_service(&service)
+                .await
+                .info()
+                .dependencies
+                .clone();
+            dependencies.push((service, service_dependencies));
+        }
+
+        let mut failed = vec![service_id.clone()];
+        let mut dependents = Vec::new();
+        let mut index = 0;
+        while index < failed.len() {
+            for (service, service_dependencies) in dependencies.iter() {
+                let id = self.lock_service(service).await.info().id.clone();
+                if service_dependencies.contains(&failed[index]) && !failed.contains(&id) {
+                    failed.push(id);
+                    dependents.push(Arc::clone(service));
+                }
+            }
+
+            index += 1;
+        }
+
+        dependents.reverse();
+        dependents
+    }
+
+    async fn has_background_task_registered(&self, service_id: &ServiceId) -> bool {
+        let tasks = self.background_tasks.lock().await;
+        tasks.contains_key(service_id)
+    }
+
+    async fn start_background_tasks(
+        &self,
+        service_lock: &MutexGuard<'_, dyn Service>,
+        service: Arc<Mutex<dyn Service>>,
+    ) {
+        let service_id = service_lock.info().id.clone();
+        if self.has_background_task_registered(&service_id).await {
+            return;
+        }
+
+        let cancellation_token = CancellationToken::new();
+        let draining = CancellationToken::new();
+        let tasks = service_lock.tasks(cancellation_token.clone());
+        if tasks.is_empty() {
+            return;
+        }
+
+        let mut task_statuses = BTreeMap::new();
+        let mut handles = Vec::new();
+        for (task_name, task) in tasks {
+            task_statuses.insert(task_name.clone(), TaskStatus::Running);
+
+            let description = format!(
+                "Background task {} of service {}",
+                task_name,
+                service_lock.info().name
+            );
+            let mut taskchain = Taskchain::new(Box::pin(catch_panic(description, task)));
+            let span = info_span!(
+                "background_task",
+                service_id = %service_id,
+                service_name = %service_lock.info().name,
+                task = %task_name
+            );
+            let task_id = format!("{}/{}", service_id, task_name);
+            let service = Arc::clone(&service);
+            let service_manager = self.weak.clone();
+            let stopped = cancellation_token.clone();
+            let draining = draining.clone();
+            let service_id = service_id.clone();
+            let name = task_name.clone();
+            taskchain.append(|result| async move {
+                let service_manager = match service_manager.upgrade() {
+                    Some(service_manager) => service_manager,
+                    None => return Ok(()),
+                };
+
+                // The service is being stopped, which holds its lock while waiting for this task
+                if stopped.is_cancelled() || draining.is_cancelled() {
+                    let task_status = match result {
+                        Ok(()) => TaskStatus::Stopped,
+                        Err(error) => {
+                            warn!(
+                                "Background task {} ended with error while stopping: {}",
+                                name, error
+                            );
+                            TaskStatus::Failed(error.to_string())
+                        }
+                    };
+                    service_manager
+                        .set_task_status(&service_id, &name, task_status)
+                        .await;
+
+                    return Ok(());
+                }
+
+                // One failed task fails the whole service, so its other tasks are stopped too
+                stopped.cancel();
+
+                let ended_with_error = result.is_err();
+                let error = match result {
+                    Ok(()) => "ended unexpectedly".to_string(),
+                    Err(error) => format!("ended with error: {}", error),
+                };
+                service_manager
+                    .set_task_status(&service_id, &name, TaskStatus::Failed(error.clone()))
+                    .await;
+
+                /*
+                    Whoever stops the service cancels draining while holding its lock and then waits for this task,
+                    so losing the lock to that means the failure is part of the stop and the service is left to it.
+                */
+                let service_lock = tokio::select! {
+                    service_lock = service_manager.lock_service(&service) => service_lock,
+                    _ = draining.cancelled() => {
+                        warn!(
+                            "Background task {} {} while its service was being stopped",
+                            name, error
+                        );
+                        return Ok(());
+                    }
+                };
+                error!(
+                    "Background task {} of service {} {}! Service will be marked as failed.",
+                    name,
+                    service_lock.info().name,
+                    error
+                );
+                service_lock
+                    .info()
+                    .set_status(Status::RuntimeError(format!(
+                        "Background task {} {}",
+                        name, error
+                    )))
+                    .await;
+
+                let restart_policy = service_lock.info().restart_policy;
+                drop(service_lock);
+
+                service_manager.recover_failed_service(
+                    service,
+                    service_id,
+                    restart_policy.should_restart(ended_with_error),
+                );
+
+                Ok(())
+            });
 
-        if !others.is_empty() {
-            text_buffer.push_str(&format!("{}:\n", "Other services"));
-            text_buffer.push_str(others.join("\n").as_str());
+            let handle = spawn_for_service(
+                service_lock.info(),
+                task_id,
+                taskchain.run().instrument(span),
+            );
+            handles.push((task_name, handle));
         }
 
-        let longest_width = text_buffer
-            .lines()
-            .map(|line| line.len())
-            .max()
-            .unwrap_or(0);
+        self.task_statuses
+            .lock()
+            .await
+            .insert(service_id.clone(), task_statuses);
+        self.background_tasks.lock().await.insert(
+            service_id,
+            BackgroundTasks {
+                handles,
+                cancellation_token,
+                draining,
+            },
+        );
+    }
 
-        let mut headline = String::from("Status overview\n");
-        headline.push_str("─".repeat(longest_width).as_str());
-        headline.push('\n');
-        text_buffer.insert_str(0, &headline);
+    // Doesn't lock the service, because a stopping service holds its own lock
+    async fn set_task_status(
+        &self,
+        service_id: &ServiceId,
+        task_name: &str,
+        task_status: TaskStatus,
+    ) {
+        if let Some(task_statuses) = self.task_statuses.lock().await.get_mut(service_id) {
+            task_statuses.insert(task_name.to_string(), task_status);
+        }
+    }
 
-        text_buffer
+    async fn start_monitors(
+        &self,
+        service_lock: &MutexGuard<'_, dyn Service>,
+        service: Arc<Mutex<dyn Service>>,
+    ) {
+        self.start_health_check(service_lock, Arc::clone(&service))
+            .await;
+        self.start_heartbeat_monitor(service_lock, service).await;
     }
 
-    async fn init_service(
+    async fn start_health_check(
         &self,
-        service: &mut MutexGuard<'_, dyn Service>,
-    ) -> Result<(), StartupError> {
-        let weak = match self.weak.get() {
-            Some(weak) => weak,
-            None => {
+        service_lock: &MutexGuard<'_, dyn Service>,
+        service: Arc<Mutex<dyn Service>>,
+    ) {
+        let health_check = match service_lock.info().health_check {
+            Some(health_check) => health_check,
+            None => return,
+        };
+
+        let service_manager = self.weak.clone();
+        let task_name = format!("{}/health_check", service_lock.info().id);
+        let join_handle = spawn_for_service(service_lock.info(), task_name, async move {
+            let mut failures = 0;
+            loop {
+                sleep(health_check.interval).await;
+                let service_manager = match service_manager.upgrade() {
+                    Some(service_manager) => service_manager,
+                    None => return,
+                };
+
+                // The probe borrows the service, so it holds the lock, but never longer than the health check timeout
+                let (service_name, probe) = {
+                    let service_lock = service_manager.lock_service(&service).await;
+                    let probe = timeout(health_check.timeout, service_lock.health_check()).await;
+                    (service_lock.info().name.clone(), probe)
+                };
+                let error = match probe {
+                    Ok(Ok(())) => {
+                        failures = 0;
+                        continue;
+                    }
+                    Ok(Err(error)) => error.to_string(),
+                    Err(_) => format!("Timed out after {}ms", health_check.timeout.as_millis()),
+                };
+
+                failures += 1;
+                warn!(
+                    "Health check of service {} failed ({}/{}): {}",
+                    service_name, failures, health_check.failure_threshold, error
+                );
+
+                if failures < health_check.failure_threshold {
+                    continue;
+                }
+
                 error!(
-                    "ServiceManager's Weak self-reference was None while initializing service {}. This should never happen. Did you not use a ServiceManagerBuilder? Shutting down ungracefully to prevent further undefined behavior.",
-                    service.info().name
+                    "Health check of service {} failed {} times in a row. Service will be marked as failed.",
+                    service_name, failures
                 );
-                unreachable!(
-                    "ServiceManager's Weak self-reference was None while initializing service {}.",
-                    service.info().name
+                let service_lock = service_manager.lock_service(&service).await;
+                service_lock
+                    .info()
+                    .set_status(Status::RuntimeError(format!(
+                        "Health check failed {} times in a row: {}",
+                        failures, error
+                    )))
+                    .await;
+
+                let restart_policy = service_lock.info().restart_policy;
+                let service_id = service_lock.info().id.clone();
+                drop(service_lock);
+
+                service_manager.recover_failed_service(
+                    service,
+                    service_id,
+                    restart_policy.should_restart(true),
                 );
+
+                return;
             }
+        });
+
+        self.add_monitor(&service_lock.info().id, join_handle).await;
+    }
+
+    async fn start_heartbeat_monitor(
+        &self,
+        service_lock: &MutexGuard<'_, dyn Service>,
+        service: Arc<Mutex<dyn Service>>,
+    ) {
+        let heartbeat_timeout = match service_lock.info().heartbeat_timeout {
+            Some(heartbeat_timeout) => heartbeat_timeout,
+            None => return,
         };
+        let heartbeat = service_lock.info().heartbeat();
+
+        let service_manager = self.weak.clone();
+        let task_name = format!("{}/heartbeat", service_lock.info().id);
+        let join_handle = spawn_for_service(service_lock.info(), task_name, async move {
+            let mut last_beats = heartbeat.beats();
+            loop {
+                sleep(heartbeat_timeout).await;
+
+                let beats = heartbeat.beats();
+                if beats != last_beats {
+                    last_beats = beats;
+                    continue;
+                }
 
-        // This can't fail because the Arc is guaranteed to be valid as long as &self is valid.
-        let arc = match weak.upgrade() {
-            Some(arc) => arc,
-            None => {
+                let service_manager = match service_manager.upgrade() {
+                    Some(service_manager) => service_manager,
+                    None => return,
+                };
+                let service_lock = service_manager.lock_service(&service).await;
                 error!(
-                    "ServiceManager's Weak self-reference could not be upgraded to Arc while initializing service {}. This should never happen. Shutting down ungracefully to prevent further undefined behavior.",
-                    service.info().name
+                    "Service {} missed its heartbeat for {}ms. Service will be marked as failed.",
+                    service_lock.info().name,
+                    heartbeat_timeout.as_millis()
                 );
-                unreachable!(
-                    "ServiceManager's Weak self-reference could not be upgraded to Arc while initializing service {}.",
-                    service.info().name
+                service_lock
+                    .info()
+                    .set_status(Status::RuntimeError("heartbeat missed".to_string()))
+                    .await;
+
+                let restart_policy = service_lock.info().restart_policy;
+                let service_id = service_lock.info().id.clone();
+                drop(service_lock);
+
+                service_manager.recover_failed_service(
+                    service,
+                    service_id,
+                    restart_policy.should_restart(true),
                 );
+
+                return;
             }
-        };
+        });
 
-        //TODO: Add to config instead of hardcoding duration
-        let start = service.start(arc);
-        let timeout_result = timeout(Duration::from_secs(10), start).await;
+        self.add_monitor(&service_lock.info().id, join_handle).await;
+    }
 
-        match timeout_result {
-            Ok(start_result) => match start_result {
-                Ok(()) => {
-                    service.info().status.set(Status::Started).await;
-                }
-                Err(error) => {
-                    service
-                        .info()
-                        .status
-                        .set(Status::FailedToStart(error.to_string()))
-                        .await;
-                    return Err(StartupError::FailedToStartService(
-                        service.info().id.clone(),
-                    ));
-                }
-            },
-            Err(error) => {
-                service
+    async fn add_monitor(&self, service_id: &ServiceId, monitor: JoinHandle<()>) {
+        self.monitors
+            .lock()
+            .await
+            .entry(service_id.clone())
+            .or_default()
+            .push(monitor);
+    }
+
+    // Restarts or escalates a service that just failed at runtime. Its lock must not be held.
+    fn recover_failed_service(
+        self: Arc<Self>,
+        service: Arc<Mutex<dyn Service>>,
+        service_id: ServiceId,
+        restart: bool,
+    ) {
+        if restart {
+            spawn_named(
+                format!("{}/restart", service_id),
+                self.restart_failed_service(service),
+            );
+        } else {
+            // Spawned because escalating may stop the task that detected the failure
+            spawn_named(format!("{}/escalation", service_id), async move {
+                self.escalate(&service_id).await
+            });
+        }
+    }
+
+    // Boxed because this is spawned from the background task started by start_service, which would
+    // otherwise make the future's type recursive.
+    fn restart_failed_service(
+        self: Arc<Self>,
+        service: Arc<Mutex<dyn Service>>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async move {
+            let (service_id, service_name, backoff) = {
+                let service_lock = self.lock_service(&service).await;
+                let info = service_lock.info();
+
+                (
+                    info.id.clone(),
+                    info.name.clone(),
+                    info.restart_policy.backoff().copied(),
+                )
+            };
+
+            let backoff = match backoff {
+                Some(backoff) => backoff,
+                None => return,
+            };
+
+            if self.record_restart(&service_id).await {
+                error!(
+                    "Service {} was restarted more than {} times within {}s. It is crash looping and will not be restarted again.",
+                    service_name,
+                    self.crash_loop_detection.max_restarts,
+                    self.crash_loop_detection.window.as_secs()
+                );
+
+                self.lock_service(&service)
+                    .await
                     .info()
-                    .status
-                    .set(Status::FailedToStart(error.to_string()))
+                    .set_status(Status::CrashLooping)
                     .await;
-                return Err(StartupError::FailedToStartService(
-                    service.info().id.clone(),
-                ));
+                let _ = self
+                    .on_crash_loop
+                    .dispatch(Arc::new(service_id.clone()))
+                    .await;
+                self.escalate(&service_id).await;
+
+                return;
             }
-        }
 
-        Ok(())
+            let stopped_siblings = self.stop_supervision_siblings(&service_id).await;
+            let snapshot = self
+                .snapshot_service(&self.lock_service(&service).await)
+                .await;
+
+            let mut attempt = 1;
+            while backoff.allows_attempt(attempt) {
+                let delay = backoff.delay(attempt);
+                warn!(
+                    "Restarting service {} in {}ms (attempt {})",
+                    service_name,
+                    delay.as_millis(),
+                    attempt
+                );
+                sleep(delay).await;
+
+                if !self.reset_failed_service(&service).await {
+                    info!(
+                        "Service {} is no longer failed. Cancelling its restart.",
+                        service_name
+                    );
+                    return;
+                }
+                self.restore_service(&mut self.lock_service(&service).await, snapshot.clone())
+                    .await;
+
+                match self
+                    .start_service_without_escalation(Arc::clone(&service))
+                    .await
+                {
+                    Ok(()) => {
+                        self.lock_service(&service)
+                            .await
+                            .info()
+                            .record_restart()
+                            .await;
+                        info!(
+                            "Restarted service {} after {} attempt(s)",
+                            service_name, attempt
+                        );
+                        self.restart_supervision_siblings(stopped_siblings).await;
+                        return;
+                    }
+                    Err(error) => {
+                        warn!("Failed to restart service {}: {}", service_name, error);
+                    }
+                }
+
+                attempt += 1;
+            }
+
+            error!(
+                "Giving up on restarting service {} after {} attempt(s)",
+                service_name,
+                attempt - 1
+            );
+            self.escalate(&service_id).await;
+        })
     }
 
-    async fn shutdown_service(
+    // Stops the running group siblings of a failed service in reverse group order and returns them
+    async fn stop_supervision_siblings(
         &self,
-        service: &mut MutexGuard<'_, dyn Service>,
-    ) -> Result<(), ShutdownError> {
-        //TODO: Add to config instead of hardcoding duration
-        let stop = service.stop();
-        let timeout_result = timeout(Duration::from_secs(10), stop).await;
+        service_id: &ServiceId,
+    ) -> Vec<Arc<Mutex<dyn Service>>> {
+        let sibling_ids = self
+            .supervision_groups
+            .iter()
+            .flat_map(|group| group.siblings_to_restart(service_id))
+            .collect::<Vec<_>>();
+
+        let mut stopped_siblings = Vec::new();
+        for sibling_id in sibling_ids.iter().rev() {
+            let sibling = match self.get_service_by_id(sibling_id).await {
+                Some(sibling) => sibling,
+                None => continue,
+            };
+
+            if !self
+                .lock_service(&sibling)
+                .await
+                .info()
+                .status
+                .get()
+                .await
+                .is_running()
+            {
+                continue;
+            }
 
-        match timeout_result {
-            Ok(stop_result) => match stop_result {
+            info!(
+                "Stopping service {} because its supervision group sibling {} failed",
+                sibling_id, service_id
+            );
+            match self.stop_service(Arc::clone(&sibling)).await {
+                Ok(()) => stopped_siblings.push(sibling),
+                Err(error) => warn!("Failed to stop service {}: {}", sibling_id, error),
+            }
+        }
+
+        stopped_siblings.reverse();
+        stopped_siblings
+    }
+
+    async fn restart_supervision_siblings(&self, siblings: Vec<Arc<Mutex<dyn Service>>>) {
+        for sibling in siblings {
+            let sibling_id = self.lock_service(&sibling).await.info().id.clone();
+            match self.start_service(Arc::clone(&sibling)).await {
                 Ok(()) => {
-                    service.info().status.set(Status::Stopped).await;
-                }
-                Err(error) => {
-                    service
+                    self.lock_service(&sibling)
+                        .await
                         .info()
-                        .status
-                        .set(Status::FailedToStop(error.to_string()))
-                        .await;
-                    return Err(ShutdownError::FailedToStopService(
-                        service.info().id.clone(),
-                    ));
+                        .record_restart()
+                        .await
                 }
-            },
-            Err(error) => {
-                service
-                    .info()
-                    .status
-                    .set(Status::FailedToStop(error.to_string()))
-                    .await;
-                return Err(ShutdownError::FailedToStopService(
-                    service.info().id.clone(),
-                ));
+                Err(error) => warn!("Failed to restart service {}: {}", sibling_id, error),
             }
         }
+    }
 
-        Ok(())
+    // Returns true if this restart exceeds the allowed number of restarts within the crash loop window
+    async fn record_restart(&self, service_id: &ServiceId) -> bool {
+        let now = Instant::now();
+        let window = self.crash_loop_detection.window;
+
+        let mut restart_history = self.restart_history.lock().await;
+        let restarts = restart_history.entry(service_id.clone()).or_default();
+        restarts.retain(|restart| now.duration_since(*restart) <= window);
+        restarts.push(now);
+
+        restarts.len() > self.crash_loop_detection.max_restarts as usize
     }
 
-    async fn has_background_task_registered(&self, service_id: &str) -> bool {
-        let tasks = self.background_tasks.lock().await;
-        tasks.contains_key(service_id)
+    // Brings a failed service back to Stopped so start_service accepts it again.
+    // Returns false if the service is not in a failed state (e.g. it was stopped manually meanwhile).
+    async fn reset_failed_service(&self, service: &Arc<Mutex<dyn Service>>) -> bool {
+        let mut service_lock = self.lock_service(service).await;
+        self.reset_locked_failed_service(&mut service_lock).await
+    }
+
+    // A failing snapshot only costs the warm restart, so it doesn't stop the restart itself
+    async fn snapshot_service(&self, service_lock: &MutexGuard<'_, dyn Service>) -> Option<Value> {
+        match service_lock.snapshot().await {
+            Ok(snapshot) => snapshot,
+            Err(error) => {
+                warn!(
+                    "Service {} failed to take a snapshot before restarting: {}",
+                    service_lock.info().name,
+                    error
+                );
+                None
+            }
+        }
     }
 
-    async fn start_background_task(
+    async fn restore_service(
         &self,
-        service_lock: &MutexGuard<'_, dyn Service>,
-        service: Arc<Mutex<dyn Service>>,
+        service_lock: &mut MutexGuard<'_, dyn Service>,
+        snapshot: Option<Value>,
     ) {
-        if self
-            .has_background_task_registered(&service_lock.info().id)
-            .await
-        {
-            return;
+        let snapshot = match snapshot {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+
+        if let Err(error) = service_lock.restore(snapshot).await {
+            warn!(
+                "Service {} failed to restore its snapshot. It will start cold: {}",
+                service_lock.info().name,
+                error
+            );
         }
+    }
 
-        let task = service_lock.task();
-        if let Some(task) = task {
-            let mut taskchain = Taskchain::new(task);
+    async fn reset_locked_failed_service(
+        &self,
+        service_lock: &mut MutexGuard<'_, dyn Service>,
+    ) -> bool {
+        let status = service_lock.info().status.get().await;
+        if !matches!(
+            status,
+            Status::RuntimeError(_)
+                | Status::FailedToStart(_)
+                | Status::FailedToStop(_)
+                | Status::CrashLooping
+        ) {
+            return false;
+        }
 
-            taskchain.append(|result| async move {
-                let service = service.lock().await;
+        self.stop_background_tasks(service_lock).await;
+        self.stop_monitors(service_lock).await;
+
+        // Not being attached is fine here, e.g. when a previous restart attempt failed early
+        let service_status_event = &service_lock.info().on_status_change;
+        let _ = self.on_status_change.detach(service_status_event).await;
+
+        let shutdown_timeout = self.shutdown_timeout(&**service_lock);
+        let stop = service_lock.stop();
+        match timeout(shutdown_timeout, stop).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => warn!(
+                "Service {} failed to clean up before restarting: {}",
+                service_lock.info().name,
+                error
+            ),
+            Err(error) => warn!(
+                "Service {} failed to clean up before restarting: {}",
+                service_lock.info().name,
+                error
+            ),
+        }
 
-                match result {
-                    Ok(()) => {
-                        error!(
-                            "Background task of service {} ended unexpectedly! Service will be marked as failed.",
-                            service.info().name
-                        );
+        let service_id = service_lock.info().id.clone();
+        self.startup_order
+            .lock()
+            .await
+            .retain(|started_service_id| *started_service_id != service_id);
 
-                        service
-                            .info()
-                            .status
-                            .set(Status::RuntimeError("Background task ended unexpectedly!".to_string()))
-                            .await;
-                    }
+        service_lock.info().set_status(Status::Stopped).await;
 
-                    Err(error) => {
-                        error!(
-                            "Background task of service {} ended with error: {}. Service will be marked as failed.",
-                            service.info().name,
-                            error
-                        );
+        true
+    }
 
-                        service
-                            .info()
-                            .status
-                            .set(Status::RuntimeError(
-                                format!("Background task ended with error: {}", error),
-                            ))
-                            .await;
-                    }
+    // Returns how the first task that didn't end cleanly ended, if any
+    async fn stop_background_tasks(
+        &self,
+        service_lock: &MutexGuard<'_, dyn Service>,
+    ) -> Option<String> {
+        let service_id = service_lock.info().id.clone();
+        let background_tasks = self
+            .background_tasks
+            .lock()
+            .await
+            .remove(&service_lock.info().id);
+        let BackgroundTasks {
+            mut handles,
+            cancellation_token,
+            draining,
+        } = match background_tasks {
+            Some(background_tasks) => background_tasks,
+            None => return None,
+        };
+
+        // Tasks get the shutdown timeout to wind down cooperatively before being aborted.
+        // A task failing meanwhile must not wait for the lock held here, which draining tells it.
+        draining.cancel();
+        cancellation_token.cancel();
+        let shutdown_timeout = self.shutdown_timeout(&**service_lock);
+        let finished = timeout(shutdown_timeout, async {
+            for (_, handle) in handles.iter_mut() {
+                let _ = handle.await;
+            }
+        })
+        .await;
+
+        if finished.is_err() {
+            for (task_name, handle) in handles {
+                if handle.is_finished() {
+                    continue;
                 }
-                Ok(())
-            });
 
-            let join_handle = spawn(taskchain.run());
+                warn!(
+                    "Background task {} of service {} did not finish within {}ms after being cancelled. Aborting it.",
+                    task_name,
+                    service_lock.info().name,
+                    shutdown_timeout.as_millis()
+                );
+                handle.abort();
 
-            self.background_tasks
-                .lock()
-                .await
-                .insert(service_lock.info().id.clone(), join_handle);
+                // The task is cancelled at its next await point, so this doesn't block for long
+                let _ = handle.await;
+                self.set_task_status(&service_id, &task_name, TaskStatus::Aborted)
+                    .await;
+            }
         }
-    }
 
-    async fn stop_background_task(&self, service_lock: &MutexGuard<'_, dyn Service>) {
-        if !self
-            .has_background_task_registered(&service_lock.info().id)
+        self.task_statuses(&service_id)
             .await
-        {
-            return;
+            .into_iter()
+            .find_map(|(task_name, task_status)| match task_status {
+                TaskStatus::Failed(error) => Some(format!(
+                    "background task {} ended with error: {}",
+                    task_name, error
+                )),
+                TaskStatus::Aborted => Some(format!("background task {} was aborted", task_name)),
+                _ => None,
+            })
+    }
+
+    // Doesn't need any service lock, because hung services might still hold theirs
+    async fn abort_all_tasks(&self) {
+        for (_, tasks) in self.background_tasks.lock().await.drain() {
+            tasks.cancellation_token.cancel();
+            for (_, handle) in tasks.handles {
+                handle.abort();
+            }
+        }
+
+        for (_, monitors) in self.monitors.lock().await.drain() {
+            for monitor in monitors {
+                monitor.abort();
+            }
         }
+    }
 
-        let mut tasks_lock = self.background_tasks.lock().await;
-        let task = tasks_lock.get(&service_lock.info().id).unwrap();
-        task.abort();
-        tasks_lock.remove(&service_lock.info().id);
+    async fn stop_monitors(&self, service_lock: &MutexGuard<'_, dyn Service>) {
+        let monitors = self.monitors.lock().await.remove(&service_lock.info().id);
+
+        for monitor in monitors.into_iter().flatten() {
+            monitor.abort();
+            let _ = monitor.await;
+        }
     }
 }
 
-impl Display for ServiceManager {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Services: ")?;
-
-        if self.services.is_empty() {
-            write!(f, "None")?;
-            return Ok(());
+// Without this, dropping a manager that wasn't shut down would leave its tasks detached on the runtime
+impl Drop for ServiceManager {
+    fn drop(&mut self) {
+        for (_, tasks) in self.background_tasks.get_mut().drain() {
+            tasks.cancellation_token.cancel();
+            for (_, handle) in tasks.handles {
+                handle.abort();
+            }
         }
 
-        let mut services = self.services.iter().peekable();
-        while let Some(service) = services.next() {
-            let service = service.blocking_lock();
-            write!(f, "{} ({})", service.info().name, service.info().id)?;
-            if services.peek().is_some() {
-                write!(f, ", ")?;
+        for (_, monitors) in self.monitors.get_mut().drain() {
+            for monitor in monitors {
+                monitor.abort();
             }
         }
-        Ok(())
+    }
+}
+
+// Renders the summary cached by the last refresh, so it is safe to format from within the runtime
+impl Display for ServiceManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.summary.read() {
+            Ok(summary) => write!(f, "{}", summary),
+            Err(_) => write!(f, "Services: unavailable"),
+        }
     }
 }
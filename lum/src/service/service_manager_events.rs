@@ -0,0 +1,38 @@
+use crate::event::{Event, Observable};
+
+use super::types::{OverallStatus, ServiceId};
+
+pub struct ServiceManagerEvents {
+    pub on_service_added: Event<ServiceId>,
+    pub on_service_removed: Event<ServiceId>,
+    pub on_all_started: Event<()>,
+    pub on_shutdown: Event<()>,
+
+    // Dispatched with the failed essential service when the escalation policy asks for a shutdown
+    pub on_shutdown_requested: Event<ServiceId>,
+
+    // Its event fires whenever the overall status changes
+    pub overall_status: Observable<OverallStatus>,
+}
+
+impl ServiceManagerEvents {
+    pub fn new() -> Self {
+        Self {
+            on_service_added: Event::new("service_manager_on_service_added"),
+            on_service_removed: Event::new("service_manager_on_service_removed"),
+            on_all_started: Event::new("service_manager_on_all_started"),
+            on_shutdown: Event::new("service_manager_on_shutdown"),
+            on_shutdown_requested: Event::new("service_manager_on_shutdown_requested"),
+            overall_status: Observable::new(
+                OverallStatus::Healthy,
+                "service_manager_on_overall_status_change",
+            ),
+        }
+    }
+}
+
+impl Default for ServiceManagerEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
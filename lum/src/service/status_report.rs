@@ -0,0 +1,223 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+};
+
+use serde::Serialize;
+
+use super::types::{OverallStatus, Priority, ServiceId, Status, StatusChange, TaskStatus};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceReport {
+    pub id: ServiceId,
+    pub name: String,
+    pub priority: Priority,
+    pub status: Status,
+    pub error: Option<String>,
+
+    // Oldest transition first
+    pub history: Vec<StatusChange>,
+
+    // Background tasks by name
+    pub tasks: BTreeMap<String, TaskStatus>,
+}
+
+impl ServiceReport {
+    pub fn is_failed(&self) -> bool {
+        self.status.is_failed()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct StatusCounts {
+    // Started or Ready
+    pub started: usize,
+    pub failed: usize,
+    pub stopped: usize,
+
+    // Services in between, like Starting or Paused
+    pub other: usize,
+}
+
+impl StatusCounts {
+    fn count(&mut self, status: &Status) {
+        match status {
+            Status::Started | Status::Ready => self.started += 1,
+            Status::Stopped => self.stopped += 1,
+            _ if status.is_failed() => self.failed += 1,
+            _ => self.other += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.started + self.failed + self.stopped + self.other
+    }
+}
+
+// Counts per priority, for health endpoints that need more than the overall status
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusSummary {
+    pub overall_status: OverallStatus,
+    pub essential: StatusCounts,
+    pub optional: StatusCounts,
+}
+
+impl StatusSummary {
+    pub fn of<'a>(services: impl IntoIterator<Item = (&'a Priority, &'a Status)>) -> Self {
+        let services = services.into_iter().collect::<Vec<_>>();
+        let mut essential = StatusCounts::default();
+        let mut optional = StatusCounts::default();
+
+        for (priority, status) in services.iter() {
+            match priority {
+                Priority::Essential => essential.count(status),
+                Priority::Optional => optional.count(status),
+            }
+        }
+
+        Self {
+            overall_status: OverallStatus::of(services),
+            essential,
+            optional,
+        }
+    }
+
+    pub fn started(&self) -> usize {
+        self.essential.started + self.optional.started
+    }
+
+    pub fn failed(&self) -> usize {
+        self.essential.failed + self.optional.failed
+    }
+
+    pub fn stopped(&self) -> usize {
+        self.essential.stopped + self.optional.stopped
+    }
+
+    pub fn total(&self) -> usize {
+        self.essential.total() + self.optional.total()
+    }
+}
+
+// E.g. "Degraded: 14 started, 1 optional failed"
+impl Display for StatusSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = [
+            (self.started(), "started"),
+            (self.essential.failed, "essential failed"),
+            (self.optional.failed, "optional failed"),
+            (self.stopped(), "stopped"),
+            (self.essential.other + self.optional.other, "other"),
+        ];
+
+        let parts = counts
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, label)| format!("{} {}", count, label))
+            .collect::<Vec<_>>();
+
+        if parts.is_empty() {
+            return write!(f, "{}: no services", self.overall_status);
+        }
+
+        write!(f, "{}: {}", self.overall_status, parts.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    pub overall_status: OverallStatus,
+    pub services: Vec<ServiceReport>,
+}
+
+impl StatusReport {
+    pub fn new(services: Vec<ServiceReport>) -> Self {
+        let overall_status = OverallStatus::of(
+            services
+                .iter()
+                .map(|service| (&service.priority, &service.status)),
+        );
+
+        Self {
+            overall_status,
+            services,
+        }
+    }
+
+    pub fn summary(&self) -> StatusSummary {
+        StatusSummary::of(
+            self.services
+                .iter()
+                .map(|service| (&service.priority, &service.status)),
+        )
+    }
+
+    pub fn failed_services(&self) -> impl Iterator<Item = &ServiceReport> {
+        self.services.iter().filter(|service| service.is_failed())
+    }
+}
+
+impl Display for StatusReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut failed_essentials = Vec::new();
+        let mut failed_optionals = Vec::new();
+        let mut non_failed_essentials = Vec::new();
+        let mut non_failed_optionals = Vec::new();
+        let mut others = Vec::new();
+
+        for service in self.services.iter() {
+            let mut entry = format!(" - {}: {}", service.name, service.status);
+            // A single task is already covered by the service's own status
+            if service.tasks.len() > 1 {
+                for (task_name, task_status) in service.tasks.iter() {
+                    entry.push_str(&format!("\n    Task {}: {}", task_name, task_status));
+                }
+            }
+
+            match service.status {
+                Status::Started | Status::Ready | Status::Stopped => match service.priority {
+                    Priority::Essential => non_failed_essentials.push(entry),
+                    Priority::Optional => non_failed_optionals.push(entry),
+                },
+                _ if service.is_failed() => {
+                    // Shows how the service ended up failing
+                    for status_change in service.history.iter() {
+                        entry.push_str(&format!(
+                            "\n    {}: {} -> {}",
+                            humantime::format_rfc3339_seconds(status_change.timestamp),
+                            status_change.old,
+                            status_change.new
+                        ));
+                    }
+
+                    match service.priority {
+                        Priority::Essential => failed_essentials.push(entry),
+                        Priority::Optional => failed_optionals.push(entry),
+                    }
+                }
+                _ => others.push(entry),
+            }
+        }
+
+        let sections = [
+            ("Failed essential services", failed_essentials),
+            ("Failed optional services", failed_optionals),
+            ("Essential services", non_failed_essentials),
+            ("Optional services", non_failed_optionals),
+            ("Other services", others),
+        ];
+
+        let body = sections
+            .iter()
+            .filter(|(_, entries)| !entries.is_empty())
+            .map(|(title, entries)| format!("{}:\n{}", title, entries.join("\n")))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let longest_width = body.lines().map(|line| line.len()).max().unwrap_or(0);
+
+        writeln!(f, "Status overview")?;
+        writeln!(f, "{}", "─".repeat(longest_width))?;
+        write!(f, "{}", body)
+    }
+}
@@ -1,10 +1,18 @@
 use std::{
+    borrow::Borrow,
     error::Error,
     fmt::{self, Display},
     future::Future,
     pin::Pin,
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{self, AtomicU64},
+    },
+    time::{Duration, SystemTime},
 };
 
+use serde::{Serialize, Serializer};
 use thiserror::Error;
 
 use crate::event::event_repeater::{AttachError, DetachError};
@@ -18,27 +26,143 @@ pub type LifetimedPinnedBoxedFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Se
 pub type LifetimedPinnedBoxedFutureResult<'a, T> =
     LifetimedPinnedBoxedFuture<'a, Result<T, BoxedError>>;
 
-#[derive(Debug, Clone)]
+// Allowed are ASCII letters, digits, '_', '-' and '.'
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ServiceId(Arc<str>);
+
+impl ServiceId {
+    pub fn new(id: &str) -> Result<Self, InvalidServiceIdError> {
+        if id.is_empty() {
+            return Err(InvalidServiceIdError::Empty);
+        }
+
+        if let Some(character) = id
+            .chars()
+            .find(|character| !Self::is_valid_character(*character))
+        {
+            return Err(InvalidServiceIdError::InvalidCharacter(
+                id.to_string(),
+                character,
+            ));
+        }
+
+        Ok(Self(Arc::from(id)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn is_valid_character(character: char) -> bool {
+        character.is_ascii_alphanumeric() || matches!(character, '_' | '-' | '.')
+    }
+}
+
+impl Display for ServiceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for ServiceId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ServiceId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for ServiceId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for ServiceId {
+    type Err = InvalidServiceIdError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Self::new(id)
+    }
+}
+
+impl TryFrom<&str> for ServiceId {
+    type Error = InvalidServiceIdError;
+
+    fn try_from(id: &str) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl TryFrom<String> for ServiceId {
+    type Error = InvalidServiceIdError;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        Self::new(&id)
+    }
+}
+
+impl PartialEq<str> for ServiceId {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for ServiceId {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum InvalidServiceIdError {
+    #[error("Service ID must not be empty")]
+    Empty,
+
+    #[error("Service ID {0} contains invalid character '{1}'")]
+    InvalidCharacter(String, char),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", content = "error")]
 pub enum Status {
     Started,
+    Ready,
     Stopped,
     Starting,
     Stopping,
+    Paused,
+
+    // Finishing in-flight work before stopping, without accepting new work
+    Draining,
     FailedToStart(String),
     FailedToStop(String),
     RuntimeError(String),
+    CrashLooping,
+
+    // Didn't stop in time and was torn down forcefully. Terminal, as its state is unknown.
+    Killed,
 }
 
 impl Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Status::Started => write!(f, "Started"),
+            Status::Ready => write!(f, "Ready"),
             Status::Stopped => write!(f, "Stopped"),
             Status::Starting => write!(f, "Starting"),
             Status::Stopping => write!(f, "Stopping"),
+            Status::Paused => write!(f, "Paused"),
+            Status::Draining => write!(f, "Draining"),
             Status::FailedToStart(error) => write!(f, "Failed to start: {}", error),
             Status::FailedToStop(error) => write!(f, "Failed to stop: {}", error),
             Status::RuntimeError(error) => write!(f, "Runtime error: {}", error),
+            Status::CrashLooping => write!(f, "Crash looping"),
+            Status::Killed => write!(f, "Killed"),
         }
     }
 }
@@ -48,21 +172,181 @@ impl PartialEq for Status {
         matches!(
             (self, other),
             (Status::Started, Status::Started)
+                | (Status::Ready, Status::Ready)
                 | (Status::Stopped, Status::Stopped)
                 | (Status::Starting, Status::Starting)
                 | (Status::Stopping, Status::Stopping)
+                | (Status::Paused, Status::Paused)
+                | (Status::Draining, Status::Draining)
                 | (Status::FailedToStart(_), Status::FailedToStart(_))
                 | (Status::FailedToStop(_), Status::FailedToStop(_))
                 | (Status::RuntimeError(_), Status::RuntimeError(_))
+                | (Status::CrashLooping, Status::CrashLooping)
+                | (Status::Killed, Status::Killed)
         )
     }
 }
 
 impl Eq for Status {}
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+impl Status {
+    // Started services are alive, but only Ready ones accept work
+    pub fn is_running(&self) -> bool {
+        matches!(self, Status::Started | Status::Ready)
+    }
+
+    // Paused services aren't running, but still hold their resources and need to be stopped
+    pub fn is_active(&self) -> bool {
+        self.is_running() || *self == Status::Paused
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self,
+            Status::FailedToStart(_)
+                | Status::FailedToStop(_)
+                | Status::RuntimeError(_)
+                | Status::CrashLooping
+                | Status::Killed
+        )
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Status::FailedToStart(error)
+            | Status::FailedToStop(error)
+            | Status::RuntimeError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// Lazy services are skipped by start_services and started the first time they are looked up
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StartupMode {
+    #[default]
+    Eager,
+    Lazy,
+}
+
+// With Manual readiness, a started service stays Started until it marks itself as ready
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+    #[default]
+    Immediate,
+    Manual,
+}
+
+// A background task together with the name it is reported under
+pub type NamedTask<'a> = (String, LifetimedPinnedBoxedFutureResult<'a, ()>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "error")]
+pub enum TaskStatus {
+    Running,
+    Stopped,
+    Failed(String),
+
+    // Didn't react to its cancellation token in time
+    Aborted,
+}
+
+impl Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskStatus::Running => write!(f, "Running"),
+            TaskStatus::Stopped => write!(f, "Stopped"),
+            TaskStatus::Failed(error) => write!(f, "Failed: {}", error),
+            TaskStatus::Aborted => write!(f, "Aborted"),
+        }
+    }
+}
+
+pub const DEFAULT_STATUS_HISTORY_CAPACITY: usize = 16;
+
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Bounds stop_services as a whole, no matter how many services hang
+pub const DEFAULT_SHUTDOWN_DEADLINE: Duration = Duration::from_secs(30);
+
+// Takes precedence over the timeouts a service declares itself
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutOverride {
+    pub startup: Option<Duration>,
+    pub shutdown: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceMetrics {
+    pub service_id: ServiceId,
+    pub last_started: Option<SystemTime>,
+    // Only set while the service is running
+    pub current_uptime: Option<Duration>,
+    // Includes the current run
+    pub total_uptime: Duration,
+    pub restarts: u32,
+    pub failures: u32,
+
+    // Background tasks and monitors spawned for the service so far
+    pub tasks_spawned: u32,
+
+    // Time spent polling the service's tasks. Only tracked with the task-cpu-time feature.
+    pub task_cpu_time: Option<Duration>,
+}
+
+impl Display for ServiceMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.current_uptime {
+            Some(uptime) => write!(
+                f,
+                "up {}",
+                humantime::format_duration(Duration::from_secs(uptime.as_secs()))
+            )?,
+            None => write!(f, "down")?,
+        }
+
+        write!(
+            f,
+            ", {} restart(s), {} failure(s), {} task(s) spawned",
+            self.restarts, self.failures, self.tasks_spawned
+        )?;
+
+        match self.task_cpu_time {
+            Some(task_cpu_time) => write!(f, ", {}ms task CPU time", task_cpu_time.as_millis()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusChange {
+    pub service_id: ServiceId,
+    pub old: Status,
+    pub new: Status,
+    #[serde(serialize_with = "serialize_rfc3339")]
+    pub timestamp: SystemTime,
+}
+
+fn serialize_rfc3339<S: Serializer>(
+    timestamp: &SystemTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&humantime::format_rfc3339_millis(*timestamp))
+}
+
+impl Display for StatusChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.service_id, self.old, self.new)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize)]
+// Ordered from best to worst
 pub enum OverallStatus {
     Healthy,
+    Degraded,
     Unhealthy,
 }
 
@@ -70,12 +354,30 @@ impl Display for OverallStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             OverallStatus::Healthy => write!(f, "Healthy"),
+            OverallStatus::Degraded => write!(f, "Degraded"),
             OverallStatus::Unhealthy => write!(f, "Unhealthy"),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+impl OverallStatus {
+    // Essential services that aren't running make it Unhealthy, failed optional services make it Degraded.
+    // Paused services were suspended on purpose, so they only degrade it.
+    pub fn of<'a>(services: impl IntoIterator<Item = (&'a Priority, &'a Status)>) -> Self {
+        services
+            .into_iter()
+            .map(|(priority, status)| match priority {
+                _ if *status == Status::Paused => OverallStatus::Degraded,
+                Priority::Essential if !status.is_running() => OverallStatus::Unhealthy,
+                Priority::Optional if status.is_failed() => OverallStatus::Degraded,
+                _ => OverallStatus::Healthy,
+            })
+            .max()
+            .unwrap_or(OverallStatus::Healthy)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize)]
 pub enum Priority {
     Essential,
     Optional,
@@ -90,39 +392,460 @@ impl Display for Priority {
     }
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ShutdownOrder {
+    // One service at a time
+    #[default]
+    ReverseStartup,
+    Unordered,
+
+    // Dependents are stopped before their dependencies, independent services concurrently.
+    // Only declared dependencies are respected. Hung services then only cost one shutdown timeout in total, instead of one each.
+    Concurrent,
+}
+
+impl Display for ShutdownOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownOrder::ReverseStartup => write!(f, "Reverse startup order"),
+            ShutdownOrder::Unordered => write!(f, "Unordered"),
+            ShutdownOrder::Concurrent => write!(f, "Concurrent"),
+        }
+    }
+}
+
+// Which members of a supervision group are restarted when one of them fails
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SupervisionStrategy {
+    // Only the failed service
+    #[default]
+    OneForOne,
+
+    // Every service in the group
+    OneForAll,
+
+    // The failed service and the ones listed after it in the group
+    RestForOne,
+}
+
+impl Display for SupervisionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupervisionStrategy::OneForOne => write!(f, "One for one"),
+            SupervisionStrategy::OneForAll => write!(f, "One for all"),
+            SupervisionStrategy::RestForOne => write!(f, "Rest for one"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupervisionGroup {
+    pub name: String,
+    pub strategy: SupervisionStrategy,
+    pub members: Vec<ServiceId>,
+}
+
+impl SupervisionGroup {
+    pub fn new(name: &str, strategy: SupervisionStrategy) -> Self {
+        Self {
+            name: name.to_string(),
+            strategy,
+            members: Vec::new(),
+        }
+    }
+
+    pub fn with_member(mut self, service_id: ServiceId) -> Self {
+        self.members.push(service_id);
+        self
+    }
+
+    // The other members that have to be restarted together with the failed one, in group order
+    pub fn siblings_to_restart(&self, failed_service_id: &ServiceId) -> Vec<ServiceId> {
+        let position = match self.members.iter().position(|id| id == failed_service_id) {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+
+        match self.strategy {
+            SupervisionStrategy::OneForOne => Vec::new(),
+            SupervisionStrategy::OneForAll => self
+                .members
+                .iter()
+                .filter(|id| *id != failed_service_id)
+                .cloned()
+                .collect(),
+            SupervisionStrategy::RestForOne => self.members[position + 1..].to_vec(),
+        }
+    }
+}
+
+/*
+    What happens when an essential service fails and won't be restarted, or ends up Killed or FailedToStop
+    when stopped on its own. Stops during a full shutdown (ServiceManager::stop_services) don't escalate.
+*/
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum EscalationPolicy {
+    Ignore,
+    StopDependents,
+    #[default]
+    Shutdown,
+}
+
+impl Display for EscalationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EscalationPolicy::Ignore => write!(f, "Ignore"),
+            EscalationPolicy::StopDependents => write!(f, "Stop dependents"),
+            EscalationPolicy::Shutdown => write!(f, "Shutdown"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Backoff {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub max_attempts: Option<u32>,
+}
+
+impl Backoff {
+    pub fn new(initial_delay: Duration, max_delay: Duration, max_attempts: Option<u32>) -> Self {
+        Self {
+            initial_delay,
+            max_delay,
+            max_attempts,
+        }
+    }
+
+    // Attempts are counted from 1. The delay doubles with every attempt until it reaches max_delay.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self.initial_delay.saturating_mul(1 << exponent);
+
+        delay.min(self.max_delay)
+    }
+
+    pub fn allows_attempt(&self, attempt: u32) -> bool {
+        match self.max_attempts {
+            Some(max_attempts) => attempt <= max_attempts,
+            None => true,
+        }
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: Some(5),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct CrashLoopDetection {
+    pub max_restarts: u32,
+    pub window: Duration,
+}
+
+impl CrashLoopDetection {
+    pub fn new(max_restarts: u32, window: Duration) -> Self {
+        Self {
+            max_restarts,
+            window,
+        }
+    }
+}
+
+impl Default for CrashLoopDetection {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(300),
+        }
+    }
+}
+
+// A service is marked as failed after failure_threshold consecutive failed probes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthCheck {
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub failure_threshold: u32,
+}
+
+impl HealthCheck {
+    pub fn new(interval: Duration, failure_threshold: u32) -> Self {
+        Self {
+            interval,
+            timeout: interval,
+            failure_threshold,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+// Cloned into long-running tasks, which beat it to show they aren't hung
+#[derive(Debug, Default, Clone)]
+pub struct Heartbeat {
+    beats: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn beat(&self) {
+        self.beats.fetch_add(1, atomic::Ordering::Relaxed);
+    }
+
+    pub fn beats(&self) -> u64 {
+        self.beats.load(atomic::Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure(Backoff),
+    Always(Backoff),
+}
+
+impl RestartPolicy {
+    pub fn backoff(&self) -> Option<&Backoff> {
+        match self {
+            RestartPolicy::Never => None,
+            RestartPolicy::OnFailure(backoff) | RestartPolicy::Always(backoff) => Some(backoff),
+        }
+    }
+
+    // A background task that returned Ok(()) still ended unexpectedly, but only counts as a
+    // failure if it returned an error.
+    pub fn should_restart(&self, ended_with_error: bool) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure(_) => ended_with_error,
+            RestartPolicy::Always(_) => true,
+        }
+    }
+}
+
+impl Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestartPolicy::Never => write!(f, "Never"),
+            RestartPolicy::OnFailure(_) => write!(f, "On failure"),
+            RestartPolicy::Always(_) => write!(f, "Always"),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum StartupError {
     #[error("Service {0} is not managed by this Service Manager")]
-    ServiceNotManaged(String),
+    ServiceNotManaged(ServiceId),
 
     #[error("Service {0} is not stopped")]
-    ServiceNotStopped(String),
+    ServiceNotStopped(ServiceId),
 
     #[error("Service {0} already has a background task running")]
-    BackgroundTaskAlreadyRunning(String),
+    BackgroundTaskAlreadyRunning(ServiceId),
 
     #[error(
         "Failed to attach Service Manager's status_change EventRepeater to {0}'s status_change Event: {1}"
     )]
-    StatusAttachmentFailed(String, AttachError),
+    StatusAttachmentFailed(ServiceId, AttachError),
 
     #[error("Service {0} failed to start")]
-    FailedToStartService(String),
+    FailedToStartService(ServiceId),
+
+    #[error("Service {0} has an invalid config: {1}")]
+    InvalidConfig(ServiceId, String),
+}
+
+#[derive(Debug, Error)]
+pub enum ReadinessError {
+    #[error("Service {0} is not managed by this Service Manager")]
+    ServiceNotManaged(ServiceId),
+
+    #[error("Service {0} can't become ready while it is in status {1}")]
+    ServiceNotStarted(ServiceId, Status),
+
+    #[error("Service {0} failed before becoming ready: {1}")]
+    ServiceFailed(ServiceId, Status),
+}
+
+#[derive(Debug, Error)]
+pub enum WaitError {
+    #[error("Service {0} is not managed by this Service Manager")]
+    ServiceNotManaged(ServiceId),
+
+    #[error("Service {0} failed while waiting for it: {1}")]
+    ServiceFailed(ServiceId, Status),
+
+    #[error("Service {0} was dropped while waiting for it")]
+    ServiceDropped(ServiceId),
+}
+
+#[derive(Debug, Error)]
+pub enum ActorError {
+    #[error("The actor of service {0} is no longer running")]
+    ActorStopped(ServiceId),
+
+    #[error(transparent)]
+    Startup(#[from] StartupError),
+
+    #[error(transparent)]
+    Shutdown(#[from] ShutdownError),
+
+    #[error(transparent)]
+    Pause(#[from] PauseError),
+
+    #[error(transparent)]
+    Resume(#[from] ResumeError),
+}
+
+#[derive(Debug, Error)]
+pub enum BuildError {
+    #[error("Services were registered more than once: {}", format_service_ids(.0))]
+    DuplicateServices(Vec<ServiceId>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PlanError {
+    #[error("Service {0} depends on {1}, which is not managed by this Service Manager")]
+    MissingDependency(ServiceId, ServiceId),
+
+    #[error("Services have circular dependencies: {}", format_service_ids(.0))]
+    CircularDependencies(Vec<ServiceId>),
+
+    #[error("Service {0} has an invalid config: {1}")]
+    InvalidConfig(ServiceId, String),
+}
+
+// What start_services would do, computed without starting anything
+#[derive(Debug, Clone, Default)]
+pub struct StartupPlan {
+    // Lazy services are validated, but not part of the order because they start on first use
+    pub order: Vec<ServiceId>,
+    pub errors: Vec<PlanError>,
+}
+
+impl StartupPlan {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn format_service_ids(service_ids: &[ServiceId]) -> String {
+    service_ids
+        .iter()
+        .map(|service_id| service_id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Error)]
+pub enum RegistrationError {
+    #[error("Service {0} is already managed by this Service Manager")]
+    ServiceAlreadyManaged(ServiceId),
+
+    #[error("Service was added, but failed to start: {0}")]
+    Startup(#[from] StartupError),
+}
+
+#[derive(Debug, Error)]
+pub enum RemovalError {
+    #[error("Service {0} is not managed by this Service Manager")]
+    ServiceNotManaged(ServiceId),
+
+    #[error("Unable to stop service before removing it: {0}")]
+    Shutdown(#[from] ShutdownError),
+}
+
+#[derive(Debug, Error)]
+pub enum ReplaceError {
+    #[error("Service {0} is not managed by this Service Manager")]
+    ServiceNotManaged(ServiceId),
+
+    #[error("Service {0} can't be replaced by service {1}, as their IDs differ")]
+    ServiceIdMismatch(ServiceId, ServiceId),
+
+    #[error("Unable to stop service before replacing it: {0}")]
+    Shutdown(#[from] ShutdownError),
+
+    #[error("Service was replaced, but the replacement failed to start: {0}")]
+    Startup(#[from] StartupError),
+}
+
+#[derive(Debug, Error)]
+pub enum PauseError {
+    #[error("Service {0} is not managed by this Service Manager")]
+    ServiceNotManaged(ServiceId),
+
+    #[error("Service {0} can't be paused while it is in status {1}")]
+    ServiceNotRunning(ServiceId, Status),
+
+    #[error("Service {0} failed to pause: {1}")]
+    FailedToPause(ServiceId, String),
+}
+
+#[derive(Debug, Error)]
+pub enum ResumeError {
+    #[error("Service {0} is not managed by this Service Manager")]
+    ServiceNotManaged(ServiceId),
+
+    #[error("Service {0} can't be resumed while it is in status {1}")]
+    ServiceNotPaused(ServiceId, Status),
+
+    #[error("Service {0} failed to resume: {1}")]
+    FailedToResume(ServiceId, String),
+}
+
+#[derive(Debug, Error)]
+pub enum RestartError {
+    #[error("Service {0} is not managed by this Service Manager")]
+    ServiceNotManaged(ServiceId),
+
+    #[error("Service {0} can't be restarted while it is in status {1}")]
+    ServiceNotRestartable(ServiceId, Status),
+
+    #[error("Unable to stop service: {0}")]
+    Shutdown(#[from] ShutdownError),
+
+    #[error("Unable to start service: {0}")]
+    Startup(#[from] StartupError),
 }
 
 #[derive(Debug, Error)]
 pub enum ShutdownError {
     #[error("Service {0} is not managed by this Service Manager")]
-    ServiceNotManaged(String),
+    ServiceNotManaged(ServiceId),
 
     #[error("Service {0} is not started")]
-    ServiceNotStarted(String),
+    ServiceNotStarted(ServiceId),
 
     #[error("Service {0} failed to stop")]
-    FailedToStopService(String),
+    FailedToStopService(ServiceId),
+
+    #[error("Service {0} stopped, but {1}")]
+    BackgroundTaskFailed(ServiceId, String),
+
+    #[error("Service {0} did not stop in time and was killed")]
+    Killed(ServiceId),
+
+    #[error("Service {0} did not stop before the shutdown deadline")]
+    DeadlineExceeded(ServiceId),
 
     #[error(
         "Failed to detach Service Manager's status_change EventRepeater from {0}'s status_change Event: {1}"
     )]
-    StatusDetachmentFailed(String, DetachError),
+    StatusDetachmentFailed(ServiceId, DetachError),
 }
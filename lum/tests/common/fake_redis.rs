@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream, tcp::OwnedWriteHalf},
+    sync::{Mutex, mpsc},
+    task::JoinHandle,
+};
+
+type Subscribers = Arc<Mutex<Vec<(String, Arc<Mutex<OwnedWriteHalf>>)>>>;
+
+// Just enough of a Redis server for SUBSCRIBE and PUBLISH. Anything else, like CLIENT SETINFO, is answered with OK.
+pub struct FakeRedis {
+    pub url: String,
+    subscribers: Subscribers,
+    connections: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    published: mpsc::UnboundedReceiver<(String, String)>,
+    server: JoinHandle<()>,
+}
+
+impl FakeRedis {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("redis://{}", listener.local_addr().unwrap());
+        let subscribers: Subscribers = Arc::new(Mutex::new(Vec::new()));
+        let connections = Arc::new(Mutex::new(Vec::new()));
+        let (published_sender, published) = mpsc::unbounded_channel();
+
+        let server = {
+            let subscribers = Arc::clone(&subscribers);
+            let connections = Arc::clone(&connections);
+            tokio::spawn(async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    let connection = tokio::spawn(serve(
+                        stream,
+                        Arc::clone(&subscribers),
+                        published_sender.clone(),
+                    ));
+                    connections.lock().await.push(connection);
+                }
+            })
+        };
+
+        Self {
+            url,
+            subscribers,
+            connections,
+            published,
+            server,
+        }
+    }
+
+    // Delivers a message like another client's PUBLISH would
+    pub async fn publish(&self, channel: &str, message: &str) -> usize {
+        deliver(&self.subscribers, channel, message).await
+    }
+
+    pub async fn subscriber_count(&self, channel: &str) -> usize {
+        self.subscribers
+            .lock()
+            .await
+            .iter()
+            .filter(|(subscribed, _)| subscribed == channel)
+            .count()
+    }
+
+    // Channel and message of the next PUBLISH by a client
+    pub async fn next_published(&mut self) -> (String, String) {
+        self.published.recv().await.unwrap()
+    }
+
+    // Closes every open connection, like a restarting server would
+    pub async fn disconnect_all(&self) {
+        for connection in self.connections.lock().await.drain(..) {
+            connection.abort();
+        }
+        self.subscribers.lock().await.clear();
+    }
+}
+
+impl Drop for FakeRedis {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+fn bulk(data: &str) -> String {
+    format!("${}\r\n{}\r\n", data.len(), data)
+}
+
+async fn serve(
+    stream: TcpStream,
+    subscribers: Subscribers,
+    published: mpsc::UnboundedSender<(String, String)>,
+) {
+    let (reader, writer) = stream.into_split();
+    let writer = Arc::new(Mutex::new(writer));
+    let mut reader = BufReader::new(reader);
+
+    while let Some(command) = read_command(&mut reader).await {
+        let reply = match command[0].to_uppercase().as_str() {
+            "SUBSCRIBE" => {
+                let mut reply = String::new();
+                for (index, channel) in command[1..].iter().enumerate() {
+                    subscribers
+                        .lock()
+                        .await
+                        .push((channel.clone(), Arc::clone(&writer)));
+                    reply += &format!(
+                        "*3\r\n{}{}:{}\r\n",
+                        bulk("subscribe"),
+                        bulk(channel),
+                        index + 1
+                    );
+                }
+                reply
+            }
+            "PUBLISH" => {
+                let _ = published.send((command[1].clone(), command[2].clone()));
+                let receivers = deliver(&subscribers, &command[1], &command[2]).await;
+                format!(":{}\r\n", receivers)
+            }
+            _ => "+OK\r\n".to_string(),
+        };
+
+        if writer
+            .lock()
+            .await
+            .write_all(reply.as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+
+    subscribers
+        .lock()
+        .await
+        .retain(|(_, subscriber)| !Arc::ptr_eq(subscriber, &writer));
+}
+
+async fn deliver(subscribers: &Subscribers, channel: &str, message: &str) -> usize {
+    let pushed = format!(
+        "*3\r\n{}{}{}",
+        bulk("message"),
+        bulk(channel),
+        bulk(message)
+    );
+
+    let mut receivers = 0;
+    for (subscribed, subscriber) in subscribers.lock().await.iter() {
+        if subscribed == channel
+            && subscriber
+                .lock()
+                .await
+                .write_all(pushed.as_bytes())
+                .await
+                .is_ok()
+        {
+            receivers += 1;
+        }
+    }
+
+    receivers
+}
+
+// Commands arrive as arrays of bulk strings
+async fn read_command(reader: &mut (impl AsyncBufRead + Unpin)) -> Option<Vec<String>> {
+    let count = read_header(reader, '*').await?;
+
+    let mut parts = Vec::with_capacity(count);
+    for _ in 0..count {
+        let length = read_header(reader, '$').await?;
+        let mut data = vec![0; length + 2];
+        reader.read_exact(&mut data).await.ok()?;
+        data.truncate(length);
+        parts.push(String::from_utf8(data).ok()?);
+    }
+
+    Some(parts)
+}
+
+async fn read_header(reader: &mut (impl AsyncBufRead + Unpin), kind: char) -> Option<usize> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    line.trim_end().strip_prefix(kind)?.parse().ok()
+}
@@ -0,0 +1,703 @@
+use std::{
+    future,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use lum::service::{
+    Backoff, BoxedError, CancellationToken, LifetimedPinnedBoxedFutureResult, NamedTask, Priority,
+    Service, ServiceContext, ServiceId, ServiceInfo,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::{sync::Mutex, time::sleep};
+
+pub type Journal = Arc<Mutex<Vec<String>>>;
+
+pub struct TestService {
+    info: ServiceInfo,
+    journal: Journal,
+}
+
+impl TestService {
+    pub fn new(id: &str, priority: Priority, journal: Journal) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, priority),
+            journal,
+        }
+    }
+
+    pub fn with_info(info: ServiceInfo, journal: Journal) -> Self {
+        Self { info, journal }
+    }
+}
+
+#[async_trait]
+impl Service for TestService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        self.journal
+            .lock()
+            .await
+            .push(format!("start {}", self.info.id));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        self.journal
+            .lock()
+            .await
+            .push(format!("stop {}", self.info.id));
+
+        Ok(())
+    }
+}
+
+// Journals every lifecycle hook and the transition itself
+pub struct HookedService {
+    info: ServiceInfo,
+    journal: Journal,
+}
+
+impl HookedService {
+    pub fn new(id: &str, journal: Journal) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Essential),
+            journal,
+        }
+    }
+
+    async fn log(&self, entry: &str) {
+        self.journal.lock().await.push(entry.to_string());
+    }
+}
+
+#[async_trait]
+impl Service for HookedService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn pre_start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        self.log("pre_start").await;
+        Ok(())
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        self.log("start").await;
+        Ok(())
+    }
+
+    async fn post_start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        self.log("post_start").await;
+        Ok(())
+    }
+
+    async fn pre_stop(&mut self) -> Result<(), BoxedError> {
+        self.log("pre_stop").await;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        self.log("stop").await;
+        Ok(())
+    }
+
+    async fn post_stop(&mut self) -> Result<(), BoxedError> {
+        self.log("post_stop").await;
+        Ok(())
+    }
+}
+
+// Its health check fails while `healthy` is false
+pub struct ProbedService {
+    info: ServiceInfo,
+    healthy: Arc<AtomicBool>,
+}
+
+impl ProbedService {
+    pub fn new(info: ServiceInfo, healthy: Arc<AtomicBool>) -> Self {
+        Self { info, healthy }
+    }
+}
+
+#[async_trait]
+impl Service for ProbedService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<(), BoxedError> {
+        if self.healthy.load(Ordering::SeqCst) {
+            Ok(())
+        } else {
+            Err("unhealthy".into())
+        }
+    }
+}
+
+pub fn service_id(id: &str) -> ServiceId {
+    ServiceId::new(id).unwrap()
+}
+
+pub fn journal() -> Journal {
+    Arc::new(Mutex::new(Vec::new()))
+}
+
+pub fn test_service(id: &str, priority: Priority, journal: &Journal) -> Arc<Mutex<TestService>> {
+    Arc::new(Mutex::new(TestService::new(
+        id,
+        priority,
+        Arc::clone(journal),
+    )))
+}
+
+// Its background task fails on the first `failures` starts and runs forever afterwards
+pub struct CrashingService {
+    info: ServiceInfo,
+    failures: u32,
+    starts: Arc<AtomicU32>,
+}
+
+impl CrashingService {
+    pub fn new(info: ServiceInfo, failures: u32, starts: Arc<AtomicU32>) -> Self {
+        Self {
+            info,
+            failures,
+            starts,
+        }
+    }
+}
+
+#[async_trait]
+impl Service for CrashingService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        self.starts.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn task<'a>(
+        &self,
+        _cancellation_token: CancellationToken,
+    ) -> Option<LifetimedPinnedBoxedFutureResult<'a, ()>> {
+        let should_fail = self.starts.load(Ordering::SeqCst) <= self.failures;
+
+        Some(Box::pin(async move {
+            if should_fail {
+                return Err("crashed".into());
+            }
+
+            future::pending::<()>().await;
+            Ok(())
+        }))
+    }
+}
+
+// Never finishes stopping
+pub struct HangingService {
+    info: ServiceInfo,
+}
+
+impl HangingService {
+    pub fn new(id: &str) -> Self {
+        Self::with_info(ServiceInfo::new(service_id(id), id, Priority::Optional))
+    }
+
+    pub fn with_info(info: ServiceInfo) -> Self {
+        Self { info }
+    }
+}
+
+#[async_trait]
+impl Service for HangingService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        future::pending::<()>().await;
+        Ok(())
+    }
+}
+
+// Its background task runs until it is cancelled
+pub struct CancellableService {
+    info: ServiceInfo,
+    journal: Journal,
+}
+
+impl CancellableService {
+    pub fn new(id: &str, journal: Journal) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Essential),
+            journal,
+        }
+    }
+}
+
+#[async_trait]
+impl Service for CancellableService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn task<'a>(
+        &self,
+        cancellation_token: CancellationToken,
+    ) -> Option<LifetimedPinnedBoxedFutureResult<'a, ()>> {
+        let journal = Arc::clone(&self.journal);
+        let id = self.info.id.clone();
+
+        Some(Box::pin(async move {
+            cancellation_token.cancelled().await;
+            journal.lock().await.push(format!("cancelled {}", id));
+
+            Ok(())
+        }))
+    }
+}
+
+// Runs a task until cancelled next to one that crashes right away
+pub struct MultiTaskService {
+    info: ServiceInfo,
+}
+
+impl MultiTaskService {
+    pub fn new(id: &str) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Optional),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for MultiTaskService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn tasks<'a>(&self, cancellation_token: CancellationToken) -> Vec<NamedTask<'a>> {
+        vec![
+            (
+                "worker".to_string(),
+                Box::pin(async move {
+                    cancellation_token.cancelled().await;
+                    Ok(())
+                }),
+            ),
+            (
+                "crasher".to_string(),
+                Box::pin(async { Err("crashed".into()) }),
+            ),
+        ]
+    }
+}
+
+// Panics in the given lifecycle step ("start", "stop" or "task")
+pub struct PanickingService {
+    info: ServiceInfo,
+    panics_in: &'static str,
+}
+
+impl PanickingService {
+    pub fn new(id: &str, panics_in: &'static str) -> Self {
+        Self::with_info(
+            ServiceInfo::new(service_id(id), id, Priority::Optional),
+            panics_in,
+        )
+    }
+
+    pub fn with_info(info: ServiceInfo, panics_in: &'static str) -> Self {
+        Self { info, panics_in }
+    }
+}
+
+#[async_trait]
+impl Service for PanickingService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        if self.panics_in == "start" {
+            panic!("boom");
+        }
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        if self.panics_in == "stop" {
+            panic!("boom");
+        }
+
+        Ok(())
+    }
+
+    fn task<'a>(
+        &self,
+        cancellation_token: CancellationToken,
+    ) -> Option<LifetimedPinnedBoxedFutureResult<'a, ()>> {
+        let panics = self.panics_in == "task";
+
+        Some(Box::pin(async move {
+            if panics {
+                panic!("boom");
+            }
+
+            cancellation_token.cancelled().await;
+            Ok(())
+        }))
+    }
+}
+
+// Beats its heartbeat every 10ms for as long as it is told to
+pub struct HeartbeatService {
+    info: ServiceInfo,
+    beating: Arc<AtomicBool>,
+}
+
+impl HeartbeatService {
+    pub fn new(info: ServiceInfo, beating: Arc<AtomicBool>) -> Self {
+        Self { info, beating }
+    }
+}
+
+#[async_trait]
+impl Service for HeartbeatService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn task<'a>(
+        &self,
+        cancellation_token: CancellationToken,
+    ) -> Option<LifetimedPinnedBoxedFutureResult<'a, ()>> {
+        let heartbeat = self.info.heartbeat();
+        let beating = Arc::clone(&self.beating);
+
+        Some(Box::pin(async move {
+            while !cancellation_token.is_cancelled() {
+                if beating.load(Ordering::SeqCst) {
+                    heartbeat.beat();
+                }
+
+                sleep(Duration::from_millis(10)).await;
+            }
+
+            Ok(())
+        }))
+    }
+}
+
+// Records what its ServiceContext offered when it was started, and keeps the context around
+pub struct ContextProbeService {
+    info: ServiceInfo,
+    journal: Journal,
+    context: Option<ServiceContext>,
+}
+
+impl ContextProbeService {
+    pub fn new(id: &str, journal: Journal) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Optional),
+            journal,
+            context: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Service for ContextProbeService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, context: ServiceContext) -> Result<(), BoxedError> {
+        let found_test_service = context.service::<TestService>().await.is_some();
+        self.journal.lock().await.push(format!(
+            "bot {}, found test service: {}",
+            context.bot_name, found_test_service
+        ));
+        self.context = Some(context);
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PollerConfig {
+    pub interval: u64,
+}
+
+// Requires a services.<id> config section with an interval
+pub struct ConfiguredService {
+    info: ServiceInfo,
+    journal: Journal,
+}
+
+impl ConfiguredService {
+    pub fn new(id: &str, journal: Journal) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Optional),
+            journal,
+        }
+    }
+}
+
+#[async_trait]
+impl Service for ConfiguredService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    fn validate_config(&self, context: &ServiceContext) -> Result<(), BoxedError> {
+        context.config_section::<PollerConfig>()?;
+        Ok(())
+    }
+
+    async fn start(&mut self, context: ServiceContext) -> Result<(), BoxedError> {
+        let config = context.config_section::<PollerConfig>()?;
+        self.journal
+            .lock()
+            .await
+            .push(format!("polling every {}s", config.interval));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+}
+
+// Loses its sessions when stopped, unless they are carried over by a snapshot
+pub struct StatefulService {
+    info: ServiceInfo,
+    pub sessions: Vec<String>,
+}
+
+impl StatefulService {
+    pub fn new(id: &str) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Optional),
+            sessions: Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for StatefulService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        self.sessions.clear();
+        Ok(())
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, BoxedError> {
+        Ok(Some(serde_json::to_value(&self.sessions)?))
+    }
+
+    async fn restore(&mut self, snapshot: Value) -> Result<(), BoxedError> {
+        self.sessions = serde_json::from_value(snapshot)?;
+        Ok(())
+    }
+}
+
+// A capability that services can be looked up by
+pub trait SessionHolder: Send {
+    fn sessions(&self) -> &[String];
+}
+
+impl SessionHolder for StatefulService {
+    fn sessions(&self) -> &[String] {
+        &self.sessions
+    }
+}
+
+// Takes drain_time to finish its in-flight work once asked to drain
+pub struct DrainingService {
+    info: ServiceInfo,
+    journal: Journal,
+    drain_time: Duration,
+}
+
+impl DrainingService {
+    pub fn new(id: &str, drain_time: Duration, journal: Journal) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Optional),
+            journal,
+            drain_time,
+        }
+    }
+}
+
+#[async_trait]
+impl Service for DrainingService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn drain(&mut self) -> Result<(), BoxedError> {
+        self.journal
+            .lock()
+            .await
+            .push(format!("draining {}", self.info.id));
+        sleep(self.drain_time).await;
+        self.journal
+            .lock()
+            .await
+            .push(format!("drained {}", self.info.id));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        self.journal
+            .lock()
+            .await
+            .push(format!("stop {}", self.info.id));
+        Ok(())
+    }
+}
+
+// Its background task errors when it is cancelled
+pub struct UncleanTaskService {
+    info: ServiceInfo,
+}
+
+impl UncleanTaskService {
+    pub fn new(id: &str) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Optional),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for UncleanTaskService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+
+    fn task<'a>(
+        &self,
+        cancellation_token: CancellationToken,
+    ) -> Option<LifetimedPinnedBoxedFutureResult<'a, ()>> {
+        Some(Box::pin(async move {
+            cancellation_token.cancelled().await;
+            Err("left work behind".into())
+        }))
+    }
+}
+
+// Fails to start the given number of times before it comes up
+pub struct FlakyService {
+    info: ServiceInfo,
+    failures_left: u32,
+}
+
+impl FlakyService {
+    pub fn new(id: &str, failures: u32, startup_retries: Backoff) -> Self {
+        Self {
+            info: ServiceInfo::new(service_id(id), id, Priority::Optional)
+                .with_startup_retries(startup_retries),
+            failures_left: failures,
+        }
+    }
+}
+
+#[async_trait]
+impl Service for FlakyService {
+    fn info(&self) -> &ServiceInfo {
+        &self.info
+    }
+
+    async fn start(&mut self, _context: ServiceContext) -> Result<(), BoxedError> {
+        if self.failures_left > 0 {
+            self.failures_left -= 1;
+            return Err("not yet".into());
+        }
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BoxedError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,1327 @@
+#[cfg(feature = "redis-bridge")]
+#[path = "common/fake_redis.rs"]
+mod fake_redis;
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use futures::StreamExt;
+    use lum::{
+        event::{
+            Backpressure, Callback, CancellableEvent, DispatchError, DispatchMode, Event, EventBus,
+            EventError, KeyedEvent, Lane, Propagation, Query, QueryError, RateLimit, Subscriber,
+            SubscriptionGroup, TopicEvent, TopicPattern, TryDispatchError,
+        },
+        service::BoxedError,
+    };
+    use tokio::{
+        sync::{Mutex, broadcast::error::RecvError},
+        time::{sleep, timeout},
+    };
+
+    #[cfg(feature = "redis-bridge")]
+    use crate::fake_redis::FakeRedis;
+
+    #[tokio::test]
+    async fn subscribe_async() {
+        let event = Event::<String>::new("greeting");
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = Arc::clone(&received);
+        let _subscription = event
+            .subscribe_async(
+                "greeter",
+                move |greeting: Arc<String>| {
+                    let sink = Arc::clone(&sink);
+                    async move {
+                        sleep(Duration::from_millis(1)).await;
+                        sink.lock().await.push(greeting.to_string());
+                        Ok(())
+                    }
+                },
+                true,
+                false,
+            )
+            .await;
+
+        event.dispatch(Arc::new("hello".to_string())).await.unwrap();
+
+        assert_eq!(*received.lock().await, vec!["hello"]);
+    }
+
+    #[tokio::test]
+    async fn dropping_subscription_handle_unsubscribes() {
+        let event = Event::<u32>::new("numbers");
+
+        let (subscription, _receiver) = event.subscribe_channel("numbers", 1, true, true).await;
+        assert_eq!(event.subscriber_count().await, 1);
+
+        drop(subscription);
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn unsubscribe_and_detach() {
+        let event = Event::<u32>::new("numbers");
+
+        let subscription = event
+            .subscribe_closure("first", |_| Ok(()), true, false)
+            .await;
+        assert!(subscription.unsubscribe().await);
+
+        let uuid = event
+            .subscribe_closure("second", |_| Ok(()), true, false)
+            .await
+            .detach();
+        assert_eq!(event.subscriber_count().await, 1);
+
+        assert!(event.unsubscribe(&uuid).await);
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn subscribe_once() {
+        let event = Event::<u32>::new("numbers");
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = Arc::clone(&received);
+        event
+            .subscribe_once(
+                "first",
+                move |number| {
+                    sink.try_lock().unwrap().push(*number);
+                    Ok(())
+                },
+                true,
+            )
+            .await
+            .detach();
+
+        event.dispatch(Arc::new(1)).await.unwrap();
+        event.dispatch(Arc::new(2)).await.unwrap();
+
+        assert_eq!(*received.lock().await, vec![1]);
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn next_resolves_with_next_value() {
+        let event = Arc::new(Event::<u32>::new("numbers"));
+
+        let next = tokio::spawn({
+            let event = Arc::clone(&event);
+            async move { event.next().await }
+        });
+        while event.subscriber_count().await == 0 {
+            sleep(Duration::from_millis(1)).await;
+        }
+
+        event.dispatch(Arc::new(7)).await.unwrap();
+
+        assert_eq!(next.await.unwrap().as_deref(), Some(&7));
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn next_skips_replayed_values() {
+        let event = Arc::new(Event::<u32>::new("numbers").with_replay(2));
+        event.dispatch(Arc::new(1)).await.unwrap();
+
+        timeout(Duration::from_millis(50), event.next())
+            .await
+            .expect_err("Replayed values must not resolve next");
+
+        let next = tokio::spawn({
+            let event = Arc::clone(&event);
+            async move { event.next().await }
+        });
+        while event.subscriber_count().await == 0 {
+            sleep(Duration::from_millis(1)).await;
+        }
+
+        event.dispatch(Arc::new(2)).await.unwrap();
+
+        assert_eq!(next.await.unwrap().as_deref(), Some(&2));
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn filtered_subscriptions() {
+        let event = Event::<u32>::new("numbers");
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = Arc::clone(&received);
+        let _subscription = event
+            .subscribe_filtered(
+                "even",
+                |number| number % 2 == 0,
+                move |number| {
+                    sink.try_lock().unwrap().push(*number);
+                    Ok(())
+                },
+                true,
+                false,
+            )
+            .await;
+        let (_channel_subscription, mut receiver) = event
+            .subscribe_channel_filtered("large", |number| *number > 2, 4, true, false)
+            .await;
+
+        for number in 1..=4 {
+            event.dispatch(Arc::new(number)).await.unwrap();
+        }
+
+        assert_eq!(*received.lock().await, vec![2, 4]);
+        assert_eq!(*receiver.recv().await.unwrap(), 3);
+        assert_eq!(*receiver.recv().await.unwrap(), 4);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn subscribers_are_dispatched_to_by_priority() {
+        let event = Event::<u32>::new("numbers");
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let subscriber = |name: &'static str, priority: i32| {
+            let order = Arc::clone(&order);
+            Subscriber::new(
+                name,
+                true,
+                false,
+                Callback::Closure(Box::new(move |_| {
+                    order.try_lock().unwrap().push(name);
+                    Ok(())
+                })),
+            )
+            .with_priority(priority)
+        };
+
+        let _handler = event.subscribe(subscriber("handler", 0)).await;
+        let late = event.subscribe(subscriber("late", 0)).await;
+        let _audit = event.subscribe(subscriber("audit", -10)).await;
+        assert!(event.set_priority(&late, -5).await);
+
+        event.dispatch(Arc::new(1)).await.unwrap();
+
+        assert_eq!(*order.lock().await, vec!["audit", "late", "handler"]);
+    }
+
+    #[tokio::test]
+    async fn concurrent_dispatch_is_not_stalled_by_full_channels() {
+        let event = Event::<u32>::new("numbers").with_dispatch_mode(DispatchMode::Concurrent);
+
+        // Never received from, so the second dispatch can't complete
+        let (_full_subscription, _full) = event.subscribe_channel("full", 1, true, false).await;
+        let (_subscription, mut receiver) =
+            event.subscribe_channel("numbers", 2, true, false).await;
+
+        event.dispatch(Arc::new(1)).await.unwrap();
+        let stalled = tokio::spawn({
+            let event = Arc::new(event);
+            async move { event.dispatch(Arc::new(2)).await }
+        });
+
+        assert_eq!(*receiver.recv().await.unwrap(), 1);
+        assert_eq!(*receiver.recv().await.unwrap(), 2);
+        assert!(!stalled.is_finished());
+        stalled.abort();
+    }
+
+    #[tokio::test]
+    async fn subscriber_timeout() {
+        let event = Event::<u32>::new("numbers");
+
+        let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+        let _subscription = event
+            .subscribe(
+                Subscriber::new("slow", false, true, Callback::Channel(sender))
+                    .with_timeout(Duration::from_millis(10)),
+            )
+            .await;
+
+        event.dispatch(Arc::new(1)).await.unwrap();
+        let err = event.dispatch(Arc::new(2)).await.unwrap_err();
+
+        assert!(matches!(
+            err.dispatch_errors()[..],
+            [DispatchError::Timeout(_)]
+        ));
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn dead_letters_are_kept_and_replayed() {
+        let event = Event::<u32>::new("numbers").with_dead_letter_queue(2);
+
+        let healthy = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let _subscription = event
+            .subscribe_closure(
+                "flaky",
+                {
+                    let healthy = Arc::clone(&healthy);
+                    move |_| {
+                        if healthy.load(std::sync::atomic::Ordering::SeqCst) {
+                            Ok(())
+                        } else {
+                            Err("unavailable".into())
+                        }
+                    }
+                },
+                false,
+                false,
+            )
+            .await;
+
+        for i in 1..=3 {
+            event.dispatch(Arc::new(i)).await.unwrap_err();
+        }
+
+        // Bounded, so the oldest letter was dropped
+        let letters = event.dead_letters().unwrap().letters();
+        let payloads: Vec<u32> = letters.iter().map(|letter| *letter.payload).collect();
+        assert_eq!(payloads, vec![2, 3]);
+        assert_eq!(letters[0].event_name, "numbers");
+        assert_eq!(letters[0].subscriber_name, "flaky");
+
+        healthy.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(event.replay_dead_letters().await, 2);
+        assert!(event.dead_letters().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn late_subscribers_get_replayed_values() {
+        let event = Event::<u32>::new("status").with_replay(2);
+
+        for i in 1..=3 {
+            event.dispatch(Arc::new(i)).await.unwrap();
+        }
+
+        let (_subscription, mut receiver) = event.subscribe_channel("late", 4, true, false).await;
+        event.dispatch(Arc::new(4)).await.unwrap();
+
+        for expected in 2..=4 {
+            assert_eq!(*receiver.recv().await.unwrap(), expected);
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn replay_never_blocks_the_subscription() {
+        let event = Event::<u32>::new("status").with_replay(3);
+        for i in 1..=3 {
+            event.dispatch(Arc::new(i)).await.unwrap();
+        }
+
+        // Only the newest value fits into the buffer
+        let (_subscription, mut receiver) = timeout(
+            Duration::from_secs(1),
+            event.subscribe_channel("late", 1, true, false),
+        )
+        .await
+        .expect("Subscribing must not wait for the replay to be received");
+        assert_eq!(*receiver.recv().await.unwrap(), 3);
+
+        event.dispatch(Arc::new(4)).await.unwrap();
+        assert_eq!(*receiver.recv().await.unwrap(), 4);
+
+        // Async closures get their replay in the background, in order
+        let (sender, mut replayed) = tokio::sync::mpsc::unbounded_channel();
+        let _slow = timeout(
+            Duration::from_secs(1),
+            event.subscribe_async(
+                "slow",
+                move |value: Arc<u32>| {
+                    let sender = sender.clone();
+                    async move {
+                        sleep(Duration::from_millis(20)).await;
+                        sender.send(*value)?;
+                        Ok(())
+                    }
+                },
+                true,
+                false,
+            ),
+        )
+        .await
+        .expect("Subscribing must not wait for async closures");
+
+        // Live dispatches wait for the pending replay instead of overtaking it
+        event.dispatch(Arc::new(5)).await.unwrap();
+        for expected in 2..=5 {
+            assert_eq!(replayed.recv().await.unwrap(), expected);
+        }
+        assert_eq!(event.subscriber_count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn broadcast_subscribers_lag_instead_of_blocking() {
+        let event = Event::<u32>::new("numbers");
+
+        let (_subscription, mut first) = event.subscribe_broadcast("numbers", 2, true, false).await;
+        let mut second = first.resubscribe();
+
+        for i in 1..=3 {
+            event.dispatch(Arc::new(i)).await.unwrap();
+        }
+
+        assert!(matches!(first.recv().await, Err(RecvError::Lagged(1))));
+        assert_eq!(*first.recv().await.unwrap(), 2);
+        assert_eq!(*first.recv().await.unwrap(), 3);
+
+        assert!(matches!(second.recv().await, Err(RecvError::Lagged(1))));
+        assert_eq!(*second.recv().await.unwrap(), 2);
+
+        drop(first);
+        drop(second);
+
+        // Without receivers, the subscriber is removed instead of failing the dispatch
+        event.dispatch(Arc::new(4)).await.unwrap();
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn latest_value_is_watchable() {
+        let event = Event::<u32>::new("status").with_latest_value();
+        assert!(event.latest().is_none());
+
+        event.dispatch(Arc::new(1)).await.unwrap();
+        event.dispatch(Arc::new(2)).await.unwrap();
+        assert_eq!(event.latest().as_deref(), Some(&2));
+
+        let mut watcher = event.watch().unwrap();
+        assert_eq!(watcher.borrow_and_update().as_deref(), Some(&2));
+
+        event.dispatch(Arc::new(3)).await.unwrap();
+        watcher.changed().await.unwrap();
+        assert_eq!(watcher.borrow().as_deref(), Some(&3));
+
+        let plain = Event::<u32>::new("numbers");
+        plain.dispatch(Arc::new(1)).await.unwrap();
+        assert!(plain.latest().is_none());
+        assert!(plain.watch().is_none());
+    }
+
+    #[tokio::test]
+    async fn event_bus_shares_events_by_type_and_name() {
+        let bus = EventBus::new();
+
+        let (_subscription, mut receiver) = bus
+            .event::<u32>()
+            .subscribe_channel("numbers", 1, true, false)
+            .await;
+        bus.event::<u32>().dispatch(Arc::new(1)).await.unwrap();
+        assert_eq!(*receiver.recv().await.unwrap(), 1);
+
+        assert!(Arc::ptr_eq(&bus.event::<u32>(), &bus.event::<u32>()));
+        assert!(!Arc::ptr_eq(
+            &bus.event::<u32>(),
+            &bus.named_event::<u32, _>("other")
+        ));
+        assert_eq!(bus.named_event::<u32, _>("other").name, "other");
+        assert!(bus.contains::<u32>(Some("other")));
+        assert!(!bus.contains::<u64>(None));
+
+        let replaying = Arc::new(Event::<String>::new("replaying").with_replay(1));
+        assert!(bus.insert(None, Arc::clone(&replaying)).is_none());
+        assert!(Arc::ptr_eq(&bus.event::<String>(), &replaying));
+        assert_eq!(bus.len(), 3);
+    }
+
+    #[test]
+    fn topic_patterns() {
+        let pattern = TopicPattern::new("discord.*");
+        assert!(pattern.matches("discord.message"));
+        assert!(pattern.matches("discord.message.created"));
+        assert!(!pattern.matches("discord"));
+        assert!(!pattern.matches("twitch.message"));
+
+        let pattern = TopicPattern::new("discord.*.created");
+        assert!(pattern.matches("discord.message.created"));
+        assert!(!pattern.matches("discord.message.deleted"));
+        assert!(!pattern.matches("discord.message.created.late"));
+
+        assert!(TopicPattern::new("discord.ready").matches("discord.ready"));
+        assert!(!TopicPattern::new("discord.ready").matches("discord.ready.now"));
+    }
+
+    #[tokio::test]
+    async fn topic_subscribers_only_get_matching_topics() {
+        let topics = TopicEvent::<u32>::new("topics");
+
+        let (_discord_subscription, mut discord) = topics
+            .subscribe_channel("discord.*", "discord", 4, true, false)
+            .await;
+        let (_created_subscription, mut created) = topics
+            .subscribe_channel("*.message.created", "created", 4, true, false)
+            .await;
+
+        topics
+            .publish("discord.message.created", Arc::new(1))
+            .await
+            .unwrap();
+        topics.publish("discord.ready", Arc::new(2)).await.unwrap();
+        topics
+            .publish("twitch.message.created", Arc::new(3))
+            .await
+            .unwrap();
+
+        let message = discord.recv().await.unwrap();
+        assert_eq!(
+            (message.topic.as_str(), *message.payload),
+            ("discord.message.created", 1)
+        );
+        assert_eq!(*discord.recv().await.unwrap().payload, 2);
+        assert!(discord.try_recv().is_err());
+
+        assert_eq!(*created.recv().await.unwrap().payload, 1);
+        assert_eq!(*created.recv().await.unwrap().payload, 3);
+        assert!(created.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn event_metrics() {
+        let bus = EventBus::new();
+        let event = bus.event::<u32>();
+        assert_eq!(event.metrics().average_dispatch_latency, None);
+
+        let (_subscription, _receiver) = event.subscribe_channel("numbers", 4, true, false).await;
+        let failing = event
+            .subscribe_closure("failing", |_| Err("unavailable".into()), false, false)
+            .await;
+
+        event.dispatch(Arc::new(1)).await.unwrap_err();
+        failing.unsubscribe().await;
+        event.dispatch(Arc::new(2)).await.unwrap();
+
+        let metrics = event.metrics();
+        assert_eq!(metrics.dispatches, 2);
+        assert_eq!(metrics.errors, 1);
+        assert_eq!(metrics.subscribers, 1);
+        assert_eq!(metrics.peak_subscribers, 2);
+        assert!(metrics.average_dispatch_latency.is_some());
+
+        bus.named_event::<u32, _>("idle");
+        let names = bus
+            .metrics()
+            .into_iter()
+            .map(|metrics| metrics.event_name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["idle".to_string(), std::any::type_name::<u32>().to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn try_dispatch_reports_blocking_subscribers() {
+        let event = Event::<u32>::new("numbers");
+
+        let (full_subscription, _full) = event.subscribe_channel("full", 1, true, false).await;
+        let (_subscription, mut receiver) =
+            event.subscribe_channel("numbers", 4, true, false).await;
+        let async_subscription = event
+            .subscribe_async_closure("async", |_| Box::pin(async { Ok(()) }), true, false)
+            .await;
+
+        let Err(TryDispatchError::Incomplete {
+            would_block,
+            errors,
+        }) = event.try_dispatch(Arc::new(1))
+        else {
+            panic!("Async closures can't be dispatched to without waiting");
+        };
+        assert_eq!(would_block, vec![async_subscription.uuid()]);
+        assert!(errors.is_empty());
+
+        let Err(TryDispatchError::Incomplete { would_block, .. }) = event.try_dispatch(Arc::new(2))
+        else {
+            panic!("The full channel should have blocked");
+        };
+        assert_eq!(
+            would_block,
+            vec![full_subscription.uuid(), async_subscription.uuid()]
+        );
+
+        assert_eq!(*receiver.recv().await.unwrap(), 1);
+        assert_eq!(*receiver.recv().await.unwrap(), 2);
+
+        async_subscription.unsubscribe().await;
+        assert!(event.try_dispatch(Arc::new(3)).is_err());
+
+        // A sequential dispatch waiting on the full channel keeps the subscribers locked
+        let event = Arc::new(event);
+        let stalled = tokio::spawn({
+            let event = Arc::clone(&event);
+            async move { event.dispatch(Arc::new(4)).await }
+        });
+        sleep(Duration::from_millis(10)).await;
+        assert!(matches!(
+            event.try_dispatch(Arc::new(5)),
+            Err(TryDispatchError::Busy)
+        ));
+
+        stalled.abort();
+        let _ = stalled.await;
+        full_subscription.unsubscribe().await;
+        assert!(event.try_dispatch(Arc::new(6)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn backpressure_policies() {
+        let event = Event::<u32>::new("numbers");
+
+        let (_newest_subscription, mut newest) = event
+            .subscribe_channel_with_backpressure("newest", 2, Backpressure::DropNewest, true, false)
+            .await;
+        let (_oldest_subscription, mut oldest) = event
+            .subscribe_channel_with_backpressure("oldest", 2, Backpressure::DropOldest, true, false)
+            .await;
+
+        for i in 1..=5 {
+            event.dispatch(Arc::new(i)).await.unwrap();
+        }
+
+        assert_eq!(*newest.recv().await.unwrap(), 1);
+        assert_eq!(*newest.recv().await.unwrap(), 2);
+        assert!(newest.try_recv().is_err());
+
+        assert_eq!(*oldest.recv().await.unwrap(), 4);
+        assert_eq!(*oldest.recv().await.unwrap(), 5);
+
+        let event = Event::<u32>::new("numbers");
+        let (_subscription, _receiver) = event
+            .subscribe_channel_with_backpressure("error", 1, Backpressure::Error, false, false)
+            .await;
+
+        event.dispatch(Arc::new(1)).await.unwrap();
+        let err = event.dispatch(Arc::new(2)).await.unwrap_err();
+        assert!(matches!(
+            err.dispatch_errors()[..],
+            [DispatchError::ChannelFull(_)]
+        ));
+    }
+
+    #[tokio::test]
+    async fn event_errors_name_the_failed_subscribers() {
+        let event = Event::<u32>::new("numbers");
+
+        let failing = event
+            .subscribe_closure("failing", |_| Err("unavailable".into()), false, false)
+            .await;
+
+        let err = event.dispatch(Arc::new(1)).await.unwrap_err();
+        let EventError::Subscriber {
+            event_name,
+            subscriber_name,
+            subscriber_uuid,
+            ..
+        } = &err
+        else {
+            panic!("A single failed subscriber should be reported on its own");
+        };
+        assert_eq!(event_name, "numbers");
+        assert_eq!(subscriber_name, "failing");
+        assert_eq!(*subscriber_uuid, failing.uuid());
+        assert!(std::error::Error::source(&err).is_some());
+
+        let _also_failing = event
+            .subscribe_closure("also_failing", |_| Err("unavailable".into()), false, false)
+            .await;
+
+        // Usable as a BoxedError
+        let err: BoxedError = event.dispatch(Arc::new(2)).await.unwrap_err().into();
+        assert_eq!(
+            err.to_string(),
+            "Event \"numbers\" failed to dispatch data to subscribers failing, also_failing"
+        );
+    }
+
+    #[tokio::test]
+    async fn event_streams() {
+        let event = Event::<u32>::new("numbers");
+
+        let stream = event.stream("numbers", 4).await;
+        for i in 1..=4 {
+            event.dispatch(Arc::new(i)).await.unwrap();
+        }
+
+        let even = stream
+            .filter_map(|value| async move { (*value % 2 == 0).then_some(*value) })
+            .take(2)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(even, vec![2, 4]);
+
+        // collect consumed the stream, and dropping it unsubscribed
+        assert_eq!(event.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn middleware_can_rewrite_and_stop_dispatches() {
+        let audited = Arc::new(Mutex::new(Vec::new()));
+        let event = Event::<String>::new("messages")
+            .with_middleware({
+                let audited = Arc::clone(&audited);
+                move |name, data| {
+                    audited
+                        .try_lock()
+                        .unwrap()
+                        .push(format!("{}: {}", name, data));
+                    Some(data)
+                }
+            })
+            .with_middleware(|_, data| (!data.is_empty()).then_some(data))
+            .with_middleware(|_, data| Some(Arc::new(data.replace("secret", "******"))));
+
+        let (_subscription, mut receiver) =
+            event.subscribe_channel("messages", 4, true, false).await;
+
+        event
+            .dispatch(Arc::new("my secret".to_string()))
+            .await
+            .unwrap();
+        event.dispatch(Arc::new(String::new())).await.unwrap();
+
+        assert_eq!(*receiver.recv().await.unwrap(), "my ******");
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(audited.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn bus_middleware_applies_to_events_of_its_type() {
+        let bus = EventBus::new();
+        let existing = bus.event::<u32>();
+        bus.add_middleware::<u32>(|_, data| Some(Arc::new(*data * 10)));
+        let created = bus.named_event::<u32, _>("created");
+        let untouched = bus.event::<u64>();
+
+        let (_existing_subscription, mut existing_receiver) =
+            existing.subscribe_channel("existing", 1, true, false).await;
+        let (_created_subscription, mut created_receiver) =
+            created.subscribe_channel("created", 1, true, false).await;
+        let (_untouched_subscription, mut untouched_receiver) = untouched
+            .subscribe_channel("untouched", 1, true, false)
+            .await;
+
+        existing.dispatch(Arc::new(1)).await.unwrap();
+        created.dispatch(Arc::new(2)).await.unwrap();
+        untouched.dispatch(Arc::new(3)).await.unwrap();
+
+        assert_eq!(*existing_receiver.recv().await.unwrap(), 10);
+        assert_eq!(*created_receiver.recv().await.unwrap(), 20);
+        assert_eq!(*untouched_receiver.recv().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn cancellable_events_stop_at_the_first_consumer() {
+        let event = CancellableEvent::<String>::new("commands");
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let record = |name: &'static str, consumes: &'static str| {
+            let seen = Arc::clone(&seen);
+            move |command: Arc<String>| {
+                seen.try_lock().unwrap().push(name);
+                if *command == consumes {
+                    Ok(Propagation::Stop)
+                } else {
+                    Ok(Propagation::Continue)
+                }
+            }
+        };
+
+        event
+            .subscribe_closure("fallback", 10, record("fallback", ""))
+            .await;
+        let ping = event
+            .subscribe_closure("ping", 0, record("ping", "ping"))
+            .await;
+        event
+            .subscribe_closure("help", 0, record("help", "help"))
+            .await;
+
+        assert_eq!(
+            event.dispatch(Arc::new("ping".to_string())).await.unwrap(),
+            Some(ping)
+        );
+        assert_eq!(*seen.lock().await, vec!["ping"]);
+
+        seen.lock().await.clear();
+        assert_eq!(
+            event
+                .dispatch(Arc::new("unknown".to_string()))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(*seen.lock().await, vec!["ping", "help", "fallback"]);
+
+        event
+            .subscribe_async_closure("broken", -1, |_| Box::pin(async { Err("broken".into()) }))
+            .await;
+        seen.lock().await.clear();
+        let err = event
+            .dispatch(Arc::new("ping".to_string()))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, EventError::Subscriber { ref subscriber_name, .. } if subscriber_name == "broken")
+        );
+        assert!(seen.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn queries_are_answered_by_one_responder() {
+        let query = Query::<u32, String>::new("describe", Duration::from_millis(50));
+        assert!(matches!(
+            query.request(1).await,
+            Err(QueryError::NoResponder(_))
+        ));
+
+        let responder = query
+            .respond_closure(|number| Ok(format!("number {}", number)))
+            .await
+            .unwrap();
+        assert!(matches!(
+            query.respond_closure(|_| Ok(String::new())).await,
+            Err(QueryError::ResponderTaken(_))
+        ));
+        assert_eq!(query.request(1).await.unwrap(), "number 1");
+
+        assert!(query.remove_responder(&responder).await);
+        query
+            .respond_async_closure(|_| {
+                Box::pin(async {
+                    sleep(Duration::from_secs(1)).await;
+                    Ok(String::new())
+                })
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            query.request(2).await,
+            Err(QueryError::Timeout { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn keyed_events_only_reach_the_key_subscribers() {
+        let event = KeyedEvent::<u64, String>::new("messages");
+
+        let (_first_subscription, mut first) = event
+            .partition(&1)
+            .await
+            .subscribe_channel("first", 4, true, false)
+            .await;
+        let (second_subscription, mut second) = event
+            .partition(&2)
+            .await
+            .subscribe_channel("second", 4, true, false)
+            .await;
+
+        event
+            .dispatch(&1, Arc::new("hello".to_string()))
+            .await
+            .unwrap();
+        event
+            .dispatch(&2, Arc::new("world".to_string()))
+            .await
+            .unwrap();
+        event
+            .dispatch(&3, Arc::new("nobody".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(*first.recv().await.unwrap(), "hello");
+        assert!(first.try_recv().is_err());
+        assert_eq!(*second.recv().await.unwrap(), "world");
+        assert_eq!(event.partition(&1).await.name, "messages[1]");
+        assert_eq!(event.partition_count().await, 2);
+
+        second_subscription.unsubscribe().await;
+        assert_eq!(event.prune().await, 1);
+        assert_eq!(event.keys().await, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn batched_dispatch() {
+        let event = Event::<u32>::new("numbers");
+
+        let (_subscription, mut receiver) =
+            event.subscribe_channel("numbers", 8, true, false).await;
+        let (_even_subscription, mut even) = event
+            .subscribe_channel_filtered("even", |number| number % 2 == 0, 8, true, false)
+            .await;
+        let once = Arc::new(Mutex::new(Vec::new()));
+        let _once_subscription = event
+            .subscribe_once(
+                "once",
+                {
+                    let once = Arc::clone(&once);
+                    move |number| {
+                        once.try_lock().unwrap().push(*number);
+                        Ok(())
+                    }
+                },
+                true,
+            )
+            .await;
+
+        event.dispatch_batch(vec![1, 2, 3, 4]).await.unwrap();
+
+        for expected in 1..=4 {
+            assert_eq!(*receiver.recv().await.unwrap(), expected);
+        }
+        assert_eq!(*even.recv().await.unwrap(), 2);
+        assert_eq!(*even.recv().await.unwrap(), 4);
+        assert!(even.try_recv().is_err());
+        assert_eq!(*once.lock().await, vec![1]);
+        assert_eq!(event.metrics().dispatches, 4);
+    }
+
+    #[tokio::test]
+    async fn sequence_numbers_reveal_dropped_values() {
+        let event = Event::<u32>::new("numbers").with_replay(1);
+
+        let (_subscription, mut receiver) = event
+            .subscribe_sequenced("numbers", 2, Backpressure::DropNewest, true, false)
+            .await;
+
+        for i in 1..=3 {
+            event.dispatch(Arc::new(i)).await.unwrap();
+        }
+        event.dispatch_batch(vec![4, 5]).await.unwrap();
+        assert_eq!(event.sequence(), 5);
+
+        let first = receiver.recv().await.unwrap();
+        assert_eq!((first.sequence, *first.data), (1, 1));
+        assert_eq!(receiver.recv().await.unwrap().sequence, 2);
+
+        // 3 was dropped while the buffer was full, which the gap shows
+        event.dispatch(Arc::new(6)).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap().sequence, 6);
+
+        // Replayed values keep their original sequence number
+        let (_late_subscription, mut late) = event
+            .subscribe_sequenced("late", 1, Backpressure::Block, true, false)
+            .await;
+        assert_eq!(late.recv().await.unwrap().sequence, 6);
+    }
+
+    #[tokio::test]
+    async fn closed_channel_subscribers_are_pruned() {
+        let event = Event::<u32>::new("numbers");
+
+        let (_subscription, receiver) = event.subscribe_channel("dropped", 1, true, false).await;
+        let (_sequenced_subscription, sequenced) = event
+            .subscribe_sequenced("sequenced", 1, Backpressure::Block, true, false)
+            .await;
+        let (_open_subscription, mut open) = event.subscribe_channel("open", 1, true, false).await;
+
+        drop(receiver);
+        event.dispatch(Arc::new(1)).await.unwrap();
+        assert_eq!(event.subscriber_count().await, 2);
+        assert_eq!(*open.recv().await.unwrap(), 1);
+
+        drop(sequenced);
+        assert_eq!(event.prune_closed().await, 1);
+        assert_eq!(event.subscriber_count().await, 1);
+
+        // Relayed subscribers are closed as soon as their receiver is gone, not only after a failed forward
+        let (_oldest_subscription, oldest) = event
+            .subscribe_channel_with_backpressure("oldest", 1, Backpressure::DropOldest, true, false)
+            .await;
+        assert_eq!(event.subscriber_count().await, 2);
+        drop(oldest);
+        assert_eq!(event.prune_closed().await, 1);
+        assert_eq!(event.subscriber_count().await, 1);
+    }
+
+    #[cfg(feature = "event-bridge")]
+    #[tokio::test]
+    async fn events_are_bridged_to_other_processes() {
+        use lum::event::{BridgeAddress, EventBridgeClient, EventBridgeServer};
+
+        let event = Event::<String>::new("messages");
+        let server = EventBridgeServer::bind(BridgeAddress::Tcp("127.0.0.1:0".parse().unwrap()), 8)
+            .await
+            .unwrap();
+        server.bridge(&event).await;
+
+        let local = Arc::new(Event::<String>::new("messages"));
+        let (_subscription, mut receiver) = local.subscribe_channel("local", 1, true, false).await;
+        let client = EventBridgeClient::new().with_event(Arc::clone(&local));
+        let address = BridgeAddress::Tcp(server.local_addr().unwrap());
+        let client_task = tokio::spawn(async move { client.run(address).await });
+
+        while server.connection_count() == 0 {
+            sleep(Duration::from_millis(1)).await;
+        }
+
+        event.dispatch(Arc::new("hello".to_string())).await.unwrap();
+        assert_eq!(*receiver.recv().await.unwrap(), "hello");
+
+        client_task.abort();
+    }
+
+    #[cfg(feature = "redis-bridge")]
+    #[tokio::test]
+    async fn events_are_mirrored_between_instances_through_redis() {
+        use lum::service::{RedisEventBridge, ServiceManager};
+
+        let redis = FakeRedis::start().await;
+
+        let mut instances = Vec::new();
+        let mut receivers = Vec::new();
+        for _ in 0..2 {
+            // Middleware that replaces the data must not make injected values look local
+            let event = Arc::new(
+                Event::<String>::new("cache_invalidated")
+                    .with_middleware(|_, data| Some(Arc::new(data.trim().to_string()))),
+            );
+            let (subscription, receiver) = event.subscribe_channel("local", 8, true, false).await;
+            let bridge = RedisEventBridge::new(redis.url.clone())
+                .with_outbound(Arc::clone(&event))
+                .with_inbound(Arc::clone(&event));
+            let manager = ServiceManager::builder()
+                .with_service(Arc::new(Mutex::new(bridge)))
+                .await
+                .build()
+                .await
+                .unwrap();
+            manager.start_services().await;
+
+            instances.push((event, subscription, manager));
+            receivers.push(receiver);
+        }
+
+        instances[0]
+            .0
+            .dispatch(Arc::new("user:42".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(*receivers[0].recv().await.unwrap(), "user:42");
+        assert_eq!(*receivers[1].recv().await.unwrap(), "user:42");
+
+        // Neither the publisher's own echo nor the injected copy may come around again
+        sleep(Duration::from_millis(50)).await;
+        assert!(receivers[0].try_recv().is_err());
+        assert!(receivers[1].try_recv().is_err());
+
+        for (_, _, manager) in &instances {
+            manager.stop_services().await;
+        }
+    }
+
+    #[cfg(feature = "redis-bridge")]
+    #[tokio::test]
+    async fn redis_bridge_reconnects_after_losing_its_connections() {
+        use lum::service::{Backoff, RedisEventBridge, ServiceManager};
+
+        let mut redis = FakeRedis::start().await;
+
+        let event = Arc::new(Event::<String>::new("cache_invalidated"));
+        let (_subscription, mut receiver) = event.subscribe_channel("local", 8, true, false).await;
+        let bridge = RedisEventBridge::new(redis.url.clone())
+            .with_reconnect_backoff(Backoff::new(
+                Duration::from_millis(10),
+                Duration::from_millis(50),
+                None,
+            ))
+            .with_outbound(Arc::clone(&event))
+            .with_inbound(Arc::clone(&event));
+        let channel = bridge.channel(&event.name);
+        let manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(bridge)))
+            .await
+            .build()
+            .await
+            .unwrap();
+        manager.start_services().await;
+        assert_eq!(redis.subscriber_count(&channel).await, 1);
+
+        redis.disconnect_all().await;
+
+        // Resubscribed on a new connection
+        timeout(Duration::from_secs(2), async {
+            while redis.subscriber_count(&channel).await == 0 {
+                sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let message = serde_json::json!({
+            "origin": "00000000-0000-0000-0000-000000000000",
+            "payload": "from another instance"
+        })
+        .to_string();
+        redis.publish(&channel, &message).await;
+        let injected = timeout(Duration::from_secs(2), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*injected, "from another instance");
+
+        // Republished on a new connection after the first one was closed
+        event
+            .dispatch(Arc::new("user:42".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(*receiver.recv().await.unwrap(), "user:42");
+        let (published_channel, message) = timeout(Duration::from_secs(2), redis.next_published())
+            .await
+            .unwrap();
+        assert_eq!(published_channel, channel);
+        assert!(message.contains("user:42"));
+
+        manager.stop_services().await;
+    }
+
+    #[tokio::test]
+    async fn retained_value_is_delivered_to_new_subscribers() {
+        let event = Event::<String>::new("status").with_retained();
+        event
+            .dispatch(Arc::new("starting".to_string()))
+            .await
+            .unwrap();
+        event.dispatch(Arc::new("ready".to_string())).await.unwrap();
+        assert_eq!(*event.retained().unwrap(), "ready");
+
+        let (_subscription, mut receiver) = event.subscribe_channel("late", 4, true, false).await;
+        assert_eq!(*receiver.recv().await.unwrap(), "ready");
+        assert!(receiver.try_recv().is_err());
+
+        event.clear_retained();
+        assert!(event.retained().is_none());
+        let (_subscription, mut receiver) = event.subscribe_channel("later", 4, true, false).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn retained_value_never_blocks_a_full_subscriber() {
+        let event = Event::<String>::new("status").with_retained();
+        event.dispatch(Arc::new("ready".to_string())).await.unwrap();
+
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        sender.try_send(Arc::new("queued".to_string())).unwrap();
+        let subscriber = Subscriber::new("full", true, false, Callback::Channel(sender));
+        let _subscription = timeout(Duration::from_secs(1), event.subscribe(subscriber))
+            .await
+            .expect("A full subscriber must not block the subscription");
+
+        // There was no room for the retained value, later dispatches still arrive
+        assert_eq!(*receiver.recv().await.unwrap(), "queued");
+        let dispatch = event.dispatch(Arc::new("stopping".to_string()));
+        timeout(Duration::from_secs(1), dispatch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*receiver.recv().await.unwrap(), "stopping");
+
+        let (sender, mut retained) = tokio::sync::mpsc::unbounded_channel();
+        let _slow = timeout(
+            Duration::from_secs(1),
+            event.subscribe_async(
+                "slow",
+                move |status: Arc<String>| {
+                    let sender = sender.clone();
+                    async move {
+                        sleep(Duration::from_millis(50)).await;
+                        sender.send(status.to_string())?;
+                        Ok(())
+                    }
+                },
+                true,
+                false,
+            ),
+        )
+        .await
+        .expect("A slow async closure must not block the subscription");
+        assert_eq!(retained.recv().await.unwrap(), "stopping");
+    }
+
+    #[tokio::test]
+    async fn retained_value_never_overtakes_a_new_dispatch() {
+        let event = Event::<String>::new("status").with_retained();
+        event
+            .dispatch(Arc::new("starting".to_string()))
+            .await
+            .unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let _subscription = event
+            .subscribe_async(
+                "late",
+                move |status: Arc<String>| {
+                    let sink = Arc::clone(&sink);
+                    async move {
+                        sleep(Duration::from_millis(20)).await;
+                        sink.lock().await.push(status.to_string());
+                        Ok(())
+                    }
+                },
+                true,
+                false,
+            )
+            .await;
+
+        // Dispatched while the retained value is still being replayed
+        event.dispatch(Arc::new("ready".to_string())).await.unwrap();
+
+        assert_eq!(*seen.lock().await, vec!["starting", "ready"]);
+    }
+
+    #[tokio::test]
+    async fn describe_lists_subscribers_by_name() {
+        let event = Event::<String>::new("chat");
+        let (_audit, _receiver) = event.subscribe_channel("audit_log", 1, true, false).await;
+        let moderation = event
+            .subscribe_closure("moderation", |_| Ok(()), true, false)
+            .await;
+        event.set_priority(&moderation.uuid(), -1).await;
+
+        let description = event.describe().await;
+        let lines: Vec<&str> = description.lines().collect();
+        assert!(lines[0].starts_with("Event \"chat\""));
+        assert!(lines[0].ends_with("with 2 subscriber(s)"));
+        assert!(lines[1].contains("\"moderation\"") && lines[1].contains("closure, priority -1"));
+        assert!(lines[2].contains("\"audit_log\"") && lines[2].contains("channel, priority 0"));
+    }
+
+    #[tokio::test]
+    async fn event_bus_introspection_lists_events_and_listeners() {
+        let bus = EventBus::new();
+        let _ready = bus.named_event::<(), _>("ready");
+        let messages = bus.named_event::<String, _>("messages");
+        let _logger = messages
+            .subscribe_closure("logger", |_| Ok(()), true, false)
+            .await;
+        let _relay = messages
+            .subscribe_closure("relay", |_| Ok(()), true, false)
+            .await;
+
+        let infos = bus.introspect().await;
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].name, "messages");
+        assert_eq!(infos[0].payload_type, "alloc::string::String");
+        assert_eq!(infos[0].subscribers, vec!["logger", "relay"]);
+        assert_eq!(infos[1].name, "ready");
+        assert!(infos[1].subscribers.is_empty());
+        assert_eq!(
+            infos[0].to_string(),
+            "messages (alloc::string::String): 2 subscriber(s): logger, relay"
+        );
+    }
+
+    #[tokio::test]
+    async fn clear_removes_all_subscribers() {
+        let event = Event::<u32>::new("ticks");
+        let _first = event
+            .subscribe_closure("first", |_| Ok(()), true, false)
+            .await;
+        let _second = event
+            .subscribe_closure("second", |_| Ok(()), true, false)
+            .await;
+
+        assert_eq!(event.clear().await, 2);
+        assert_eq!(event.subscriber_count().await, 0);
+        assert_eq!(event.metrics().subscribers, 0);
+    }
+
+    #[tokio::test]
+    async fn subscription_group_unsubscribes_a_component_at_once() {
+        let numbers = Event::<u32>::new("numbers");
+        let words = Event::<String>::new("words");
+        let _other = numbers
+            .subscribe_closure("other", |_| Ok(()), true, false)
+            .await;
+
+        let mut group = SubscriptionGroup::new()
+            .with(
+                numbers
+                    .subscribe_closure("component", |_| Ok(()), true, false)
+                    .await,
+            )
+            .with(
+                words
+                    .subscribe_closure("component", |_| Ok(()), true, false)
+                    .await,
+            );
+        assert_eq!(group.len(), 2);
+
+        assert_eq!(group.unsubscribe_all().await, 2);
+        assert!(group.is_empty());
+        assert_eq!(numbers.subscriber_names().await, vec!["other"]);
+        assert_eq!(words.subscriber_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_subscribers_thin_out_bursts() {
+        let event = Event::<u32>::new("status");
+        let (_throttled, mut throttled) = event
+            .subscribe_rate_limited(
+                "throttled",
+                RateLimit::Throttle(Duration::from_secs(60)),
+                true,
+                false,
+            )
+            .await;
+        let (_debounced, mut debounced) = event
+            .subscribe_rate_limited(
+                "debounced",
+                RateLimit::Debounce(Duration::from_millis(20)),
+                true,
+                false,
+            )
+            .await;
+        let (_sampled, mut sampled) = event
+            .subscribe_rate_limited(
+                "sampled",
+                RateLimit::Sample(Duration::from_millis(20)),
+                true,
+                false,
+            )
+            .await;
+
+        event.dispatch(Arc::new(1)).await.unwrap();
+        assert_eq!(*throttled.recv().await.unwrap(), 1);
+        for value in 2..=5 {
+            event.dispatch(Arc::new(value)).await.unwrap();
+        }
+
+        assert_eq!(*debounced.recv().await.unwrap(), 5);
+        assert_eq!(*sampled.recv().await.unwrap(), 5);
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(throttled.try_recv().is_err());
+        assert!(debounced.try_recv().is_err());
+        assert!(sampled.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn urgent_dispatches_overtake_queued_values() {
+        let event = Event::<String>::new("notifications");
+        let (_subscription, mut receiver) = event
+            .subscribe_priority_channel("notifier", 8, true, false)
+            .await;
+
+        event.dispatch(Arc::new("first".to_string())).await.unwrap();
+        event
+            .dispatch(Arc::new("second".to_string()))
+            .await
+            .unwrap();
+        event
+            .dispatch_urgent(Arc::new("shutting down".to_string()))
+            .await
+            .unwrap();
+
+        let (lane, data) = receiver.recv().await.unwrap();
+        assert_eq!((lane, data.as_str()), (Lane::High, "shutting down"));
+        let (lane, data) = receiver.recv().await.unwrap();
+        assert_eq!((lane, data.as_str()), (Lane::Normal, "first"));
+        let (lane, data) = receiver.try_recv().unwrap();
+        assert_eq!((lane, data.as_str()), (Lane::Normal, "second"));
+    }
+}
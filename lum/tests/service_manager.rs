@@ -0,0 +1,2163 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, AtomicU32, Ordering},
+        },
+        time::{Duration, Instant},
+    };
+
+    use lum::{
+        bot::{Bot, ExitReason},
+        config::{FileConfig, TimeoutConfig},
+        service::{
+            ActorError, Backoff, BuildError, CrashLoopDetection, EscalationPolicy, HealthCheck,
+            InvalidServiceIdError, OverallStatus, PauseError, PlanError, Priority, Readiness,
+            RegistrationError, RemovalError, ReplaceError, RestartPolicy, ResumeError, Service,
+            ServiceActor, ServiceHandle, ServiceId, ServiceInfo, ServiceManager, ShutdownError,
+            ShutdownOrder, StartupError, StartupMode, Status, SupervisionGroup,
+            SupervisionStrategy, TaskStatus,
+        },
+    };
+    use tokio::{
+        sync::Mutex,
+        time::{sleep, timeout},
+    };
+
+    use crate::common::{
+        CancellableService, ConfiguredService, ContextProbeService, CrashingService,
+        DrainingService, FlakyService, HangingService, HeartbeatService, HookedService,
+        MultiTaskService, PanickingService, ProbedService, SessionHolder, StatefulService,
+        TestService, UncleanTaskService, journal, service_id, test_service,
+    };
+
+    fn fast_backoff() -> Backoff {
+        Backoff::new(
+            Duration::from_millis(10),
+            Duration::from_millis(50),
+            Some(3),
+        )
+    }
+
+    #[tokio::test]
+    async fn stop_services_in_reverse_startup_order() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(test_service("consumer", Priority::Optional, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        let results = service_manager.stop_services().await;
+        assert!(results.iter().all(|result| result.is_ok()));
+
+        assert_eq!(
+            *journal.lock().await,
+            vec![
+                "start database",
+                "start consumer",
+                "stop consumer",
+                "stop database"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stop_services_unordered() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_shutdown_order(ShutdownOrder::Unordered)
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(test_service("consumer", Priority::Optional, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        service_manager.stop_services().await;
+
+        assert_eq!(
+            *journal.lock().await,
+            vec![
+                "start database",
+                "start consumer",
+                "stop database",
+                "stop consumer"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stop_services_concurrently_respects_dependencies() {
+        let journal = journal();
+        let consumer_info =
+            ServiceInfo::new(service_id("consumer"), "consumer", Priority::Optional)
+                .with_dependency(service_id("database"));
+        let service_manager = ServiceManager::builder()
+            .with_shutdown_order(ShutdownOrder::Concurrent)
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                consumer_info,
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(test_service("standalone", Priority::Optional, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        let results = service_manager.stop_services().await;
+        assert!(results.iter().all(|result| result.is_ok()));
+
+        let journal = journal.lock().await;
+        let stop_position = |entry: &str| journal.iter().position(|e| e == entry).unwrap();
+        assert!(stop_position("stop consumer") < stop_position("stop database"));
+        assert!(stop_position("stop standalone") < stop_position("stop database"));
+    }
+
+    #[tokio::test]
+    async fn stop_services_honors_shutdown_deadline() {
+        let mut builder = ServiceManager::builder()
+            .with_shutdown_order(ShutdownOrder::Concurrent)
+            .with_shutdown_deadline(Duration::from_millis(100));
+        for id in ["first", "second", "third"] {
+            builder = builder
+                .with_service(Arc::new(Mutex::new(HangingService::new(id))))
+                .await;
+        }
+        let service_manager = builder.build().await.unwrap();
+
+        service_manager.start_services().await;
+        let results = timeout(Duration::from_secs(1), service_manager.stop_services())
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert!(
+            results
+                .iter()
+                .all(|result| matches!(result, Err(ShutdownError::DeadlineExceeded(_))))
+        );
+    }
+
+    #[tokio::test]
+    async fn hung_services_stop_concurrently() {
+        let mut builder = ServiceManager::builder()
+            .with_shutdown_order(ShutdownOrder::Concurrent)
+            .with_default_shutdown_timeout(Duration::from_millis(500))
+            .with_shutdown_deadline(Duration::from_secs(10));
+        for id in ["first", "second", "third", "fourth"] {
+            builder = builder
+                .with_service(Arc::new(Mutex::new(HangingService::new(id))))
+                .await;
+        }
+        let service_manager = builder.build().await.unwrap();
+
+        service_manager.start_services().await;
+        let started = Instant::now();
+        let results = service_manager.stop_services().await;
+
+        // One after the other, they would take four timeouts
+        assert!(started.elapsed() < Duration::from_millis(1500));
+        assert_eq!(results.len(), 4);
+        assert!(
+            results
+                .iter()
+                .all(|result| matches!(result, Err(ShutdownError::Killed(_))))
+        );
+    }
+
+    #[tokio::test]
+    async fn stop_timeout_kills_service() {
+        let service = Arc::new(Mutex::new(HangingService::new("hanging")));
+        let service_manager = ServiceManager::builder()
+            .with_default_shutdown_timeout(Duration::from_millis(50))
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        let (_subscription, mut receiver) = service_manager
+            .on_status_change
+            .event
+            .subscribe_channel("test", 8, true, true)
+            .await;
+        let result = service_manager
+            .stop_service_by_id(&service_id("hanging"))
+            .await;
+        assert!(matches!(result, Err(ShutdownError::Killed(_))));
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Killed
+        );
+
+        // Killed is terminal
+        assert!(
+            service_manager
+                .restart_service(&service_id("hanging"))
+                .await
+                .is_err()
+        );
+
+        // And the service is no longer wired into the manager
+        while receiver.try_recv().is_ok() {}
+        service
+            .lock()
+            .await
+            .info()
+            .set_status(Status::Stopped)
+            .await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn services_drain_before_stopping() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_default_drain_timeout(Duration::from_millis(100))
+            .with_service(Arc::new(Mutex::new(DrainingService::new(
+                "queue",
+                Duration::from_millis(10),
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(Arc::new(Mutex::new(DrainingService::new(
+                "stuck",
+                Duration::from_secs(60),
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        let results = timeout(Duration::from_secs(1), service_manager.stop_services())
+            .await
+            .unwrap();
+        assert!(results.iter().all(Result::is_ok));
+
+        // The stuck service is stopped anyway once its drain times out
+        assert_eq!(
+            *journal.lock().await,
+            vec![
+                "draining stuck",
+                "stop stuck",
+                "draining queue",
+                "drained queue",
+                "stop queue"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stop_surfaces_background_task_outcome() {
+        let service = Arc::new(Mutex::new(UncleanTaskService::new("unclean")));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+        let id = service_id("unclean");
+
+        service_manager.start_services().await;
+        let result = service_manager.stop_service_by_id(&id).await;
+        assert!(matches!(
+            result,
+            Err(ShutdownError::BackgroundTaskFailed(_, _))
+        ));
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::FailedToStop(String::new())
+        );
+        assert!(matches!(
+            service_manager.task_statuses(&id).await["main"],
+            TaskStatus::Failed(_)
+        ));
+
+        // The stopped task was cleaned up, so the service can be started again
+        service_manager.restart_service(&id).await.unwrap();
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Ready
+        );
+    }
+
+    #[tokio::test]
+    async fn stopping_cancels_background_task() {
+        let journal = journal();
+        let service = Arc::new(Mutex::new(CancellableService::new(
+            "worker",
+            Arc::clone(&journal),
+        )));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        service_manager.stop_services().await;
+
+        assert_eq!(*journal.lock().await, vec!["cancelled worker"]);
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Stopped
+        );
+    }
+
+    #[tokio::test]
+    async fn startup_is_retried_until_it_succeeds() {
+        let service = Arc::new(Mutex::new(FlakyService::new("flaky", 2, fast_backoff())));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let results = service_manager.start_services().await;
+        assert!(results.iter().all(Result::is_ok));
+
+        let service_lock = service.lock().await;
+        assert_eq!(service_lock.info().status.get().await, Status::Ready);
+
+        let failures = service_lock
+            .info()
+            .history()
+            .await
+            .into_iter()
+            .filter(|status_change| matches!(status_change.new, Status::FailedToStart(_)))
+            .count();
+        assert_eq!(failures, 2);
+    }
+
+    #[tokio::test]
+    async fn startup_fails_once_retries_are_exhausted() {
+        let service = Arc::new(Mutex::new(FlakyService::new("flaky", 5, fast_backoff())));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let results = service_manager.start_services().await;
+        assert!(matches!(
+            results[0],
+            Err(StartupError::FailedToStartService(_))
+        ));
+
+        // The first attempt plus three retries
+        let service_lock = service.lock().await;
+        let attempts = service_lock
+            .info()
+            .history()
+            .await
+            .into_iter()
+            .filter(|status_change| status_change.new == Status::Starting)
+            .count();
+        assert_eq!(attempts, 4);
+        assert!(service_lock.info().status.get().await.is_failed());
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_all_services() {
+        let journal = journal();
+        let service = Arc::new(Mutex::new(CancellableService::new(
+            "worker",
+            Arc::clone(&journal),
+        )));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        let results = service_manager.shutdown().await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(*journal.lock().await, vec!["cancelled worker"]);
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Stopped
+        );
+    }
+
+    #[tokio::test]
+    async fn dropping_manager_aborts_background_tasks() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(CancellableService::new(
+                "worker",
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        // The service and its running task each hold a clone of the journal
+        assert_eq!(Arc::strong_count(&journal), 3);
+
+        drop(service_manager);
+        sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(Arc::strong_count(&journal), 1);
+    }
+
+    #[tokio::test]
+    async fn failing_task_stops_other_tasks() {
+        let service = Arc::new(Mutex::new(MultiTaskService::new("multi")));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(50)).await;
+
+        let status = service.lock().await.info().status.get().await;
+        assert_eq!(status, Status::RuntimeError(String::new()));
+
+        let task_statuses = service_manager.task_statuses(&service_id("multi")).await;
+        assert_eq!(task_statuses["worker"], TaskStatus::Stopped);
+        assert!(matches!(task_statuses["crasher"], TaskStatus::Failed(_)));
+
+        let report = service_manager.status_report().await.to_string();
+        assert!(report.contains("Task crasher: Failed: ended with error: crashed"));
+        assert!(report.contains("Task worker: Stopped"));
+    }
+
+    #[tokio::test]
+    async fn panics_are_isolated() {
+        let starting = Arc::new(Mutex::new(PanickingService::new("starting", "start")));
+        let stopping = Arc::new(Mutex::new(PanickingService::new("stopping", "stop")));
+        let tasking = Arc::new(Mutex::new(PanickingService::new("tasking", "task")));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&starting))
+            .await
+            .with_service(Arc::clone(&stopping))
+            .await
+            .with_service(Arc::clone(&tasking))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(50)).await;
+        service_manager.stop_services().await;
+
+        let status_of = |service: Arc<Mutex<PanickingService>>| async move {
+            service.lock().await.info().status.get().await.to_string()
+        };
+        assert_eq!(status_of(starting).await, "Failed to start: panicked: boom");
+        assert_eq!(status_of(stopping).await, "Failed to stop: panicked: boom");
+        assert_eq!(
+            status_of(tasking).await,
+            "Runtime error: Background task main ended with error: panicked: boom"
+        );
+    }
+
+    #[tokio::test]
+    async fn missed_heartbeat_marks_service_as_failed() {
+        let beating = Arc::new(AtomicBool::new(true));
+        let info = ServiceInfo::new(service_id("beating"), "Beating", Priority::Optional)
+            .with_heartbeat(Duration::from_millis(50));
+        let service = Arc::new(Mutex::new(HeartbeatService::new(
+            info,
+            Arc::clone(&beating),
+        )));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(150)).await;
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Ready
+        );
+
+        beating.store(false, Ordering::SeqCst);
+        sleep(Duration::from_millis(150)).await;
+        assert_eq!(
+            service.lock().await.info().status.get().await.to_string(),
+            "Runtime error: heartbeat missed"
+        );
+    }
+
+    #[tokio::test]
+    async fn rest_for_one_restarts_later_group_members() {
+        let journal = journal();
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Optional)
+            .with_restart_policy(RestartPolicy::OnFailure(fast_backoff()));
+        let crashing = Arc::new(Mutex::new(CrashingService::new(
+            info,
+            1,
+            Arc::new(AtomicU32::new(0)),
+        )));
+        let group = SupervisionGroup::new("pipeline", SupervisionStrategy::RestForOne)
+            .with_member(service_id("first"))
+            .with_member(service_id("crashing"))
+            .with_member(service_id("last"));
+
+        let service_manager = ServiceManager::builder()
+            .with_supervision_group(group)
+            .with_service(test_service("first", Priority::Optional, &journal))
+            .await
+            .with_service(test_service("last", Priority::Optional, &journal))
+            .await
+            .with_service(Arc::clone(&crashing))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(
+            crashing.lock().await.info().status.get().await,
+            Status::Ready
+        );
+        assert_eq!(
+            *journal.lock().await,
+            vec!["start first", "start last", "stop last", "start last"]
+        );
+    }
+
+    #[tokio::test]
+    async fn services_receive_context() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_bot_name("Lum")
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(Arc::new(Mutex::new(ContextProbeService::new(
+                "probe",
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+
+        assert_eq!(
+            *journal.lock().await,
+            vec!["start database", "bot Lum, found test service: true"]
+        );
+    }
+
+    #[tokio::test]
+    async fn service_context_does_not_keep_manager_alive() {
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(ContextProbeService::new(
+                "probe",
+                journal(),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+
+        let weak = Arc::downgrade(&service_manager);
+        drop(service_manager);
+
+        // The status watcher briefly holds the manager while refreshing
+        sleep(Duration::from_millis(50)).await;
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[tokio::test]
+    async fn services_get_their_config_section() {
+        let journal = journal();
+        let mut config = FileConfig::default();
+        config
+            .services
+            .insert("poller".to_string(), serde_json::json!({ "interval": 30 }));
+
+        let service_manager = ServiceManager::builder()
+            .with_config(Arc::new(config))
+            .with_service(Arc::new(Mutex::new(ConfiguredService::new(
+                "poller",
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let results = service_manager.start_services().await;
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(*journal.lock().await, vec!["polling every 30s"]);
+    }
+
+    #[tokio::test]
+    async fn invalid_config_fails_startup() {
+        let journal = journal();
+        let mut config = FileConfig::default();
+        config.services.insert(
+            "poller".to_string(),
+            serde_json::json!({ "interval": "often" }),
+        );
+
+        let service = Arc::new(Mutex::new(ConfiguredService::new(
+            "poller",
+            Arc::clone(&journal),
+        )));
+        let service_manager = ServiceManager::builder()
+            .with_config(Arc::new(config))
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let result = service_manager.start_service(service.clone()).await;
+        assert!(matches!(result, Err(StartupError::InvalidConfig(_, _))));
+        assert!(
+            service
+                .lock()
+                .await
+                .info()
+                .status
+                .get()
+                .await
+                .to_string()
+                .starts_with("Failed to start: Invalid config:")
+        );
+        assert!(journal.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn plan_orders_services_without_starting_them() {
+        let journal = journal();
+        let info = |id: &str| ServiceInfo::new(service_id(id), id, Priority::Optional);
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                info("consumer").with_dependency(service_id("database")),
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                info("lazy").with_startup_mode(StartupMode::Lazy),
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let plan = service_manager.plan().await;
+
+        assert!(plan.is_valid());
+        assert_eq!(
+            plan.order,
+            vec![service_id("database"), service_id("consumer")]
+        );
+        assert!(journal.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn plan_reports_errors() {
+        let journal = journal();
+        let mut config = FileConfig::default();
+        config.services.insert(
+            "poller".to_string(),
+            serde_json::json!({ "interval": "often" }),
+        );
+
+        let info = |id: &str| ServiceInfo::new(service_id(id), id, Priority::Optional);
+        let service_manager = ServiceManager::builder()
+            .with_config(Arc::new(config))
+            .with_service(Arc::new(Mutex::new(ConfiguredService::new(
+                "poller",
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                info("orphan").with_dependency(service_id("missing")),
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                info("chicken").with_dependency(service_id("egg")),
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                info("egg").with_dependency(service_id("chicken")),
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let plan = service_manager.plan().await;
+
+        assert!(!plan.is_valid());
+        assert_eq!(plan.order.len(), 4);
+        assert!(
+            plan.errors
+                .contains(&PlanError::CircularDependencies(vec![service_id(
+                    "chicken"
+                )]))
+        );
+        assert!(plan.errors.contains(&PlanError::MissingDependency(
+            service_id("orphan"),
+            service_id("missing")
+        )));
+        assert!(plan.errors.iter().any(|error| matches!(
+            error,
+            PlanError::InvalidConfig(id, _) if *id == service_id("poller")
+        )));
+        assert!(journal.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn lazy_service_starts_on_first_use() {
+        let journal = journal();
+        let info = ServiceInfo::new(service_id("lazy"), "lazy", Priority::Optional)
+            .with_startup_mode(StartupMode::Lazy);
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                info,
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let results = service_manager.start_services().await;
+        assert!(results.is_empty());
+        assert!(journal.lock().await.is_empty());
+
+        let service = service_manager.get_service::<TestService>().await.unwrap();
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Ready
+        );
+
+        service_manager
+            .ensure_started(&service_id("lazy"))
+            .await
+            .unwrap();
+        assert_eq!(*journal.lock().await, vec!["start lazy"]);
+    }
+
+    #[tokio::test]
+    async fn concurrent_ensure_started_starts_once() {
+        let journal = journal();
+        let info = ServiceInfo::new(service_id("lazy"), "lazy", Priority::Optional)
+            .with_startup_mode(StartupMode::Lazy);
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                info,
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let service_id = service_id("lazy");
+        let starts = (0..8).map(|_| {
+            let service_manager = Arc::clone(&service_manager);
+            let service_id = service_id.clone();
+            tokio::spawn(async move { service_manager.ensure_started(&service_id).await })
+        });
+
+        for start in starts.collect::<Vec<_>>() {
+            assert!(start.await.unwrap().is_ok());
+        }
+        assert_eq!(*journal.lock().await, vec!["start lazy"]);
+    }
+
+    #[tokio::test]
+    async fn lock_diagnostics_keep_waiting_for_held_lock() {
+        let journal = journal();
+        let service = test_service("database", Priority::Essential, &journal);
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .with_lock_diagnostics(Duration::from_millis(10))
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+
+        // Outlasts the threshold, which only logs who held the lock last
+        let service_lock = service.lock().await;
+        let stop = tokio::spawn({
+            let service_manager = Arc::clone(&service_manager);
+            async move {
+                service_manager
+                    .stop_service_by_id(&service_id("database"))
+                    .await
+            }
+        });
+        sleep(Duration::from_millis(50)).await;
+        assert!(!stop.is_finished());
+        drop(service_lock);
+
+        assert!(stop.await.unwrap().is_ok());
+        assert_eq!(
+            *journal.lock().await,
+            vec!["start database", "stop database"]
+        );
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume_service() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("poller", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        let id = service_id("poller");
+
+        service_manager.pause_service(&id).await.unwrap();
+        let service = service_manager.get_service_by_id(&id).await.unwrap();
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Paused
+        );
+        assert_eq!(
+            service_manager.overall_status().await,
+            OverallStatus::Degraded
+        );
+        assert!(matches!(
+            service_manager.pause_service(&id).await,
+            Err(PauseError::ServiceNotRunning(_, Status::Paused))
+        ));
+
+        service_manager.resume_service(&id).await.unwrap();
+        assert!(service.lock().await.info().status.get().await.is_running());
+        assert_eq!(
+            service_manager.overall_status().await,
+            OverallStatus::Healthy
+        );
+        assert!(matches!(
+            service_manager.resume_service(&id).await,
+            Err(ResumeError::ServiceNotPaused(_, _))
+        ));
+
+        service_manager.pause_service(&id).await.unwrap();
+        let results = service_manager.stop_services().await;
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Stopped
+        );
+    }
+
+    #[tokio::test]
+    async fn restart_service_on_failure() {
+        let starts = Arc::new(AtomicU32::new(0));
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Essential)
+            .with_restart_policy(RestartPolicy::OnFailure(fast_backoff()));
+        let service = Arc::new(Mutex::new(CrashingService::new(
+            info,
+            1,
+            Arc::clone(&starts),
+        )));
+
+        let service_manager = ServiceManager::builder()
+            .with_service(service.clone())
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+
+        sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Ready
+        );
+    }
+
+    #[tokio::test]
+    async fn never_restart_by_default() {
+        let starts = Arc::new(AtomicU32::new(0));
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Essential);
+        let service = Arc::new(Mutex::new(CrashingService::new(
+            info,
+            1,
+            Arc::clone(&starts),
+        )));
+
+        let service_manager = ServiceManager::builder()
+            .with_service(service.clone())
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+
+        sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::RuntimeError(String::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn detect_crash_loop() {
+        let starts = Arc::new(AtomicU32::new(0));
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Essential)
+            .with_restart_policy(RestartPolicy::Always(fast_backoff()));
+        let service = Arc::new(Mutex::new(CrashingService::new(
+            info,
+            u32::MAX,
+            Arc::clone(&starts),
+        )));
+
+        let service_manager = ServiceManager::builder()
+            .with_crash_loop_detection(CrashLoopDetection::new(2, Duration::from_secs(10)))
+            .with_service(service.clone())
+            .await
+            .build()
+            .await
+            .unwrap();
+        let (_subscription, mut receiver) = service_manager
+            .on_crash_loop
+            .subscribe_channel("test", 1, false, false)
+            .await;
+        service_manager.start_services().await;
+
+        let service_id = timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(*service_id, "crashing");
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::CrashLooping
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_service_ids_fail_build() {
+        let journal = journal();
+        let result = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(test_service("database", Priority::Optional, &journal))
+            .await
+            .build()
+            .await;
+
+        match result {
+            Err(BuildError::DuplicateServices(duplicates)) => {
+                assert_eq!(duplicates, vec![service_id("database")])
+            }
+            Ok(_) => panic!("Expected duplicate service IDs to fail the build"),
+        }
+    }
+
+    #[tokio::test]
+    async fn with_services_registers_all_services() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_services([
+                test_service("database", Priority::Essential, &journal),
+                test_service("cache", Priority::Optional, &journal),
+            ])
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        assert!(
+            service_manager
+                .manages_service(&service_id("database"))
+                .await
+        );
+        assert!(service_manager.manages_service(&service_id("cache")).await);
+
+        let result = ServiceManager::builder()
+            .with_services(vec![
+                test_service("cache", Priority::Optional, &journal),
+                test_service("cache", Priority::Optional, &journal),
+            ])
+            .await
+            .build()
+            .await;
+        assert!(matches!(result, Err(BuildError::DuplicateServices(_))));
+    }
+
+    #[tokio::test]
+    async fn start_and_stop_services_by_tag() {
+        let journal = journal();
+        let tagged = |id: &str, tag: &str| {
+            let info = ServiceInfo::new(service_id(id), id, Priority::Optional).with_tag(tag);
+            Arc::new(Mutex::new(TestService::with_info(
+                info,
+                Arc::clone(&journal),
+            )))
+        };
+        let service_manager = ServiceManager::builder()
+            .with_service(tagged("gateway", "network"))
+            .await
+            .with_service(tagged("database", "storage"))
+            .await
+            .with_service(tagged("http", "network"))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(service_manager.services_with_tag("network").await.len(), 2);
+        assert!(
+            service_manager
+                .services_with_tag("missing")
+                .await
+                .is_empty()
+        );
+
+        let results = service_manager.start_services_with_tag("network").await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(*journal.lock().await, vec!["start gateway", "start http"]);
+
+        let results = service_manager.stop_services_with_tag("network").await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(
+            *journal.lock().await,
+            vec!["start gateway", "start http", "stop http", "stop gateway"]
+        );
+    }
+
+    #[tokio::test]
+    async fn query_services_by_status() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(test_service("cache", Priority::Optional, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            service_manager
+                .services_in_status(|status| *status == Status::Stopped)
+                .await
+                .len(),
+            2
+        );
+
+        service_manager
+            .start_service_by_id(&service_id("database"))
+            .await
+            .unwrap();
+
+        let running = service_manager.services_in_status(Status::is_running).await;
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].lock().await.info().id, service_id("database"));
+        assert!(
+            service_manager
+                .services_in_status(Status::is_failed)
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn startup_priority_orders_within_dependencies() {
+        let journal = journal();
+        let prioritized = |info: ServiceInfo, startup_priority: i32| {
+            Arc::new(Mutex::new(TestService::with_info(
+                info.with_startup_priority(startup_priority),
+                Arc::clone(&journal),
+            )))
+        };
+        let info = |id: &str| ServiceInfo::new(service_id(id), id, Priority::Optional);
+        let service_manager = ServiceManager::builder()
+            .with_service(prioritized(info("feature"), 10))
+            .await
+            .with_service(prioritized(
+                info("consumer").with_dependency(service_id("database")),
+                -20,
+            ))
+            .await
+            .with_service(prioritized(info("database"), 0))
+            .await
+            .with_service(prioritized(info("infra"), -10))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+
+        assert_eq!(
+            *journal.lock().await,
+            vec![
+                "start infra",
+                "start database",
+                "start consumer",
+                "start feature"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn display_does_not_block_the_runtime() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder().build().await.unwrap();
+        assert_eq!(service_manager.to_string(), "Services: None");
+
+        service_manager
+            .add_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .unwrap();
+
+        let description = service_manager.describe().await;
+        assert_eq!(description, "Services: database (database): Stopped");
+        assert_eq!(service_manager.to_string(), description);
+    }
+
+    #[tokio::test]
+    async fn service_actor_lifecycle() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder().build().await.unwrap();
+        let actor = ServiceActor::spawn(TestService::new(
+            "actor",
+            Priority::Optional,
+            Arc::clone(&journal),
+        ));
+        assert_eq!(actor.status(), Status::Stopped);
+
+        service_manager.start_actor(&actor).await.unwrap();
+        assert_eq!(actor.status(), Status::Ready);
+
+        actor.pause().await.unwrap();
+        assert_eq!(actor.status(), Status::Paused);
+        assert!(matches!(
+            actor.pause().await,
+            Err(ActorError::Pause(PauseError::ServiceNotRunning(_, _)))
+        ));
+        actor.resume().await.unwrap();
+        assert_eq!(actor.status(), Status::Ready);
+
+        actor.stop().await.unwrap();
+        assert_eq!(actor.status(), Status::Stopped);
+        assert!(matches!(
+            actor.stop().await,
+            Err(ActorError::Shutdown(ShutdownError::ServiceNotStarted(_)))
+        ));
+        assert_eq!(*journal.lock().await, vec!["start actor", "stop actor"]);
+    }
+
+    #[tokio::test]
+    async fn service_actor_fails_on_task_failure() {
+        let service_manager = ServiceManager::builder().build().await.unwrap();
+        let actor = ServiceActor::spawn(MultiTaskService::new("multi"));
+
+        service_manager.start_actor(&actor).await.unwrap();
+        let mut status = actor.watch_status();
+        timeout(
+            Duration::from_secs(1),
+            status.wait_for(|status| status.is_failed()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(actor.status(), Status::RuntimeError(String::new()));
+
+        // Failed actors can be started again
+        service_manager.start_actor(&actor).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn restart_keeps_snapshotted_state() {
+        let service = Arc::new(Mutex::new(StatefulService::new("sessions")));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&service))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        service.lock().await.sessions.push("alice".to_string());
+
+        service_manager
+            .restart_service(&service_id("sessions"))
+            .await
+            .unwrap();
+        assert_eq!(service.lock().await.sessions, vec!["alice"]);
+
+        service_manager.stop_services().await;
+        assert!(service.lock().await.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn replace_service_migrates_state() {
+        let old_service = Arc::new(Mutex::new(StatefulService::new("sessions")));
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&old_service))
+            .await
+            .build()
+            .await
+            .unwrap();
+        let id = service_id("sessions");
+
+        service_manager.start_services().await;
+        old_service.lock().await.sessions.push("alice".to_string());
+
+        let result = service_manager
+            .replace_service(&id, ServiceHandle::new(StatefulService::new("other")))
+            .await;
+        assert!(matches!(result, Err(ReplaceError::ServiceIdMismatch(_, _))));
+
+        let new_service = Arc::new(Mutex::new(StatefulService::new("sessions")));
+        service_manager
+            .replace_service(&id, Arc::clone(&new_service))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            old_service.lock().await.info().status.get().await,
+            Status::Stopped
+        );
+        assert_eq!(
+            new_service.lock().await.info().status.get().await,
+            Status::Ready
+        );
+        assert_eq!(new_service.lock().await.sessions, vec!["alice"]);
+
+        let looked_up = service_manager
+            .get_service::<StatefulService>()
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&looked_up, &new_service));
+    }
+
+    #[tokio::test]
+    async fn services_with_capability() {
+        let journal = journal();
+        let first = Arc::new(Mutex::new(StatefulService::new("first")));
+        let second = Arc::new(Mutex::new(StatefulService::new("second")));
+        first.lock().await.sessions.push("alice".to_string());
+        second.lock().await.sessions.push("bob".to_string());
+
+        let service_manager = ServiceManager::builder()
+            .with_service(
+                ServiceHandle::from(Arc::clone(&first))
+                    .with_capability::<dyn SessionHolder>(first.clone()),
+            )
+            .await
+            .with_service(test_service("plain", Priority::Optional, &journal))
+            .await
+            .with_service(
+                ServiceHandle::from(Arc::clone(&second))
+                    .with_capability::<dyn SessionHolder>(second.clone()),
+            )
+            .await
+            // Not the service the handle was created from, so it isn't registered
+            .with_service(
+                ServiceHandle::new(StatefulService::new("third"))
+                    .with_capability::<dyn SessionHolder>(first.clone()),
+            )
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let holders = service_manager
+            .services_with_capability::<dyn SessionHolder>()
+            .await;
+        let mut sessions = Vec::new();
+        for holder in holders.iter() {
+            sessions.extend(holder.lock().await.sessions().to_vec());
+        }
+
+        assert_eq!(sessions, vec!["alice", "bob"]);
+    }
+
+    #[tokio::test]
+    async fn add_service_at_runtime() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder().build().await.unwrap();
+
+        service_manager
+            .add_and_start_service(test_service("plugin", Priority::Optional, &journal))
+            .await
+            .unwrap();
+        assert!(service_manager.manages_service(&service_id("plugin")).await);
+        assert_eq!(*journal.lock().await, vec!["start plugin"]);
+
+        let result = service_manager
+            .add_service(test_service("plugin", Priority::Optional, &journal))
+            .await;
+        assert!(matches!(
+            result,
+            Err(RegistrationError::ServiceAlreadyManaged(_))
+        ));
+        assert_eq!(service_manager.services().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn remove_service_at_runtime() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("plugin", Priority::Optional, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+
+        let service = service_manager
+            .remove_service(&service_id("plugin"))
+            .await
+            .unwrap();
+        assert!(!service_manager.manages_service(&service_id("plugin")).await);
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Stopped
+        );
+        assert_eq!(*journal.lock().await, vec!["start plugin", "stop plugin"]);
+
+        let result = service_manager.remove_service(&service_id("plugin")).await;
+        assert!(matches!(result, Err(RemovalError::ServiceNotManaged(_))));
+    }
+
+    #[tokio::test]
+    async fn restart_service() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+
+        service_manager
+            .restart_service(&service_id("database"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *journal.lock().await,
+            vec!["start database", "stop database", "start database"]
+        );
+        assert!(
+            service_manager
+                .restart_service(&service_id("unknown"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn restart_service_refreshes_overall_status() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+        service_manager
+            .stop_service_by_id(&service_id("database"))
+            .await
+            .unwrap();
+        assert_eq!(
+            service_manager.events().overall_status.get().await,
+            OverallStatus::Unhealthy
+        );
+
+        service_manager
+            .restart_service(&service_id("database"))
+            .await
+            .unwrap();
+        assert_eq!(
+            service_manager.events().overall_status.get().await,
+            OverallStatus::Healthy
+        );
+        assert_eq!(
+            service_manager.to_string(),
+            service_manager.describe().await
+        );
+    }
+
+    #[tokio::test]
+    async fn start_and_stop_service_by_id() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager
+            .start_service_by_id(&service_id("database"))
+            .await
+            .unwrap();
+        service_manager
+            .stop_service_by_id(&service_id("database"))
+            .await
+            .unwrap();
+        assert_eq!(
+            *journal.lock().await,
+            vec!["start database", "stop database"]
+        );
+
+        assert!(
+            service_manager
+                .start_service_by_id(&service_id("unknown"))
+                .await
+                .is_err()
+        );
+        assert!(
+            service_manager
+                .stop_service_by_id(&service_id("unknown"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn get_service_by_type() {
+        let journal = journal();
+        let database = test_service("database", Priority::Essential, &journal);
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&database))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let service = service_manager.get_service::<TestService>().await.unwrap();
+        assert!(Arc::ptr_eq(&service, &database));
+        assert!(
+            service_manager
+                .get_service::<CrashingService>()
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn get_service_by_id() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let service = service_manager
+            .get_service_by_id(&service_id("database"))
+            .await
+            .unwrap();
+        assert_eq!(service.lock().await.info().id, "database");
+        assert!(
+            service_manager
+                .get_service_by_id(&service_id("unknown"))
+                .await
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn service_id_validation() {
+        assert_eq!(
+            ServiceId::new("lum_builtin.discord-1").unwrap(),
+            "lum_builtin.discord-1"
+        );
+        assert!(matches!(
+            ServiceId::new(""),
+            Err(InvalidServiceIdError::Empty)
+        ));
+        assert!(matches!(
+            ServiceId::new("my service"),
+            Err(InvalidServiceIdError::InvalidCharacter(_, ' '))
+        ));
+    }
+
+    #[tokio::test]
+    async fn status_change_event() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+        let (_subscription, mut receiver) = service_manager
+            .on_status_change
+            .event
+            .subscribe_channel("test", 8, true, true)
+            .await;
+
+        service_manager.start_services().await;
+
+        let starting = receiver.recv().await.unwrap();
+        assert_eq!(starting.service_id, "database");
+        assert_eq!(starting.old, Status::Stopped);
+        assert_eq!(starting.new, Status::Starting);
+
+        let started = receiver.recv().await.unwrap();
+        assert_eq!(started.old, Status::Starting);
+        assert_eq!(started.new, Status::Started);
+        assert!(started.timestamp >= starting.timestamp);
+    }
+
+    #[tokio::test]
+    async fn lifecycle_events() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+        let events = service_manager.events();
+        assert_eq!(events.overall_status.get().await, OverallStatus::Unhealthy);
+
+        let (_overall_status_subscription, mut overall_status) = events
+            .overall_status
+            .as_ref()
+            .subscribe_channel("test", 8, true, true)
+            .await;
+        let (_all_started_subscription, mut all_started) = events
+            .on_all_started
+            .subscribe_channel("test", 1, true, true)
+            .await;
+        let (_added_subscription, mut added) = events
+            .on_service_added
+            .subscribe_channel("test", 1, true, true)
+            .await;
+        let (_shutdown_subscription, mut shutdown) = events
+            .on_shutdown
+            .subscribe_channel("test", 1, true, true)
+            .await;
+
+        service_manager.start_services().await;
+        assert!(all_started.recv().await.is_some());
+        let status = timeout(Duration::from_secs(1), overall_status.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*status, OverallStatus::Healthy);
+
+        service_manager
+            .add_service(test_service("plugin", Priority::Optional, &journal))
+            .await
+            .unwrap();
+        assert_eq!(*added.recv().await.unwrap(), "plugin");
+
+        service_manager.stop_services().await;
+        assert!(shutdown.recv().await.is_some());
+        let status = timeout(Duration::from_secs(1), overall_status.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*status, OverallStatus::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn timeouts_from_config() {
+        let journal = journal();
+        let mut config = FileConfig::default();
+        config.default_timeouts.startup_seconds = Some(30);
+        config.service_timeouts.insert(
+            "database".to_string(),
+            TimeoutConfig {
+                startup_seconds: None,
+                shutdown_seconds: Some(5),
+            },
+        );
+
+        let service_manager = ServiceManager::builder()
+            .with_timeouts_from_config(&config)
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let service = service_manager
+            .get_service_by_id(&service_id("database"))
+            .await
+            .unwrap();
+        let service = service.lock().await;
+        assert_eq!(
+            service_manager.startup_timeout(&*service),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            service_manager.shutdown_timeout(&*service),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn lifecycle_hooks() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(HookedService::new(
+                "hooked",
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        service_manager.stop_services().await;
+
+        assert_eq!(
+            *journal.lock().await,
+            vec![
+                "pre_start",
+                "start",
+                "post_start",
+                "pre_stop",
+                "stop",
+                "post_stop"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_health_checks_mark_service_as_failed() {
+        let healthy = Arc::new(AtomicBool::new(true));
+        let info = ServiceInfo::new(service_id("probed"), "Probed", Priority::Essential)
+            .with_health_check(HealthCheck::new(Duration::from_millis(10), 3));
+        let service = Arc::new(Mutex::new(ProbedService::new(info, Arc::clone(&healthy))));
+
+        let service_manager = ServiceManager::builder()
+            .with_service(service.clone())
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Ready
+        );
+
+        healthy.store(false, Ordering::SeqCst);
+        sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            service.lock().await.info().status.get().await,
+            Status::RuntimeError(_)
+        ));
+        assert_eq!(
+            service_manager.overall_status().await,
+            OverallStatus::Unhealthy
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_until_ready() {
+        let info = ServiceInfo::new(service_id("cache"), "Cache", Priority::Essential)
+            .with_readiness(Readiness::Manual);
+        let service = Arc::new(Mutex::new(ProbedService::new(
+            info,
+            Arc::new(AtomicBool::new(true)),
+        )));
+
+        let service_manager = ServiceManager::builder()
+            .with_service(service.clone())
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let waiter = {
+            let service_manager = Arc::clone(&service_manager);
+            tokio::spawn(
+                async move { service_manager.wait_until_ready(&service_id("cache")).await },
+            )
+        };
+
+        service_manager.start_services().await;
+        assert_eq!(
+            service.lock().await.info().status.get().await,
+            Status::Started
+        );
+        assert!(!waiter.is_finished());
+
+        service_manager
+            .mark_ready(&service_id("cache"))
+            .await
+            .unwrap();
+        timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(service.lock().await.is_available().await);
+    }
+
+    #[tokio::test]
+    async fn failed_optional_service_degrades_overall_status() {
+        let journal = journal();
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Optional);
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(Arc::new(Mutex::new(CrashingService::new(
+                info,
+                1,
+                Arc::new(AtomicU32::new(0)),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(
+            service_manager.overall_status().await,
+            OverallStatus::Degraded
+        );
+    }
+
+    #[tokio::test]
+    async fn essential_failure_requests_shutdown() {
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Essential);
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(CrashingService::new(
+                info,
+                1,
+                Arc::new(AtomicU32::new(0)),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let (_subscription, mut receiver) = service_manager
+            .events()
+            .on_shutdown_requested
+            .subscribe_channel("test", 1, true, true)
+            .await;
+
+        service_manager.start_services().await;
+
+        let failed_service_id = timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*failed_service_id, service_id("crashing"));
+    }
+
+    #[tokio::test]
+    async fn essential_stop_failures_escalate() {
+        let stubborn = ServiceInfo::new(service_id("stubborn"), "stubborn", Priority::Essential);
+        let hanging = ServiceInfo::new(service_id("hanging"), "hanging", Priority::Essential);
+        let service_manager = ServiceManager::builder()
+            .with_default_shutdown_timeout(Duration::from_millis(50))
+            .with_service(Arc::new(Mutex::new(PanickingService::with_info(
+                stubborn, "stop",
+            ))))
+            .await
+            .with_service(Arc::new(Mutex::new(HangingService::with_info(hanging))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let (_subscription, mut receiver) = service_manager
+            .events()
+            .on_shutdown_requested
+            .subscribe_channel("test", 4, true, true)
+            .await;
+        service_manager.start_services().await;
+
+        let result = service_manager
+            .stop_service_by_id(&service_id("stubborn"))
+            .await;
+        assert!(matches!(result, Err(ShutdownError::FailedToStopService(_))));
+        assert_eq!(*receiver.try_recv().unwrap(), service_id("stubborn"));
+
+        let result = service_manager
+            .stop_service_by_id(&service_id("hanging"))
+            .await;
+        assert!(matches!(result, Err(ShutdownError::Killed(_))));
+        assert_eq!(*receiver.try_recv().unwrap(), service_id("hanging"));
+
+        // A full shutdown doesn't request itself again
+        service_manager.start_services().await;
+        service_manager.stop_services().await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn bot_join_ends_on_escalated_failures_only() {
+        let stubborn = ServiceInfo::new(service_id("stubborn"), "stubborn", Priority::Essential);
+        let bot = Arc::new(
+            Bot::builder("join_test")
+                .with_service(Arc::new(Mutex::new(PanickingService::with_info(
+                    stubborn, "stop",
+                ))))
+                .await
+                .build()
+                .await
+                .unwrap(),
+        );
+        bot.service_manager.start_services().await;
+
+        let join = {
+            let bot = Arc::clone(&bot);
+            tokio::spawn(async move { bot.join().await })
+        };
+        sleep(Duration::from_millis(20)).await;
+        assert!(!join.is_finished());
+
+        let _ = bot
+            .service_manager
+            .stop_service_by_id(&service_id("stubborn"))
+            .await;
+        let reason = timeout(Duration::from_secs(1), join)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(reason, ExitReason::EssentialServiceFailed));
+    }
+
+    #[tokio::test]
+    async fn essential_failure_stops_dependents() {
+        let journal = journal();
+        let api_info = ServiceInfo::new(service_id("api"), "api", Priority::Optional)
+            .with_dependency(service_id("crashing"));
+        let cache_info = ServiceInfo::new(service_id("cache"), "cache", Priority::Optional)
+            .with_dependency(service_id("api"));
+        let crashing_info =
+            ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Essential);
+
+        let service_manager = ServiceManager::builder()
+            .with_escalation_policy(EscalationPolicy::StopDependents)
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                api_info,
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(Arc::new(Mutex::new(TestService::with_info(
+                cache_info,
+                Arc::clone(&journal),
+            ))))
+            .await
+            .with_service(test_service("unrelated", Priority::Optional, &journal))
+            .await
+            .with_service(Arc::new(Mutex::new(CrashingService::new(
+                crashing_info,
+                1,
+                Arc::new(AtomicU32::new(0)),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let (_subscription, mut receiver) = service_manager
+            .events()
+            .on_shutdown_requested
+            .subscribe_channel("test", 1, true, true)
+            .await;
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(50)).await;
+
+        let status_of = |id: &str| {
+            let service_manager = Arc::clone(&service_manager);
+            let id = service_id(id);
+            async move {
+                let service = service_manager.get_service_by_id(&id).await.unwrap();
+                service.lock().await.info().status.get().await
+            }
+        };
+        assert_eq!(status_of("api").await, Status::Stopped);
+        assert_eq!(status_of("cache").await, Status::Stopped);
+        assert_eq!(status_of("unrelated").await, Status::Ready);
+
+        let journal = journal.lock().await;
+        let stops = journal
+            .iter()
+            .filter(|entry| entry.starts_with("stop"))
+            .collect::<Vec<_>>();
+        assert_eq!(stops, ["stop cache", "stop api"]);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn status_report() {
+        let journal = journal();
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Optional);
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(Arc::new(Mutex::new(CrashingService::new(
+                info,
+                1,
+                Arc::new(AtomicU32::new(0)),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(50)).await;
+
+        let report = service_manager.status_report().await;
+        assert_eq!(report.overall_status, OverallStatus::Degraded);
+        assert_eq!(report.services.len(), 2);
+
+        let database = &report.services[0];
+        assert_eq!(database.id, service_id("database"));
+        assert_eq!(database.priority, Priority::Essential);
+        assert_eq!(database.status, Status::Ready);
+        assert_eq!(database.error, None);
+
+        let failed = report.failed_services().collect::<Vec<_>>();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "Crashing");
+        assert!(failed[0].error.is_some());
+        assert!(!failed[0].history.is_empty());
+
+        let text = report.to_string();
+        assert!(text.starts_with("Status overview\n"));
+        assert!(text.contains("Failed optional services:\n - Crashing: "));
+        assert!(text.contains("\nEssential services:\n - database: Ready"));
+    }
+
+    #[tokio::test]
+    async fn summary_counts_services_by_priority() {
+        let journal = journal();
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Optional);
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .with_service(test_service("cache", Priority::Optional, &journal))
+            .await
+            .with_service(Arc::new(Mutex::new(CrashingService::new(
+                info,
+                1,
+                Arc::new(AtomicU32::new(0)),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(50)).await;
+
+        let summary = service_manager.summary().await;
+        assert_eq!(summary.overall_status, OverallStatus::Degraded);
+        assert_eq!(summary.essential.started, 1);
+        assert_eq!(summary.optional.started, 1);
+        assert_eq!(summary.optional.failed, 1);
+        assert_eq!(summary.total(), 3);
+        assert_eq!(
+            summary.to_string(),
+            "Degraded: 2 started, 1 optional failed"
+        );
+    }
+
+    #[tokio::test]
+    async fn status_report_serializes_to_json() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+
+        let report = service_manager.status_report().await;
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["overall_status"], "Healthy");
+        assert_eq!(json["services"][0]["id"], "database");
+        assert_eq!(json["services"][0]["priority"], "Essential");
+        assert_eq!(json["services"][0]["status"]["status"], "Ready");
+        assert!(json["services"][0]["error"].is_null());
+        assert!(json["services"][0]["history"][0]["timestamp"].is_string());
+    }
+
+    #[tokio::test]
+    async fn wait_for_status() {
+        let journal = journal();
+        let database = test_service("database", Priority::Essential, &journal);
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::clone(&database))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let started = {
+            let service_manager = Arc::clone(&service_manager);
+            tokio::spawn(async move {
+                service_manager
+                    .wait_for_service_started(&service_id("database"))
+                    .await
+            })
+        };
+        let ready = database.lock().await.info().wait_for_status(Status::Ready);
+
+        service_manager.start_services().await;
+        timeout(Duration::from_secs(1), started)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        let status = timeout(Duration::from_secs(1), ready)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status, Status::Ready);
+    }
+
+    #[tokio::test]
+    async fn service_metrics() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(test_service("database", Priority::Essential, &journal))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        let metrics = &service_manager.metrics().await[0];
+        assert!(metrics.current_uptime.is_none());
+        assert!(metrics.last_started.is_none());
+
+        service_manager.start_services().await;
+        service_manager
+            .restart_service(&service_id("database"))
+            .await
+            .unwrap();
+
+        let metrics = &service_manager.metrics().await[0];
+        assert_eq!(metrics.service_id, "database");
+        assert!(metrics.current_uptime.is_some());
+        assert!(metrics.last_started.is_some());
+        assert_eq!(metrics.restarts, 1);
+        assert_eq!(metrics.failures, 0);
+        assert!(metrics.to_string().starts_with("up "));
+    }
+
+    #[tokio::test]
+    async fn metrics_count_spawned_tasks() {
+        let journal = journal();
+        let service_manager = ServiceManager::builder()
+            .with_service(Arc::new(Mutex::new(CancellableService::new(
+                "worker",
+                Arc::clone(&journal),
+            ))))
+            .await
+            .build()
+            .await
+            .unwrap();
+
+        service_manager.start_services().await;
+        service_manager
+            .restart_service(&service_id("worker"))
+            .await
+            .unwrap();
+
+        let metrics = &service_manager.metrics().await[0];
+        assert_eq!(metrics.tasks_spawned, 2);
+        assert_eq!(
+            metrics.task_cpu_time.is_some(),
+            cfg!(feature = "task-cpu-time")
+        );
+        assert!(metrics.to_string().contains("2 task(s) spawned"));
+    }
+
+    #[tokio::test]
+    async fn status_history() {
+        let info = ServiceInfo::new(service_id("crashing"), "Crashing", Priority::Essential)
+            .with_history_capacity(2);
+        let service = Arc::new(Mutex::new(CrashingService::new(
+            info,
+            1,
+            Arc::new(AtomicU32::new(0)),
+        )));
+
+        let service_manager = ServiceManager::builder()
+            .with_service(service.clone())
+            .await
+            .build()
+            .await
+            .unwrap();
+        service_manager.start_services().await;
+        sleep(Duration::from_millis(50)).await;
+
+        // Starting and Started were pushed out by the later transitions
+        let history = service.lock().await.info().history().await;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].new, Status::Ready);
+        assert!(matches!(history[1].new, Status::RuntimeError(_)));
+
+        let status_overview = service_manager.status_overview().await;
+        assert!(status_overview.contains("Ready -> Runtime error"));
+    }
+}